@@ -1,7 +1,7 @@
-use std::future::Future;
-
-use tokio_util::sync::CancellationToken;
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{
+    transport::{server::CancellationToken, Server},
+    Request, Response, Status,
+};
 
 use hello_world::greeter_server::{Greeter, GreeterServer};
 use hello_world::{HelloReply, HelloRequest};
@@ -24,49 +24,36 @@ impl Greeter for MyGreeter {
         request: Request<HelloRequest>,
     ) -> Result<Response<HelloReply>, Status> {
         let remote_addr = request.remote_addr();
-        let request_future = async move {
-            println!("Got a request from {:?}", request.remote_addr());
+        // Cancelled if the call ends early, such as when the client cancels the request
+        let token = request
+            .extensions()
+            .get::<CancellationToken>()
+            .cloned()
+            .unwrap();
 
-            // Take a long time to complete request for the client to cancel early
-            sleep(Duration::from_secs(10)).await;
+        // The handler future itself is simply dropped on cancellation, spawned work
+        // has to watch the token to stop early
+        let work = tokio::spawn(async move {
+            println!("Got a request from {:?}", remote_addr);
 
-            let reply = hello_world::HelloReply {
-                message: format!("Hello {}!", request.into_inner().name),
-            };
+            select! {
+                // Take a long time to complete request for the client to cancel early
+                _ = sleep(Duration::from_secs(10)) => {
+                    let reply = hello_world::HelloReply {
+                        message: format!("Hello {}!", request.into_inner().name),
+                    };
 
-            Ok(Response::new(reply))
-        };
-        let cancellation_future = async move {
-            println!("Request from {:?} cancelled by client", remote_addr);
-            // If this future is executed it means the request future was dropped,
-            // so it doesn't actually matter what is returned here
-            Err(Status::cancelled("Request cancelled by client"))
-        };
-        with_cancellation_handler(request_future, cancellation_future).await
-    }
-}
+                    Ok(Response::new(reply))
+                }
+                _ = token.cancelled() => {
+                    println!("Request from {:?} cancelled by client", remote_addr);
+                    Err(Status::cancelled("Request cancelled by client"))
+                }
+            }
+        });
 
-async fn with_cancellation_handler<FRequest, FCancellation>(
-    request_future: FRequest,
-    cancellation_future: FCancellation,
-) -> Result<Response<HelloReply>, Status>
-where
-    FRequest: Future<Output = Result<Response<HelloReply>, Status>> + Send + 'static,
-    FCancellation: Future<Output = Result<Response<HelloReply>, Status>> + Send + 'static,
-{
-    let token = CancellationToken::new();
-    // Will call token.cancel() when the future is dropped, such as when the client cancels the request
-    let _drop_guard = token.clone().drop_guard();
-    let select_task = tokio::spawn(async move {
-        // Can select on token cancellation on any cancellable future while handling the request,
-        // allowing for custom cleanup code or monitoring
-        select! {
-            res = request_future => res,
-            _ = token.cancelled() => cancellation_future.await,
-        }
-    });
-
-    select_task.await.unwrap()
+        work.await.unwrap()
+    }
 }
 
 #[tokio::main]
@@ -6,6 +6,7 @@ util::parametrized_tests! {
     client_enabled_server_enabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
 }
 
 #[allow(dead_code)]
@@ -34,6 +35,7 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
             let expected = match self.encoding {
                 CompressionEncoding::Gzip => "gzip",
                 CompressionEncoding::Zstd => "zstd",
+                CompressionEncoding::Deflate => "deflate",
                 _ => panic!("unexpected encoding {:?}", self.encoding),
             };
             assert_eq!(req.headers().get("grpc-encoding").unwrap(), expected);
@@ -86,6 +88,7 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Deflate => "deflate",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
@@ -0,0 +1,142 @@
+use super::*;
+use tonic::codec::{CompressionEncoding, CompressionLevel};
+
+util::parametrized_tests! {
+    server_sends_small_messages_uncompressed,
+    zstd: CompressionEncoding::Zstd,
+    gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+}
+
+#[allow(dead_code)]
+async fn server_sends_small_messages_uncompressed(encoding: CompressionEncoding) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .send_compressed(encoding)
+        .send_compressed_min_size(UNCOMPRESSED_MIN_BODY_SIZE * 2);
+
+    let response_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let response_bytes_counter = response_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(MapResponseBodyLayer::new(move |body| {
+                    util::CountBytesBody {
+                        inner: body,
+                        counter: response_bytes_counter.clone(),
+                    }
+                }))
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client =
+        test_client::TestClient::new(mock_io_channel(client).await).accept_compressed(encoding);
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(
+        res.metadata().get("grpc-encoding").unwrap(),
+        encoding.to_string().as_str()
+    );
+    assert_eq!(res.into_inner().data.len(), UNCOMPRESSED_MIN_BODY_SIZE);
+    assert!(response_bytes_counter.load(SeqCst) > UNCOMPRESSED_MIN_BODY_SIZE);
+}
+
+util::parametrized_tests! {
+    client_sends_small_messages_uncompressed,
+    zstd: CompressionEncoding::Zstd,
+    gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+}
+
+#[allow(dead_code)]
+async fn client_sends_small_messages_uncompressed(encoding: CompressionEncoding) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default()).accept_compressed(encoding);
+
+    let request_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let request_bytes_counter = request_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(measure_request_body_size_layer(request_bytes_counter))
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .send_compressed(encoding)
+        .send_compressed_min_size(UNCOMPRESSED_MIN_BODY_SIZE * 2);
+
+    client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap();
+    assert!(request_bytes_counter.load(SeqCst) > UNCOMPRESSED_MIN_BODY_SIZE);
+}
+
+util::parametrized_tests! {
+    client_and_server_compress_with_level,
+    zstd_fastest: (CompressionEncoding::Zstd, CompressionLevel::Fastest),
+    zstd_best: (CompressionEncoding::Zstd, CompressionLevel::Best),
+    gzip_fastest: (CompressionEncoding::Gzip, CompressionLevel::Fastest),
+    gzip_precise: (CompressionEncoding::Gzip, CompressionLevel::Precise(9)),
+    deflate_best: (CompressionEncoding::Deflate, CompressionLevel::Best),
+}
+
+#[allow(dead_code)]
+async fn client_and_server_compress_with_level(
+    (encoding, level): (CompressionEncoding, CompressionLevel),
+) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .accept_compressed(encoding)
+        .send_compressed_with_level(encoding, level);
+
+    let response_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let response_bytes_counter = response_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(MapResponseBodyLayer::new(move |body| {
+                    util::CountBytesBody {
+                        inner: body,
+                        counter: response_bytes_counter.clone(),
+                    }
+                }))
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .send_compressed_with_level(encoding, level)
+        .accept_compressed(encoding);
+
+    client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap();
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(res.into_inner().data.len(), UNCOMPRESSED_MIN_BODY_SIZE);
+    assert!(response_bytes_counter.load(SeqCst) < UNCOMPRESSED_MIN_BODY_SIZE);
+}
@@ -0,0 +1,120 @@
+use super::*;
+use std::io;
+use tonic::codec::{CompressionEncoding, CompressionLevel, Compressor};
+
+/// The snappy framing format.
+#[allow(dead_code)]
+struct Snappy;
+
+impl Compressor for Snappy {
+    fn name(&self) -> &'static str {
+        "snappy"
+    }
+
+    fn compress(
+        &self,
+        input: &[u8],
+        output: &mut dyn io::Write,
+        _level: CompressionLevel,
+    ) -> io::Result<()> {
+        let mut encoder = snap::write::FrameEncoder::new(output);
+        io::Write::write_all(&mut encoder, input)?;
+        io::Write::flush(&mut encoder)
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        io::copy(&mut snap::read::FrameDecoder::new(input), output)?;
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+const SNAPPY: CompressionEncoding = CompressionEncoding::Custom(&Snappy);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_enabled_server_enabled() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .accept_compressed(SNAPPY)
+        .send_compressed(SNAPPY);
+
+    let request_bytes_counter = Arc::new(AtomicUsize::new(0));
+    let response_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let request_bytes_counter = request_bytes_counter.clone();
+        let response_bytes_counter = response_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(measure_request_body_size_layer(request_bytes_counter))
+                        .layer(MapResponseBodyLayer::new(move |body| {
+                            util::CountBytesBody {
+                                inner: body,
+                                counter: response_bytes_counter.clone(),
+                            }
+                        }))
+                        .into_inner(),
+                )
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .send_compressed(SNAPPY)
+        .accept_compressed(SNAPPY);
+
+    client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap();
+    assert!(request_bytes_counter.load(SeqCst) < UNCOMPRESSED_MIN_BODY_SIZE);
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(res.metadata().get("grpc-encoding").unwrap(), "snappy");
+    assert_eq!(res.into_inner().data.len(), UNCOMPRESSED_MIN_BODY_SIZE);
+    assert!(response_bytes_counter.load(SeqCst) < UNCOMPRESSED_MIN_BODY_SIZE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_enabled_server_disabled() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc =
+        test_server::TestServer::new(Svc::default()).accept_compressed(CompressionEncoding::Gzip);
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+            .await
+            .unwrap();
+    });
+
+    let mut client =
+        test_client::TestClient::new(mock_io_channel(client).await).send_compressed(SNAPPY);
+
+    let status = client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::Unimplemented);
+    assert_eq!(
+        status.message(),
+        "Content is compressed with `snappy` which isn't supported"
+    );
+    assert_eq!(
+        status.metadata().get("grpc-accept-encoding").unwrap(),
+        "gzip,identity"
+    );
+}
@@ -0,0 +1,91 @@
+use super::*;
+use tonic::codec::CompressionEncoding;
+
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+util::parametrized_tests! {
+    rejects_request_bomb,
+    zstd: CompressionEncoding::Zstd,
+    gzip: CompressionEncoding::Gzip,
+}
+
+#[allow(dead_code)]
+async fn rejects_request_bomb(encoding: CompressionEncoding) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .accept_compressed(encoding)
+        .max_decoding_message_size(MAX_MESSAGE_SIZE);
+
+    let request_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let request_bytes_counter = request_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(measure_request_body_size_layer(request_bytes_counter))
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client =
+        test_client::TestClient::new(mock_io_channel(client).await).send_compressed(encoding);
+
+    // Megabytes of zeros compress to a few kilobytes, well within the limit
+    let status = client
+        .compress_input_unary(SomeData {
+            data: vec![0_u8; 4 * 1024 * 1024],
+        })
+        .await
+        .unwrap_err();
+
+    assert!(request_bytes_counter.load(SeqCst) < MAX_MESSAGE_SIZE);
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(
+        status.message(),
+        format!(
+            "Error decompressing: decompressed message length exceeds the limit of {} bytes",
+            MAX_MESSAGE_SIZE
+        )
+    );
+}
+
+util::parametrized_tests! {
+    rejects_response_bomb,
+    zstd: CompressionEncoding::Zstd,
+    gzip: CompressionEncoding::Gzip,
+}
+
+#[allow(dead_code)]
+async fn rejects_response_bomb(encoding: CompressionEncoding) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default()).send_compressed(encoding);
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+            .await
+            .unwrap();
+    });
+
+    let limit = UNCOMPRESSED_MIN_BODY_SIZE / 2;
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .accept_compressed(encoding)
+        .max_decoding_message_size(limit);
+
+    let status = client.compress_output_unary(()).await.unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(
+        status.message(),
+        format!(
+            "Error decompressing: decompressed message length exceeds the limit of {} bytes",
+            limit
+        )
+    );
+}
@@ -22,6 +22,9 @@ mod bidirectional_stream;
 mod client_stream;
 mod compressing_request;
 mod compressing_response;
+mod compression_settings;
+mod custom_compressor;
+mod decompression_bomb;
 mod server_stream;
 mod util;
 
@@ -0,0 +1,167 @@
+use integration_tests::pb::{test1_client, test1_server, test_client, test_server};
+use integration_tests::pb::{Input, Input1, Output, Output1};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::{
+    codegen::BoxStream,
+    transport::{server::CancellationToken, Server},
+    Code, Request, Response, Status,
+};
+
+fn token<T>(req: &Request<T>) -> CancellationToken {
+    req.extensions()
+        .get::<CancellationToken>()
+        .cloned()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn cancels_spawned_work_on_deadline() {
+    struct Svc {
+        cancelled: mpsc::Sender<()>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, req: Request<Input>) -> Result<Response<Output>, Status> {
+            let token = token(&req);
+            let cancelled = self.cancelled.clone();
+            tokio::spawn(async move {
+                token.cancelled().await;
+                cancelled.send(()).await.unwrap();
+            });
+
+            tokio::time::sleep(Duration::from_secs(100)).await;
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let addr = serve(test_server::TestServer::new(Svc { cancelled: tx })).await;
+
+    let mut client = test_client::TestClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let mut req = Request::new(Input {});
+    req.set_timeout(Duration::from_millis(100));
+    let status = client.unary_call(req).await.unwrap_err();
+    assert_eq!(status.code(), Code::Cancelled);
+
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("spawned task was not cancelled")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn cancels_stream_producer_when_client_goes_away() {
+    struct Svc {
+        cancelled: mpsc::Sender<()>,
+    }
+
+    #[tonic::async_trait]
+    impl test1_server::Test1 for Svc {
+        async fn unary_call(&self, _: Request<Input1>) -> Result<Response<Output1>, Status> {
+            unimplemented!()
+        }
+
+        type StreamCallStream = BoxStream<Output1>;
+
+        async fn stream_call(
+            &self,
+            req: Request<Input1>,
+        ) -> Result<Response<Self::StreamCallStream>, Status> {
+            let token = token(&req);
+            let cancelled = self.cancelled.clone();
+            let (tx, rx) = mpsc::channel(1);
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = tx.send(Ok(Output1 { buf: vec![1] })) => {}
+                    }
+                }
+                cancelled.send(()).await.unwrap();
+            });
+
+            Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+        }
+    }
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let addr = serve(test1_server::Test1Server::new(Svc { cancelled: tx })).await;
+
+    let mut client = test1_client::Test1Client::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let mut stream = client
+        .stream_call(Input1 { buf: vec![] })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(stream.next().await.unwrap().is_ok());
+    drop(stream);
+
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("stream producer was not cancelled")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn does_not_cancel_completed_calls() {
+    struct Svc {
+        tokens: mpsc::Sender<CancellationToken>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, req: Request<Input>) -> Result<Response<Output>, Status> {
+            self.tokens.send(token(&req)).await.unwrap();
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let addr = serve(test_server::TestServer::new(Svc { tokens: tx })).await;
+
+    let mut client = test_client::TestClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    client.unary_call(Input {}).await.unwrap();
+    let token = rx.recv().await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!token.is_cancelled());
+}
+
+async fn serve<S>(svc: S) -> SocketAddr
+where
+    S: tower_service::Service<
+            http::Request<tonic::body::BoxBody>,
+            Response = http::Response<tonic::body::BoxBody>,
+            Error = std::convert::Infallible,
+        > + tonic::server::NamedService
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,119 @@
+use integration_tests::pb::{
+    test_client, test_server, test_stream_client, test_stream_server, Input, InputStream, Output,
+    OutputStream,
+};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{server::Router, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+type Stream<T> = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = std::result::Result<T, Status>> + Send + 'static>,
+>;
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+#[tonic::async_trait]
+impl test_stream_server::TestStream for Svc {
+    type StreamCallStream = Stream<OutputStream>;
+
+    async fn stream_call(
+        &self,
+        _: Request<InputStream>,
+    ) -> Result<Response<Self::StreamCallStream>, Status> {
+        // Sends a single message and never completes.
+        let stream = tokio_stream::iter([Ok(OutputStream {})]).chain(tokio_stream::pending());
+        Ok(Response::new(Box::pin(stream) as Self::StreamCallStream))
+    }
+}
+
+#[tokio::test]
+async fn max_connection_idle_closes_idle_connection() {
+    let mut builder = Server::builder().max_connection_idle(Duration::from_millis(200));
+    let handle = builder.handle();
+    let addr = serve(builder.add_service(test_server::TestServer::new(Svc))).await;
+
+    let mut client = test_client::TestClient::new(connect(addr).await);
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(handle.active_connections(), 1);
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(handle.active_connections(), 0);
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(handle.active_connections(), 1);
+}
+
+#[tokio::test]
+async fn max_connection_idle_keeps_connection_with_streams() {
+    let mut builder = Server::builder().max_connection_idle(Duration::from_millis(200));
+    let handle = builder.handle();
+    let addr = serve(builder.add_service(test_stream_server::TestStreamServer::new(Svc))).await;
+
+    let mut client = test_stream_client::TestStreamClient::new(connect(addr).await);
+    let mut stream = client
+        .stream_call(InputStream {})
+        .await
+        .unwrap()
+        .into_inner();
+    stream.message().await.unwrap().unwrap();
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(handle.active_connections(), 1);
+    assert_eq!(handle.active_streams(), 1);
+}
+
+#[tokio::test]
+async fn max_connection_age_grace_closes_draining_connection() {
+    let mut builder = Server::builder()
+        .max_connection_age(Duration::from_millis(200))
+        .max_connection_age_grace(Duration::from_millis(200));
+    let handle = builder.handle();
+    let addr = serve(builder.add_service(test_stream_server::TestStreamServer::new(Svc))).await;
+
+    let mut client = test_stream_client::TestStreamClient::new(connect(addr).await);
+    let mut stream = client
+        .stream_call(InputStream {})
+        .await
+        .unwrap()
+        .into_inner();
+    stream.message().await.unwrap().unwrap();
+
+    let closed = tokio::time::timeout(Duration::from_secs(2), stream.message()).await;
+    closed.unwrap().unwrap_err();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(handle.active_connections(), 0);
+}
+
+async fn connect(addr: SocketAddr) -> Channel {
+    Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn serve(router: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,146 @@
+use integration_tests::pb::{test_client, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+use tonic::{
+    transport::{
+        channel::{ConnectivityState, MethodConfig, ReconnectBackoff, ServiceConfig},
+        Endpoint, Server,
+    },
+    Code, Request, Response, Status,
+};
+
+#[tokio::test]
+async fn reports_connectivity_state() {
+    let addr = unused_addr().await;
+    run_service_in_background(addr).await;
+
+    let channel = endpoint(addr).connect_lazy();
+    let mut states = channel.watch_state();
+    assert_eq!(channel.state(), ConnectivityState::Idle);
+    assert_eq!(states.next().await, Some(ConnectivityState::Idle));
+
+    let mut client = test_client::TestClient::new(channel.clone());
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+
+    drop(client);
+    drop(channel);
+
+    let mut last = None;
+    while let Some(state) = states.next().await {
+        last = Some(state);
+    }
+    assert_eq!(last, Some(ConnectivityState::Shutdown));
+}
+
+#[tokio::test]
+async fn reports_transient_failure() {
+    let addr = unused_addr().await;
+
+    let channel = endpoint(addr).connect_lazy();
+    let mut client = test_client::TestClient::new(channel.clone());
+
+    let err = client.unary_call(Input {}).await.unwrap_err();
+    assert_eq!(err.code(), Code::Unavailable);
+    assert_eq!(channel.state(), ConnectivityState::TransientFailure);
+}
+
+#[tokio::test]
+async fn wait_for_ready_waits_for_server() {
+    let addr = unused_addr().await;
+
+    let channel = endpoint(addr).wait_for_ready(true).connect_lazy();
+    let mut client = test_client::TestClient::new(channel);
+    let call = tokio::spawn(async move { client.unary_call(Input {}).await });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!call.is_finished());
+
+    run_service_in_background(addr).await;
+    call.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn wait_for_ready_reconnects_after_backoff() {
+    let addr = unused_addr().await;
+
+    let backoff = ReconnectBackoff::new().initial_delay(Duration::from_millis(300));
+    let channel = endpoint(addr)
+        .reconnect_backoff(backoff)
+        .wait_for_ready(true)
+        .connect_lazy();
+    let mut client = test_client::TestClient::new(channel.clone());
+    let call = tokio::spawn(async move { client.unary_call(Input {}).await });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(channel.state(), ConnectivityState::TransientFailure);
+
+    run_service_in_background(addr).await;
+    call.await.unwrap().unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+}
+
+#[tokio::test]
+async fn wait_for_ready_respects_deadline() {
+    let addr = unused_addr().await;
+
+    let channel = endpoint(addr).wait_for_ready(true).connect_lazy();
+    let mut client = test_client::TestClient::new(channel);
+
+    let mut request = Request::new(Input {});
+    request.set_timeout(Duration::from_millis(200));
+    let err = client.unary_call(request).await.unwrap_err();
+
+    assert_eq!(err.code(), Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn method_config_enables_wait_for_ready() {
+    let addr = unused_addr().await;
+
+    let config =
+        ServiceConfig::new().service("test.Test", MethodConfig::new().wait_for_ready(true));
+    let channel = endpoint(addr).service_config(config).connect_lazy();
+    let mut client = test_client::TestClient::new(channel);
+
+    let mut request = Request::new(Input {});
+    request.set_timeout(Duration::from_millis(200));
+    let err = client.unary_call(request).await.unwrap_err();
+
+    assert_eq!(err.code(), Code::DeadlineExceeded);
+}
+
+fn endpoint(addr: SocketAddr) -> Endpoint {
+    Endpoint::from_shared(format!("http://{addr}")).unwrap()
+}
+
+/// Returns an address nobody listens on, until a service is run on it.
+async fn unused_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+async fn run_service_in_background(addr: SocketAddr) {
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let listener = TcpListener::bind(addr).await.unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test_server::TestServer::new(Svc))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+}
@@ -0,0 +1,49 @@
+use integration_tests::pb::{test_server, Input, Output};
+use std::future::poll_fn;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Service},
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+async fn call(content_type: &str) -> http::Response<BoxBody> {
+    let mut svc = test_server::TestServer::new(Svc);
+
+    let request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("/test.Test/UnaryCall")
+        .header(http::header::CONTENT_TYPE, content_type)
+        .body(BoxBody::default())
+        .unwrap();
+    poll_fn(|cx| Service::<http::Request<BoxBody>>::poll_ready(&mut svc, cx))
+        .await
+        .unwrap();
+    svc.call(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn echoes_protobuf_content_type() {
+    for content_type in ["application/grpc", "application/grpc+proto"] {
+        let res = call(content_type).await;
+
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), content_type);
+    }
+}
+
+#[tokio::test]
+async fn rejects_other_content_subtypes() {
+    let res = call("application/grpc+json").await;
+
+    assert_eq!(res.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(res.headers().get("grpc-status").unwrap(), "12");
+}
@@ -0,0 +1,128 @@
+use integration_tests::pb::{
+    test_client, test_server, test_stream_client, test_stream_server, Input, InputStream, Output,
+    OutputStream,
+};
+use std::time::Duration;
+use tokio::{net::TcpListener, sync::oneshot, time::Instant};
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{server::ServerHandle, Endpoint, Server},
+    Request, Response, Status,
+};
+
+type Stream<T> = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = std::result::Result<T, Status>> + Send + 'static>,
+>;
+
+#[tokio::test]
+async fn drain_timeout_closes_remaining_streams() {
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl test_stream_server::TestStream for Svc {
+        type StreamCallStream = Stream<OutputStream>;
+
+        async fn stream_call(
+            &self,
+            _: Request<InputStream>,
+        ) -> Result<Response<Self::StreamCallStream>, Status> {
+            let stream = tokio_stream::iter([Ok(OutputStream {})]).chain(tokio_stream::pending());
+            Ok(Response::new(Box::pin(stream) as Self::StreamCallStream))
+        }
+    }
+
+    let mut builder = Server::builder().drain_timeout(Duration::from_millis(300));
+    let handle = builder.handle();
+    let router = builder.add_service(test_stream_server::TestStreamServer::new(Svc));
+    let (addr, shutdown, server) = serve(router).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test_stream_client::TestStreamClient::new(channel);
+    let mut stream = client
+        .stream_call(InputStream {})
+        .await
+        .unwrap()
+        .into_inner();
+    stream.message().await.unwrap().unwrap();
+
+    assert_eq!(handle.active_connections(), 1);
+    assert_eq!(handle.active_streams(), 1);
+
+    let start = Instant::now();
+    shutdown.send(()).unwrap();
+    server.await.unwrap();
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+
+    stream.message().await.unwrap_err();
+    assert_eq!(handle.active_connections(), 0);
+    assert_eq!(handle.active_streams(), 0);
+}
+
+#[tokio::test]
+async fn drains_streams_completing_in_time() {
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let mut builder = Server::builder().drain_timeout(Duration::from_secs(5));
+    let handle = builder.handle();
+    let router = builder.add_service(test_server::TestServer::new(Svc));
+    let (addr, shutdown, server) = serve(router).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test_client::TestClient::new(channel);
+    let call = tokio::spawn(async move { client.unary_call(Input {}).await });
+
+    wait_for_stream(&handle).await;
+    shutdown.send(()).unwrap();
+
+    call.await.unwrap().unwrap();
+    server.await.unwrap();
+    assert_eq!(handle.active_connections(), 0);
+}
+
+async fn wait_for_stream(handle: &ServerHandle) {
+    while handle.active_streams() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+async fn serve(
+    router: tonic::transport::server::Router,
+) -> (
+    std::net::SocketAddr,
+    oneshot::Sender<()>,
+    tokio::task::JoinHandle<()>,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = oneshot::channel::<()>();
+
+    let server = tokio::spawn(async move {
+        router
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                drop(rx.await)
+            })
+            .await
+            .unwrap();
+    });
+
+    (addr, tx, server)
+}
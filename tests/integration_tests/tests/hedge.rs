@@ -0,0 +1,204 @@
+use integration_tests::pb::{test_client, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{net::TcpListener, time::Instant};
+use tonic::{
+    transport::{
+        channel::{Hedge, HedgingPolicy, RetryPolicies, RetryPolicy},
+        Channel, Endpoint, Server,
+    },
+    Code, Request, Response, Status,
+};
+use tower::discover::Change;
+
+#[tokio::test]
+async fn hedges_around_slow_endpoint() {
+    let slow_calls = Arc::new(AtomicUsize::new(0));
+    let fast_calls = Arc::new(AtomicUsize::new(0));
+    let slow = run_service_in_background(slow_calls.clone(), Duration::from_secs(5), None).await;
+    let fast = run_service_in_background(fast_calls.clone(), Duration::ZERO, None).await;
+
+    let endpoints = [slow, fast]
+        .into_iter()
+        .map(|addr| Endpoint::from_shared(format!("http://{addr}")).unwrap());
+    let channel = Hedge::new(
+        Channel::balance_list(endpoints),
+        HedgingPolicy::new(2).hedging_delay(Duration::from_millis(50)),
+    );
+    let mut client = test_client::TestClient::new(channel);
+
+    let start = Instant::now();
+    for _ in 0..10 {
+        client.unary_call(Input {}).await.unwrap();
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(fast_calls.load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn hedges_with_policy_of_balanced_list() {
+    let slow_calls = Arc::new(AtomicUsize::new(0));
+    let fast_calls = Arc::new(AtomicUsize::new(0));
+    let slow = run_service_in_background(slow_calls.clone(), Duration::from_secs(5), None).await;
+    let fast = run_service_in_background(fast_calls.clone(), Duration::ZERO, None).await;
+
+    let endpoints = [slow, fast].into_iter().map(|addr| {
+        Endpoint::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .hedging_policy(HedgingPolicy::new(2).hedging_delay(Duration::from_millis(50)))
+    });
+    let mut client = test_client::TestClient::new(Channel::balance_list(endpoints));
+
+    let start = Instant::now();
+    for _ in 0..10 {
+        client.unary_call(Input {}).await.unwrap();
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(fast_calls.load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn hedges_with_policy_of_balanced_channel() {
+    let slow_calls = Arc::new(AtomicUsize::new(0));
+    let fast_calls = Arc::new(AtomicUsize::new(0));
+    let slow = run_service_in_background(slow_calls.clone(), Duration::from_secs(5), None).await;
+    let fast = run_service_in_background(fast_calls.clone(), Duration::ZERO, None).await;
+
+    let config = Endpoint::from_static("http://[::1]:50051")
+        .hedging_policy(HedgingPolicy::new(2).hedging_delay(Duration::from_millis(50)));
+    let (channel, tx) = Channel::balance_channel_with_config(10, &config);
+    for addr in [slow, fast] {
+        let endpoint = Endpoint::from_shared(format!("http://{addr}")).unwrap();
+        tx.send(Change::Insert(addr, endpoint)).await.unwrap();
+    }
+    let mut client = test_client::TestClient::new(channel);
+
+    let start = Instant::now();
+    for _ in 0..10 {
+        client.unary_call(Input {}).await.unwrap();
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(fast_calls.load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn rejects_method_with_retry_and_hedging_policies() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), Duration::ZERO, None).await;
+
+    let endpoint = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(RetryPolicies::new().service("test.Test", RetryPolicy::new(3)))
+        .hedging_policy(HedgingPolicy::new(2));
+
+    let err = endpoint.connect().await.unwrap_err();
+    assert_eq!(err.to_string(), "invalid service config");
+
+    // Channels that cannot fail fail the calls instead.
+    let mut client = test_client::TestClient::new(Channel::balance_list([endpoint].into_iter()));
+    client.unary_call(Input {}).await.unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn non_fatal_code_sends_next_attempt() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr =
+        run_service_in_background(calls.clone(), Duration::ZERO, Some(Code::Unavailable)).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .hedging_policy(
+            HedgingPolicy::new(3)
+                .hedging_delay(Duration::from_secs(10))
+                .non_fatal_status_codes([Code::Unavailable]),
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    let start = Instant::now();
+    client.unary_call(Input {}).await.unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn fatal_code_completes_call() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr =
+        run_service_in_background(calls.clone(), Duration::ZERO, Some(Code::InvalidArgument)).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .hedging_policy(
+            HedgingPolicy::new(3)
+                .hedging_delay(Duration::from_secs(10))
+                .non_fatal_status_codes([Code::Unavailable]),
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    let err = client.unary_call(Input {}).await.unwrap_err();
+
+    assert_eq!(err.code(), Code::InvalidArgument);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+/// Runs a service that answers after `latency`, failing the first call with `first_error`.
+async fn run_service_in_background(
+    calls: Arc<AtomicUsize>,
+    latency: Duration,
+    first_error: Option<Code>,
+) -> SocketAddr {
+    struct Svc {
+        calls: Arc<AtomicUsize>,
+        latency: Duration,
+        first_error: Option<Code>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.latency).await;
+
+            match self.first_error {
+                Some(code) if attempt == 0 => Err(Status::new(code, "first call fails")),
+                _ => Ok(Response::new(Output {})),
+            }
+        }
+    }
+
+    let svc = test_server::TestServer::new(Svc {
+        calls,
+        latency,
+        first_error,
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
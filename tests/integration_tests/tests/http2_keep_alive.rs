@@ -1,6 +1,11 @@
 use std::time::Duration;
 
-use tokio::sync::oneshot;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+use tokio_stream::wrappers::TcpListenerStream;
 
 use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
 use tonic::transport::{Channel, Server};
@@ -69,3 +74,55 @@ async fn http2_keepalive_does_not_cause_panics_on_client_side() {
     tx.send(()).unwrap();
     jh.await.unwrap();
 }
+
+#[tokio::test]
+async fn http2_keepalive_enforcement_sends_goaway_on_ping_flood() {
+    let svc = test_server::TestServer::new(Svc {});
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .http2_keepalive_min_ping_interval(Some(Duration::from_secs(10)))
+            .http2_keepalive_permit_without_stream(true)
+            .add_service(svc)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+        .await
+        .unwrap();
+    // An empty SETTINGS frame.
+    stream
+        .write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0])
+        .await
+        .unwrap();
+    for _ in 0..5 {
+        let mut ping = vec![0, 0, 8, 6, 0, 0, 0, 0, 0];
+        ping.extend_from_slice(&[0; 8]);
+        stream.write_all(&ping).await.unwrap();
+    }
+
+    let goaway_error = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let mut header = [0; 9];
+            stream.read_exact(&mut header).await.unwrap();
+            let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+            let mut payload = vec![0; len];
+            stream.read_exact(&mut payload).await.unwrap();
+
+            if header[3] == 7 {
+                break u32::from_be_bytes(payload[4..8].try_into().unwrap());
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    // ENHANCE_YOUR_CALM
+    assert_eq!(goaway_error, 0xb);
+}
@@ -0,0 +1,80 @@
+use integration_tests::pb::{test_client, test_server, Input, Output};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{channel::ConnectivityState, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+#[tokio::test]
+async fn closes_idle_connection() {
+    let (channel, connections) = connect_with_idle_timeout(Duration::from_millis(200)).await;
+    let mut client = test_client::TestClient::new(channel.clone());
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(channel.state(), ConnectivityState::Idle);
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn keeps_busy_connection() {
+    let (channel, connections) = connect_with_idle_timeout(Duration::from_millis(300)).await;
+    let mut client = test_client::TestClient::new(channel);
+
+    for _ in 0..6 {
+        client.unary_call(Input {}).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+async fn connect_with_idle_timeout(idle_timeout: Duration) -> (Channel, Arc<AtomicUsize>) {
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    let incoming = TcpListenerStream::new(listener).map(move |stream| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        stream
+    });
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test_server::TestServer::new(Svc))
+            .serve_with_incoming(incoming)
+            .await
+            .unwrap();
+    });
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .idle_timeout(idle_timeout)
+        .connect_lazy();
+
+    (channel, connections)
+}
@@ -1,7 +1,10 @@
 use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
-use std::time::Duration;
-use tokio::sync::oneshot;
+use std::{future::Future, pin::Pin, time::Duration};
+use tokio::{net::TcpListener, sync::oneshot};
+use tokio_stream::wrappers::TcpListenerStream;
 use tonic::{
+    metadata::MetadataMap,
+    service::AsyncInterceptor,
     transport::{Endpoint, Server},
     GrpcMethod, Request, Response, Status,
 };
@@ -50,3 +53,73 @@ async fn interceptor_retrieves_grpc_method() {
     tx.send(()).unwrap();
     jh.await.unwrap();
 }
+
+#[derive(Clone)]
+struct Auth;
+
+impl AsyncInterceptor for Auth {
+    type Future = Pin<Box<dyn Future<Output = Result<Request<()>, Status>> + Send>>;
+
+    fn call(&mut self, req: Request<()>) -> Self::Future {
+        Box::pin(async move {
+            tokio::task::yield_now().await;
+            match req.metadata().get("authorization") {
+                Some(token) if token == "Bearer secret" => Ok(req),
+                _ => Err(Status::unauthenticated("invalid token")),
+            }
+        })
+    }
+
+    fn on_response(&mut self, metadata: &mut MetadataMap) {
+        metadata.insert("x-authenticated", "true".parse().unwrap());
+    }
+
+    fn on_trailers(&mut self, trailers: &mut MetadataMap) {
+        trailers.insert("x-server-trailer", "true".parse().unwrap());
+    }
+}
+
+#[tokio::test]
+async fn async_interceptors_intercept_requests_and_responses() {
+    use test_server::Test;
+
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test_server::TestServer::with_async_interceptor(Svc, Auth))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+
+    let mut client = TestClient::new(channel.clone());
+    let status = client.unary_call(Input {}).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    let mut client = TestClient::with_async_interceptor(channel, |mut req: Request<()>| async {
+        tokio::task::yield_now().await;
+        req.metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        Ok(req)
+    });
+    let res = client.unary_call(Input {}).await.unwrap();
+    assert_eq!(res.metadata().get("x-authenticated").unwrap(), "true");
+    assert_eq!(res.metadata().get("x-server-trailer").unwrap(), "true");
+}
@@ -0,0 +1,172 @@
+use integration_tests::pb::{test_client, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::net::TcpListener;
+use tonic::{
+    codegen::http::HeaderName,
+    transport::{channel::LoadBalancingPolicy, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+#[tokio::test]
+async fn round_robin_spreads_calls_evenly() {
+    let (endpoints, calls) = run_services_in_background(3).await;
+    let channel =
+        Channel::balance_list_with_policy(endpoints.into_iter(), LoadBalancingPolicy::RoundRobin);
+    let mut client = test_client::TestClient::new(channel);
+
+    for _ in 0..30 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    for calls in &calls {
+        let calls = calls.load(Ordering::SeqCst);
+        assert!((8..=12).contains(&calls), "{calls} calls");
+    }
+}
+
+#[tokio::test]
+async fn pick_first_sends_calls_to_first_endpoint() {
+    let (endpoints, calls) = run_services_in_background(3).await;
+    let channel =
+        Channel::balance_list_with_policy(endpoints.into_iter(), LoadBalancingPolicy::PickFirst);
+    let mut client = test_client::TestClient::new(channel);
+
+    for _ in 0..10 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert_eq!(calls[0].load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn pick_first_skips_endpoint_refusing_connections() {
+    let refusing = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+    let (endpoints, calls) = run_services_in_background(1).await;
+    let endpoints = std::iter::once(Endpoint::from_shared(format!("http://{refusing}")).unwrap())
+        .chain(endpoints);
+    let channel = Channel::balance_list_with_policy(endpoints, LoadBalancingPolicy::PickFirst);
+    let mut client = test_client::TestClient::new(channel);
+
+    for _ in 0..10 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert_eq!(calls[0].load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn weighted_round_robin_follows_weights() {
+    let (endpoints, calls) = run_services_in_background(2).await;
+    let endpoints = endpoints
+        .into_iter()
+        .zip([3, 1])
+        .map(|(endpoint, weight)| endpoint.weight(weight));
+    let channel =
+        Channel::balance_list_with_policy(endpoints, LoadBalancingPolicy::WeightedRoundRobin);
+    let mut client = test_client::TestClient::new(channel);
+
+    for _ in 0..40 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    let heavy = calls[0].load(Ordering::SeqCst);
+    let light = calls[1].load(Ordering::SeqCst);
+    assert!(heavy >= 2 * light, "{heavy} and {light} calls");
+    assert!(light > 0);
+}
+
+#[tokio::test]
+async fn ring_hash_keeps_key_on_one_endpoint() {
+    let (endpoints, calls) = run_services_in_background(3).await;
+    let channel = Channel::balance_list_with_policy(
+        endpoints.into_iter(),
+        LoadBalancingPolicy::RingHash(HeaderName::from_static("x-user-id")),
+    );
+    let mut client = test_client::TestClient::new(channel);
+
+    // Spread keys over the endpoints so every connection is established.
+    for user in 0..30 {
+        client.unary_call(with_user(user)).await.unwrap();
+    }
+    let before = counts(&calls);
+
+    for _ in 0..10 {
+        client.unary_call(with_user(7)).await.unwrap();
+    }
+
+    let after = counts(&calls);
+    let changed = (0..3).filter(|&i| after[i] != before[i]).count();
+    assert_eq!(changed, 1, "{before:?} to {after:?}");
+
+    assert!(
+        before.iter().filter(|&&calls| calls > 0).count() > 1,
+        "{before:?}"
+    );
+}
+
+fn with_user(user: u32) -> Request<Input> {
+    let mut request = Request::new(Input {});
+    request
+        .metadata_mut()
+        .insert("x-user-id", user.to_string().parse().unwrap());
+    request
+}
+
+fn counts(calls: &[Arc<AtomicUsize>]) -> Vec<usize> {
+    calls
+        .iter()
+        .map(|calls| calls.load(Ordering::SeqCst))
+        .collect()
+}
+
+async fn run_services_in_background(n: usize) -> (Vec<Endpoint>, Vec<Arc<AtomicUsize>>) {
+    let mut endpoints = Vec::new();
+    let mut calls = Vec::new();
+
+    for _ in 0..n {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let addr = run_service_in_background(counter.clone()).await;
+        endpoints.push(Endpoint::from_shared(format!("http://{addr}")).unwrap());
+        calls.push(counter);
+    }
+
+    (endpoints, calls)
+}
+
+async fn run_service_in_background(calls: Arc<AtomicUsize>) -> SocketAddr {
+    struct Svc {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let svc = test_server::TestServer::new(Svc { calls });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,235 @@
+use integration_tests::pb::{test_client, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    transport::{
+        channel::resolver::{Address, AddressStream, ResolveNow, Resolver, Target},
+        Channel, Endpoint, Server,
+    },
+    Request, Response, Status,
+};
+
+#[tokio::test]
+async fn balances_over_resolved_addresses() {
+    let calls = [Arc::default(), Arc::default()];
+    let a = run_service_in_background(Arc::clone(&calls[0])).await;
+    let b = run_service_in_background(Arc::clone(&calls[1])).await;
+
+    let (resolver, updates, _) = StubResolver::new();
+    let channel = Channel::balance_resolver("stub:///test", endpoint(), resolver).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    updates
+        .send(Ok(vec![Address::Tcp(a), Address::Tcp(b)]))
+        .await
+        .unwrap();
+
+    // Concurrent calls make the balancer spread the load over both addresses.
+    let calls_in_flight = (0..20)
+        .map(|_| {
+            let mut client = client.clone();
+            tokio::spawn(async move { client.unary_call(Input {}).await })
+        })
+        .collect::<Vec<_>>();
+    for call in calls_in_flight {
+        call.await.unwrap().unwrap();
+    }
+
+    assert!(calls[0].load(Ordering::SeqCst) > 0);
+    assert!(calls[1].load(Ordering::SeqCst) > 0);
+
+    // Addresses that are no longer resolved stop receiving calls.
+    updates.send(Ok(vec![Address::Tcp(b)])).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let before = calls[0].load(Ordering::SeqCst);
+    for _ in 0..10 {
+        client.unary_call(Input {}).await.unwrap();
+    }
+    assert_eq!(calls[0].load(Ordering::SeqCst), before);
+}
+
+#[tokio::test]
+async fn keeps_addresses_on_resolution_error() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let (resolver, updates, _) = StubResolver::new();
+    let channel = Channel::balance_resolver("stub:///test", endpoint(), resolver).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    updates.send(Ok(vec![Address::Tcp(addr)])).await.unwrap();
+    updates
+        .send(Err("resolver unavailable".into()))
+        .await
+        .unwrap();
+    updates.send(Ok(vec![])).await.unwrap();
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn connection_failure_requests_resolution() {
+    // Reserve a port nobody listens on.
+    let dead = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (resolver, updates, resolve_now) = StubResolver::new();
+    let channel = Channel::balance_resolver("stub:///test", endpoint(), resolver).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    updates.send(Ok(vec![Address::Tcp(dead)])).await.unwrap();
+
+    client.unary_call(Input {}).await.unwrap_err();
+
+    let resolve_now = resolve_now.lock().unwrap().take().unwrap();
+    tokio::time::timeout(Duration::from_secs(5), resolve_now.notified())
+        .await
+        .unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+    updates.send(Ok(vec![Address::Tcp(addr)])).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn balances_static_target() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let channel = Channel::balance_target(&format!("static:{addr}"), endpoint()).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn balances_dns_target() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let channel = Channel::balance_target(&format!("dns:///{addr}"), endpoint()).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn balances_unix_target() {
+    struct Svc;
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("tonic-resolver-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test_server::TestServer::new(Svc))
+            .serve_with_incoming(tokio_stream::wrappers::UnixListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let target = format!("unix://{}", path.display());
+    let channel = Channel::balance_target(&target, endpoint()).unwrap();
+    let mut client = test_client::TestClient::new(channel);
+
+    client.unary_call(Input {}).await.unwrap();
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn rejects_unknown_scheme() {
+    assert!(Channel::balance_target("consul://agent/service", endpoint()).is_err());
+}
+
+fn endpoint() -> Endpoint {
+    Endpoint::from_static("http://test.local")
+}
+
+type Update = Result<Vec<Address>, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Resolves to the address sets sent on a channel, no matter the target.
+struct StubResolver {
+    updates: Mutex<Option<mpsc::Receiver<Update>>>,
+    resolve_now: Arc<Mutex<Option<ResolveNow>>>,
+}
+
+impl StubResolver {
+    fn new() -> (Self, mpsc::Sender<Update>, Arc<Mutex<Option<ResolveNow>>>) {
+        let (tx, rx) = mpsc::channel(8);
+        let resolve_now = Arc::default();
+        let resolver = StubResolver {
+            updates: Mutex::new(Some(rx)),
+            resolve_now: Arc::clone(&resolve_now),
+        };
+
+        (resolver, tx, resolve_now)
+    }
+}
+
+impl Resolver for StubResolver {
+    fn resolve(&self, _target: &Target, resolve_now: ResolveNow) -> AddressStream {
+        *self.resolve_now.lock().unwrap() = Some(resolve_now);
+        let updates = self.updates.lock().unwrap().take().unwrap();
+
+        Box::pin(ReceiverStream::new(updates))
+    }
+}
+
+async fn run_service_in_background(calls: Arc<AtomicUsize>) -> SocketAddr {
+    struct Svc {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let svc = test_server::TestServer::new(Svc { calls });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
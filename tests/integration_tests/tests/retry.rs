@@ -0,0 +1,229 @@
+use integration_tests::pb::{test1_client, test1_server, Input1, Output1};
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_stream::Stream;
+use tonic::{
+    transport::{channel::RetryPolicy, Channel, Endpoint, Server},
+    Code, Request, Response, Status,
+};
+use tonic_types::{ErrorDetails, StatusExt};
+
+#[tokio::test]
+async fn retries_until_success() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), 2, Code::Unavailable, None).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(10)))
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let res = client
+        .unary_call(Input1 {
+            buf: b"hello".to_vec(),
+        })
+        .await
+        .unwrap();
+
+    // The request body is replayed on every attempt.
+    assert_eq!(res.into_inner().buf, b"hello");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn waits_for_retry_info_delay() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let retry_delay = Duration::from_millis(300);
+    let addr =
+        run_service_in_background(calls.clone(), 1, Code::Unavailable, Some(retry_delay)).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(
+            RetryPolicy::new(3)
+                .initial_backoff(Duration::from_millis(10))
+                .max_backoff(Duration::from_millis(10)),
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let start = std::time::Instant::now();
+    client.unary_call(Input1 { buf: Vec::new() }).await.unwrap();
+
+    // The delay of the error details replaces the backoff, even beyond the max backoff.
+    assert!(start.elapsed() >= retry_delay);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn retries_on_balanced_list() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), 2, Code::Unavailable, None).await;
+
+    let endpoint = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(10)));
+    let channel = Channel::balance_list(std::iter::once(endpoint));
+    let mut client = test1_client::Test1Client::new(channel);
+
+    client.unary_call(Input1 { buf: Vec::new() }).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), 5, Code::Unavailable, None).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(10)))
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let err = client
+        .unary_call(Input1 { buf: Vec::new() })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), Code::Unavailable);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_other_codes() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), 2, Code::InvalidArgument, None).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(10)))
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let err = client
+        .unary_call(Input1 { buf: Vec::new() })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), Code::InvalidArgument);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn does_not_retry_past_buffer_limit() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone(), 2, Code::Unavailable, None).await;
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .retry_policy(
+            RetryPolicy::new(3)
+                .initial_backoff(Duration::from_millis(10))
+                .max_buffer_size(16),
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let err = client
+        .unary_call(Input1 { buf: vec![0; 64] })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), Code::Unavailable);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+/// Runs a service that fails the first `failures` calls with `code`, and `google.rpc.RetryInfo`
+/// error details if there is a `retry_delay`.
+async fn run_service_in_background(
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+    code: Code,
+    retry_delay: Option<Duration>,
+) -> SocketAddr {
+    struct Svc {
+        calls: Arc<AtomicUsize>,
+        failures: usize,
+        code: Code,
+        retry_delay: Option<Duration>,
+    }
+
+    #[tonic::async_trait]
+    impl test1_server::Test1 for Svc {
+        async fn unary_call(&self, req: Request<Input1>) -> Result<Response<Output1>, Status> {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst);
+            let previous_attempts = req
+                .metadata()
+                .get("grpc-previous-rpc-attempts")
+                .map(|value| value.to_str().unwrap().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            assert_eq!(previous_attempts, attempt);
+
+            if attempt < self.failures {
+                return Err(match self.retry_delay {
+                    Some(delay) => Status::with_error_details(
+                        self.code,
+                        "try again",
+                        ErrorDetails::with_retry_info(Some(delay)),
+                    ),
+                    None => Status::new(self.code, "try again"),
+                });
+            }
+
+            Ok(Response::new(Output1 {
+                buf: req.into_inner().buf,
+            }))
+        }
+
+        type StreamCallStream =
+            Pin<Box<dyn Stream<Item = Result<Output1, Status>> + Send + 'static>>;
+
+        async fn stream_call(
+            &self,
+            _: Request<Input1>,
+        ) -> Result<Response<Self::StreamCallStream>, Status> {
+            unimplemented!()
+        }
+    }
+
+    let svc = test1_server::Test1Server::new(Svc {
+        calls,
+        failures,
+        code,
+        retry_delay,
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
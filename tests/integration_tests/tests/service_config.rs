@@ -0,0 +1,206 @@
+use integration_tests::pb::{test1_client, test1_server, Input1, Output1};
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_stream::Stream;
+use tonic::{
+    codec::CompressionEncoding,
+    transport::{
+        channel::{MethodConfig, ServiceConfig},
+        Channel, Endpoint, Server,
+    },
+    Code, Request, Response, Status,
+};
+
+#[tokio::test]
+async fn applies_method_timeout() {
+    let addr = run_service_in_background(Arc::default()).await;
+
+    let config = ServiceConfig::new()
+        .default_method_config(MethodConfig::new().timeout(Duration::from_secs(10)))
+        .method(
+            "test.Test1",
+            "UnaryCall",
+            MethodConfig::new().timeout(Duration::from_millis(100)),
+        );
+    let mut client = test1_client::Test1Client::new(connect(addr, config).await);
+
+    let err = client
+        .unary_call(Input1 {
+            buf: b"sleep".to_vec(),
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), Code::Cancelled);
+    assert!(err.message().contains("Timeout expired"));
+}
+
+#[tokio::test]
+async fn applies_message_size_limits() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let config = ServiceConfig::new().service(
+        "test.Test1",
+        MethodConfig::new()
+            .max_request_message_bytes(1024)
+            .max_response_message_bytes(512),
+    );
+    let mut client = test1_client::Test1Client::new(connect(addr, config).await);
+
+    client
+        .unary_call(Input1 { buf: vec![0; 128] })
+        .await
+        .unwrap();
+
+    // Like the encoding limit of the client, which should return `OutOfRange`.
+    // https://github.com/hyperium/tonic/issues/1334
+    let err = client
+        .unary_call(Input1 { buf: vec![0; 2048] })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Internal);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let err = client
+        .unary_call(Input1 { buf: vec![0; 768] })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::OutOfRange);
+}
+
+#[tokio::test]
+async fn applies_message_size_limits_after_decompression() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let config = ServiceConfig::new().service(
+        "test.Test1",
+        MethodConfig::new()
+            .max_request_message_bytes(1024)
+            .max_response_message_bytes(512),
+    );
+    let mut client = test1_client::Test1Client::new(connect(addr, config).await)
+        .send_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Gzip);
+
+    client
+        .unary_call(Input1 { buf: vec![0; 128] })
+        .await
+        .unwrap();
+
+    // Like the encoding limit of the client, which should return `OutOfRange`.
+    // https://github.com/hyperium/tonic/issues/1334
+    let err = client
+        .unary_call(Input1 { buf: vec![0; 2048] })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Internal);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let err = client
+        .unary_call(Input1 { buf: vec![0; 768] })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn retries_from_json_config() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(calls.clone()).await;
+
+    let config = ServiceConfig::from_json(
+        r#"{
+            "methodConfig": [{
+                "name": [{ "service": "test.Test1" }],
+                "retryPolicy": {
+                    "maxAttempts": 3,
+                    "initialBackoff": "0.01s",
+                    "maxBackoff": "0.1s",
+                    "backoffMultiplier": 2,
+                    "retryableStatusCodes": ["UNAVAILABLE"]
+                }
+            }]
+        }"#,
+    )
+    .unwrap();
+    let mut client = test1_client::Test1Client::new(connect(addr, config).await);
+
+    client
+        .unary_call(Input1 {
+            buf: b"unavailable".to_vec(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+async fn connect(addr: SocketAddr, config: ServiceConfig) -> Channel {
+    Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .service_config(config)
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Runs a service that echoes the request, the `sleep` request never finishes and the
+/// `unavailable` request fails on the first call.
+async fn run_service_in_background(calls: Arc<AtomicUsize>) -> SocketAddr {
+    struct Svc {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl test1_server::Test1 for Svc {
+        async fn unary_call(&self, req: Request<Input1>) -> Result<Response<Output1>, Status> {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst);
+            let buf = req.into_inner().buf;
+
+            match &buf[..] {
+                b"sleep" => tokio::time::sleep(Duration::from_secs(60)).await,
+                b"unavailable" if attempt == 0 => return Err(Status::unavailable("try again")),
+                _ => {}
+            }
+
+            Ok(Response::new(Output1 { buf }))
+        }
+
+        type StreamCallStream =
+            Pin<Box<dyn Stream<Item = Result<Output1, Status>> + Send + 'static>>;
+
+        async fn stream_call(
+            &self,
+            _: Request<Input1>,
+        ) -> Result<Response<Self::StreamCallStream>, Status> {
+            unimplemented!()
+        }
+    }
+
+    let svc = test1_server::Test1Server::new(Svc { calls })
+        .accept_compressed(CompressionEncoding::Gzip)
+        .send_compressed(CompressionEncoding::Gzip);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -1,7 +1,18 @@
-use integration_tests::pb::{test_client, test_server, Input, Output};
-use std::{net::SocketAddr, time::Duration};
+use integration_tests::pb::{
+    test1_client, test1_server, test_client, test_server, Input, Input1, Output, Output1,
+};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio::net::TcpListener;
-use tonic::{transport::Server, Code, Request, Response, Status};
+use tokio_stream::StreamExt;
+use tonic::{
+    codegen::BoxStream,
+    transport::{Channel, Endpoint, Server},
+    Code, Deadline, Request, Response, Status,
+};
 
 #[tokio::test]
 async fn cancelation_on_timeout() {
@@ -61,6 +72,223 @@ async fn picks_client_timeout_if_thats_sorter() {
     assert_eq!(err.code(), Code::Cancelled);
 }
 
+#[tokio::test]
+async fn propagates_deadline_to_outgoing_calls() {
+    type Timeouts = Arc<Mutex<Vec<Option<String>>>>;
+
+    struct Backend {
+        timeouts: Timeouts,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Backend {
+        async fn unary_call(&self, req: Request<Input>) -> Result<Response<Output>, Status> {
+            let timeout = req
+                .metadata()
+                .get("grpc-timeout")
+                .map(|value| value.to_str().unwrap().to_string());
+            self.timeouts.lock().unwrap().push(timeout);
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    struct Frontend {
+        backend: test_client::TestClient<Channel>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Frontend {
+        async fn unary_call(&self, req: Request<Input>) -> Result<Response<Output>, Status> {
+            assert!(req.extensions().get::<Deadline>().is_some());
+            assert!(Deadline::current().is_some());
+            self.backend.clone().unary_call(Input {}).await
+        }
+    }
+
+    let timeouts = Timeouts::default();
+    let backend_addr = serve(test_server::TestServer::new(Backend {
+        timeouts: timeouts.clone(),
+    }))
+    .await;
+
+    let backend = test_client::TestClient::connect(format!("http://{}", backend_addr))
+        .await
+        .unwrap()
+        .deadline_margin(Duration::from_millis(200));
+    let frontend_addr = serve(test_server::TestServer::new(Frontend {
+        backend: backend.clone(),
+    }))
+    .await;
+
+    let mut client = test_client::TestClient::connect(format!("http://{}", frontend_addr))
+        .await
+        .unwrap();
+
+    let mut req = Request::new(Input {});
+    req.set_timeout(Duration::from_secs(1));
+    client.unary_call(req).await.unwrap();
+
+    // Calls made outside of a handler carry no deadline
+    backend.clone().unary_call(Input {}).await.unwrap();
+
+    let timeouts = std::mem::take(&mut *timeouts.lock().unwrap());
+    assert_eq!(timeouts.len(), 2);
+    let timeout = timeouts[0].as_deref().unwrap();
+    let micros: u64 = timeout.strip_suffix('u').unwrap().parse().unwrap();
+    assert!(micros > 0 && micros <= 800_000, "grpc-timeout: {}", timeout);
+    assert_eq!(timeouts[1], None);
+}
+
+#[tokio::test]
+async fn fails_locally_when_propagated_deadline_expired() {
+    struct Svc {
+        calls: Arc<Mutex<usize>>,
+    }
+
+    #[tonic::async_trait]
+    impl test_server::Test for Svc {
+        async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+            *self.calls.lock().unwrap() += 1;
+            Ok(Response::new(Output {}))
+        }
+    }
+
+    let calls = Arc::new(Mutex::new(0));
+    let addr = serve(test_server::TestServer::new(Svc {
+        calls: calls.clone(),
+    }))
+    .await;
+
+    let client = test_client::TestClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let mut req = Request::new(Input {});
+    req.extensions_mut()
+        .insert(Deadline::new(std::time::Instant::now()));
+    let status = client.clone().unary_call(req).await.unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+
+    // The margin uses up the rest of the deadline
+    let mut req = Request::new(Input {});
+    req.extensions_mut().insert(Deadline::new(
+        std::time::Instant::now() + Duration::from_secs(1),
+    ));
+    let status = client
+        .deadline_margin(Duration::from_secs(2))
+        .unary_call(req)
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+
+    assert_eq!(*calls.lock().unwrap(), 0);
+}
+
+struct Ticker;
+
+#[tonic::async_trait]
+impl test1_server::Test1 for Ticker {
+    async fn unary_call(&self, _: Request<Input1>) -> Result<Response<Output1>, Status> {
+        unimplemented!()
+    }
+
+    type StreamCallStream = BoxStream<Output1>;
+
+    async fn stream_call(
+        &self,
+        _: Request<Input1>,
+    ) -> Result<Response<Self::StreamCallStream>, Status> {
+        let ticks = tokio_stream::iter(std::iter::repeat(Output1 { buf: vec![1] }))
+            .throttle(Duration::from_millis(50))
+            .map(Ok);
+        Ok(Response::new(Box::pin(ticks)))
+    }
+}
+
+async fn count_until_error(mut stream: tonic::Streaming<Output1>) -> (usize, Status) {
+    let mut count = 0;
+    loop {
+        match stream.message().await {
+            Ok(Some(_)) => count += 1,
+            Ok(None) => panic!("stream ended without an error"),
+            Err(status) => return (count, status),
+        }
+    }
+}
+
+#[tokio::test]
+async fn server_enforces_deadline_on_streams() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test1_server::Test1Server::new(Ticker))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let mut client = test1_client::Test1Client::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let mut req = Request::new(Input1 { buf: vec![] });
+    req.set_timeout(Duration::from_millis(300));
+    let stream = client.stream_call(req).await.unwrap().into_inner();
+
+    let (count, status) = count_until_error(stream).await;
+    assert!(count > 0);
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn endpoint_timeout_covers_streams() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test1_server::Test1Server::new(Ticker))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .timeout(Duration::from_millis(300))
+        .connect()
+        .await
+        .unwrap();
+    let mut client = test1_client::Test1Client::new(channel);
+
+    let stream = client
+        .stream_call(Input1 { buf: vec![] })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let (count, status) = count_until_error(stream).await;
+    assert!(count > 0);
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+}
+
+async fn serve(svc: test_server::TestServer<impl test_server::Test>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
 async fn run_service_in_background(latency: Duration, server_timeout: Duration) -> SocketAddr {
     struct Svc {
         latency: Duration,
@@ -0,0 +1,12 @@
+fn main() {
+    tonic_build::configure()
+        .json_codec()
+        .compile_protos(&["proto/test.proto"], &["proto"])
+        .unwrap();
+
+    tonic_build::configure()
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        .server_codec_path("tonic::codec::JsonCodec")
+        .compile_protos(&["proto/negotiated.proto"], &["proto"])
+        .unwrap();
+}
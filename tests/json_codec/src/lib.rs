@@ -0,0 +1,7 @@
+pub mod pb {
+    tonic::include_proto!("test");
+}
+
+pub mod negotiated {
+    tonic::include_proto!("negotiated");
+}
@@ -0,0 +1,105 @@
+use json_codec::pb::{
+    message::Kind,
+    test_client::TestClient,
+    test_server::{Test, TestServer},
+    Message,
+};
+use std::{future::poll_fn, net::SocketAddr};
+use tokio::net::TcpListener;
+use tonic::{
+    client::Grpc,
+    codec::ProstCodec,
+    codegen::{
+        http::{self, uri::PathAndQuery},
+        Service,
+    },
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl Test for Svc {
+    async fn echo(&self, req: Request<Message>) -> Result<Response<Message>, Status> {
+        Ok(Response::new(req.into_inner()))
+    }
+}
+
+async fn run_service_in_background() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(TestServer::new(Svc))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
+#[tokio::test]
+async fn round_trips_messages_as_json() {
+    let addr = run_service_in_background().await;
+    let mut client = TestClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let message = Message {
+        text: "hello".to_string(),
+        numbers: vec![1, 2, 3],
+        kind: Some(Kind::Label("world".to_string())),
+    };
+    let res = client.echo(message.clone()).await.unwrap();
+
+    assert_eq!(
+        res.metadata().get("content-type").unwrap(),
+        "application/grpc+json"
+    );
+    assert_eq!(res.into_inner(), message);
+}
+
+#[tokio::test]
+async fn rejects_protobuf_requests() {
+    let addr = run_service_in_background().await;
+    let channel = Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+
+    let mut grpc = Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let status = grpc
+        .unary(
+            Request::new(Message::default()),
+            PathAndQuery::from_static("/test.Test/Echo"),
+            ProstCodec::<Message, Message>::default(),
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::Unimplemented);
+}
+
+#[tokio::test]
+async fn answers_protobuf_requests_with_unsupported_media_type() {
+    let mut svc = TestServer::new(Svc);
+
+    let request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("/test.Test/Echo")
+        .header(http::header::CONTENT_TYPE, "application/grpc")
+        .body(tonic::body::BoxBody::default())
+        .unwrap();
+    poll_fn(|cx| Service::<http::Request<tonic::body::BoxBody>>::poll_ready(&mut svc, cx))
+        .await
+        .unwrap();
+    let res = svc.call(request).await.unwrap();
+
+    assert_eq!(res.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(res.headers().get("grpc-status").unwrap(), "12");
+}
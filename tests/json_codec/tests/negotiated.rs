@@ -0,0 +1,106 @@
+use json_codec::negotiated::{
+    negotiated_client::NegotiatedClient,
+    negotiated_server::{Negotiated, NegotiatedServer},
+    Message,
+};
+use std::{future::poll_fn, net::SocketAddr};
+use tokio::net::TcpListener;
+use tonic::{
+    client::Grpc,
+    codec::JsonCodec,
+    codegen::{
+        http::{self, uri::PathAndQuery},
+        Service,
+    },
+    transport::{Channel, Server},
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl Negotiated for Svc {
+    async fn echo(&self, req: Request<Message>) -> Result<Response<Message>, Status> {
+        Ok(Response::new(req.into_inner()))
+    }
+}
+
+async fn run_service_in_background() -> Channel {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(NegotiatedServer::new(Svc))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_protobuf_clients() {
+    let channel = run_service_in_background().await;
+    let mut client = NegotiatedClient::new(channel);
+
+    let message = Message {
+        text: "proto".to_string(),
+    };
+    let res = client.echo(message.clone()).await.unwrap();
+
+    assert_eq!(
+        res.metadata().get("content-type").unwrap(),
+        "application/grpc"
+    );
+    assert_eq!(res.into_inner(), message);
+}
+
+#[tokio::test]
+async fn serves_json_clients() {
+    let channel = run_service_in_background().await;
+    let mut grpc = Grpc::new(channel);
+    grpc.ready().await.unwrap();
+
+    let message = Message {
+        text: "json".to_string(),
+    };
+    let res = grpc
+        .unary(
+            Request::new(message.clone()),
+            PathAndQuery::from_static("/negotiated.Negotiated/Echo"),
+            JsonCodec::<Message, Message>::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        res.metadata().get("content-type").unwrap(),
+        "application/grpc+json"
+    );
+    assert_eq!(res.into_inner(), message);
+}
+
+#[tokio::test]
+async fn rejects_other_subtypes() {
+    let mut svc = NegotiatedServer::new(Svc);
+
+    let request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("/negotiated.Negotiated/Echo")
+        .header(http::header::CONTENT_TYPE, "application/grpc+custom")
+        .body(tonic::body::BoxBody::default())
+        .unwrap();
+    poll_fn(|cx| Service::<http::Request<tonic::body::BoxBody>>::poll_ready(&mut svc, cx))
+        .await
+        .unwrap();
+    let res = svc.call(request).await.unwrap();
+
+    assert_eq!(res.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(res.headers().get("grpc-status").unwrap(), "12");
+}
@@ -0,0 +1,17 @@
+fn main() {
+    tonic_build::configure()
+        .generate_middleware(true)
+        .compile_protos(&["proto/test.proto"], &["proto"])
+        .unwrap();
+    tonic_build::configure()
+        .generate_middleware(true)
+        .use_arc_self(true)
+        .generate_default_stubs(true)
+        .compile_protos(&["proto/test_default.proto"], &["proto"])
+        .unwrap();
+    tonic_build::configure()
+        .generate_middleware(true)
+        .pre_encoded_messages(true)
+        .compile_protos(&["proto/test_pre_encoded.proto"], &["proto"])
+        .unwrap();
+}
@@ -0,0 +1,11 @@
+pub mod pb {
+    tonic::include_proto!("test");
+}
+
+pub mod pb_default {
+    tonic::include_proto!("test_default");
+}
+
+pub mod pb_pre_encoded {
+    tonic::include_proto!("test_pre_encoded");
+}
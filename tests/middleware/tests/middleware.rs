@@ -0,0 +1,217 @@
+use middleware::pb::{
+    test_client::TestClient,
+    test_server::{Test, TestMiddleware, TestServer},
+    Message,
+};
+use std::{
+    any::Any,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use tokio::net::TcpListener;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{
+    transport::{Channel, Server},
+    GrpcMethod, Request, Response, Status, Streaming,
+};
+
+fn message(text: &str) -> Message {
+    Message {
+        text: text.to_string(),
+    }
+}
+
+struct Svc;
+
+type MessageStream = Pin<Box<dyn Stream<Item = Result<Message, Status>> + Send + 'static>>;
+
+#[tonic::async_trait]
+impl Test for Svc {
+    async fn unary(&self, req: Request<Message>) -> Result<Response<Message>, Status> {
+        Ok(Response::new(req.into_inner()))
+    }
+
+    type ServerStreamStream = MessageStream;
+
+    async fn server_stream(
+        &self,
+        req: Request<Message>,
+    ) -> Result<Response<Self::ServerStreamStream>, Status> {
+        let text = req.into_inner().text;
+        let stream = tokio_stream::iter(text.split(' ').map(message).collect::<Vec<_>>());
+        Ok(Response::new(Box::pin(stream.map(Ok))))
+    }
+
+    async fn client_stream(
+        &self,
+        req: Request<Streaming<Message>>,
+    ) -> Result<Response<Message>, Status> {
+        let texts = req
+            .into_inner()
+            .map(|message| message.map(|message| message.text))
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+        Ok(Response::new(message(&texts.join(" "))))
+    }
+
+    type BidirectionalStreamStream = MessageStream;
+
+    async fn bidirectional_stream(
+        &self,
+        req: Request<Streaming<Message>>,
+    ) -> Result<Response<Self::BidirectionalStreamStream>, Status> {
+        Ok(Response::new(Box::pin(req.into_inner())))
+    }
+}
+
+/// Records every message and rejects the ones reading "forbidden".
+#[derive(Default)]
+struct Audit {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Audit {
+    fn check(&self, kind: &str, method: &GrpcMethod<'_>, message: &dyn Any) -> Result<(), Status> {
+        let text = message
+            .downcast_ref::<Message>()
+            .map_or("?", |message| &message.text);
+        let entry = format!("{} {} {}", method.method(), kind, text);
+        self.log.lock().unwrap().push(entry);
+        if text == "forbidden" {
+            return Err(Status::permission_denied("forbidden message"));
+        }
+        Ok(())
+    }
+}
+
+impl TestMiddleware for Audit {
+    fn on_request<M>(&self, method: &GrpcMethod<'_>, request: &M) -> Result<(), Status>
+    where
+        M: Send + Sync + 'static,
+    {
+        self.check("request", method, request)
+    }
+
+    fn on_response<M>(&self, method: &GrpcMethod<'_>, response: &M) -> Result<(), Status>
+    where
+        M: Send + Sync + 'static,
+    {
+        self.check("response", method, response)
+    }
+
+    fn unary_response(&self, response: &Message) -> Result<(), Status> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("Unary response hook {}", response.text));
+        Ok(())
+    }
+}
+
+async fn run_service_in_background() -> (TestClient<Channel>, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+
+    let audit = Audit::default();
+    let log = audit.log.clone();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(TestServer::with_middleware(Svc, audit))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let channel = Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    (TestClient::new(channel), log)
+}
+
+fn take(log: &Mutex<Vec<String>>) -> Vec<String> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+#[tokio::test]
+async fn intercepts_unary_messages() {
+    let (mut client, log) = run_service_in_background().await;
+
+    let res = client.unary(message("hello")).await.unwrap();
+    assert_eq!(res.into_inner(), message("hello"));
+    assert_eq!(
+        take(&log),
+        ["Unary request hello", "Unary response hook hello",]
+    );
+
+    let status = client.unary(message("forbidden")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert_eq!(take(&log), ["Unary request forbidden"]);
+}
+
+#[tokio::test]
+async fn intercepts_stream_items() {
+    let (mut client, log) = run_service_in_background().await;
+
+    let messages = client
+        .server_stream(message("a b"))
+        .await
+        .unwrap()
+        .into_inner()
+        .collect::<Result<Vec<_>, _>>()
+        .await
+        .unwrap();
+    assert_eq!(messages, [message("a"), message("b")]);
+    assert_eq!(
+        take(&log),
+        [
+            "ServerStream request a b",
+            "ServerStream response a",
+            "ServerStream response b",
+        ]
+    );
+
+    let res = client
+        .client_stream(tokio_stream::iter([message("a"), message("b")]))
+        .await
+        .unwrap();
+    assert_eq!(res.into_inner(), message("a b"));
+    assert_eq!(
+        take(&log),
+        [
+            "ClientStream request a",
+            "ClientStream request b",
+            "ClientStream response a b",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rejects_stream_items() {
+    let (mut client, log) = run_service_in_background().await;
+
+    let mut stream = client
+        .bidirectional_stream(tokio_stream::iter([
+            message("a"),
+            message("forbidden"),
+            message("b"),
+        ]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(stream.message().await.unwrap(), Some(message("a")));
+    let status = stream.message().await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert_eq!(
+        take(&log),
+        [
+            "BidirectionalStream request a",
+            "BidirectionalStream response a",
+            "BidirectionalStream request forbidden",
+        ]
+    );
+}
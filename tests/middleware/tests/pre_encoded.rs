@@ -0,0 +1,126 @@
+use middleware::pb_pre_encoded::{
+    test_client::TestClient,
+    test_server::{Test, TestMiddleware, TestServer},
+    Message,
+};
+use prost::Message as _;
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+use tonic::{
+    codec::{EncodeItem, EncodedBytes},
+    codegen::BoxStream,
+    transport::{Channel, Server},
+    Request, Response, Status,
+};
+
+fn message(text: &str) -> Message {
+    Message {
+        text: text.to_string(),
+    }
+}
+
+fn encoded(text: &str) -> EncodeItem<Message> {
+    EncodedBytes::new(message(text).encode_to_vec()).into()
+}
+
+struct Svc;
+
+#[tonic::async_trait]
+impl Test for Svc {
+    async fn unary(&self, req: Request<Message>) -> Result<Response<EncodeItem<Message>>, Status> {
+        let text = req.into_inner().text;
+        let item = match text.strip_prefix("cached ") {
+            Some(text) => encoded(text),
+            None => EncodeItem::new(message(&text)),
+        };
+        Ok(Response::new(item))
+    }
+
+    type ServerStreamStream = BoxStream<EncodeItem<Message>>;
+
+    async fn server_stream(
+        &self,
+        req: Request<Message>,
+    ) -> Result<Response<Self::ServerStreamStream>, Status> {
+        let text = req.into_inner().text;
+        let stream = tokio_stream::iter([
+            Ok(encoded(&text)),
+            Ok(EncodeItem::uncompressed(message(&text))),
+        ]);
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Records the text of every response message.
+#[derive(Default)]
+struct Audit {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestMiddleware for Audit {
+    fn unary_response(&self, response: &Message) -> Result<(), Status> {
+        self.log.lock().unwrap().push(response.text.clone());
+        Ok(())
+    }
+
+    fn server_stream_response(&self, response: &Message) -> Result<(), Status> {
+        self.log.lock().unwrap().push(response.text.clone());
+        Ok(())
+    }
+}
+
+async fn run_service_in_background() -> (TestClient<Channel>, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+
+    let audit = Audit::default();
+    let log = audit.log.clone();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(TestServer::with_middleware(Svc, audit))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let channel = Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    (TestClient::new(channel), log)
+}
+
+fn take(log: &Mutex<Vec<String>>) -> Vec<String> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+#[tokio::test]
+async fn passes_messages_but_not_encoded_bytes_to_hooks() {
+    let (mut client, log) = run_service_in_background().await;
+
+    let res = client.unary(EncodeItem::new(message("a"))).await.unwrap();
+    assert_eq!(res.into_inner(), message("a"));
+    let res = client
+        .unary(EncodeItem::new(message("cached b")))
+        .await
+        .unwrap();
+    assert_eq!(res.into_inner(), message("b"));
+    assert_eq!(take(&log), ["a"]);
+
+    let messages = client
+        .server_stream(EncodeItem::new(message("c")))
+        .await
+        .unwrap()
+        .into_inner()
+        .collect::<Result<Vec<_>, _>>()
+        .await
+        .unwrap();
+    assert_eq!(messages, [message("c"), message("c")]);
+    assert_eq!(take(&log), ["c"]);
+}
@@ -0,0 +1,6 @@
+fn main() {
+    tonic_build::configure()
+        .pre_encoded_messages(true)
+        .compile_protos(&["proto/test.proto"], &["proto"])
+        .unwrap();
+}
@@ -0,0 +1,183 @@
+use http_body_util::{BodyExt, Full};
+use pre_encoded::pb::{
+    test_client::TestClient,
+    test_server::{Test, TestServer},
+    Message,
+};
+use prost::Message as _;
+use std::{future::poll_fn, net::SocketAddr};
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+use tonic::{
+    codec::{CompressionEncoding, EncodeItem, EncodedBytes},
+    codegen::{http, BoxStream, Bytes, Service},
+    transport::{Channel, Server},
+    Request, Response, Status, Streaming,
+};
+
+fn message(text: &str) -> Message {
+    Message {
+        text: text.to_string(),
+    }
+}
+
+fn encoded(text: &str) -> EncodedBytes<Message> {
+    EncodedBytes::new(message(text).encode_to_vec())
+}
+
+struct Svc;
+
+#[tonic::async_trait]
+impl Test for Svc {
+    async fn get(&self, req: Request<Message>) -> Result<Response<EncodeItem<Message>>, Status> {
+        let text = req.into_inner().text;
+        Ok(Response::new(encoded(&format!("cached {}", text)).into()))
+    }
+
+    type ListStream = BoxStream<EncodeItem<Message>>;
+
+    async fn list(&self, req: Request<Message>) -> Result<Response<Self::ListStream>, Status> {
+        let text = req.into_inner().text;
+        let stream = tokio_stream::iter([
+            Ok(encoded(&format!("cached {}", text)).into()),
+            Ok(EncodeItem::uncompressed(message(&text))),
+        ]);
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn collect(
+        &self,
+        req: Request<Streaming<Message>>,
+    ) -> Result<Response<EncodeItem<Message>>, Status> {
+        let texts = req
+            .into_inner()
+            .map(|message| message.map(|message| message.text))
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+        Ok(Response::new(EncodeItem::new(message(&texts.join(" ")))))
+    }
+}
+
+async fn run_service_in_background() -> Channel {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(
+                TestServer::new(Svc)
+                    .accept_compressed(CompressionEncoding::Gzip)
+                    .send_compressed(CompressionEncoding::Gzip),
+            )
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn sends_pre_encoded_responses() {
+    let channel = run_service_in_background().await;
+    let mut client = TestClient::new(channel);
+
+    let res = client.get(EncodeItem::new(message("get"))).await.unwrap();
+    assert_eq!(res.into_inner(), message("cached get"));
+
+    let messages = client
+        .list(EncodeItem::new(message("list")))
+        .await
+        .unwrap()
+        .into_inner()
+        .collect::<Result<Vec<_>, _>>()
+        .await
+        .unwrap();
+    assert_eq!(messages, [message("cached list"), message("list")]);
+}
+
+#[tokio::test]
+async fn sends_pre_encoded_requests() {
+    let channel = run_service_in_background().await;
+    let mut client = TestClient::new(channel);
+
+    let res = client
+        .get(EncodeItem::encoded(encoded("get")))
+        .await
+        .unwrap();
+    assert_eq!(res.into_inner(), message("cached get"));
+
+    let requests = tokio_stream::iter([
+        EncodeItem::from(encoded("a")),
+        EncodeItem::new(message("b")),
+    ]);
+    let res = client.collect(requests).await.unwrap();
+    assert_eq!(res.into_inner(), message("a b"));
+}
+
+#[tokio::test]
+async fn compresses_pre_encoded_messages() {
+    let channel = run_service_in_background().await;
+    let mut client = TestClient::new(channel)
+        .send_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Gzip);
+
+    let res = client
+        .get(EncodeItem::encoded(encoded("get")))
+        .await
+        .unwrap();
+    assert_eq!(res.metadata().get("grpc-encoding").unwrap(), "gzip");
+    assert_eq!(res.into_inner(), message("cached get"));
+
+    let messages = client
+        .list(EncodeItem::encoded(encoded("list")))
+        .await
+        .unwrap()
+        .into_inner()
+        .collect::<Result<Vec<_>, _>>()
+        .await
+        .unwrap();
+    assert_eq!(messages, [message("cached list"), message("list")]);
+}
+
+#[tokio::test]
+async fn sends_uncompressed_stream_items() {
+    let mut svc = TestServer::new(Svc).send_compressed(CompressionEncoding::Gzip);
+
+    let request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("/test.Test/List")
+        .header(http::header::CONTENT_TYPE, "application/grpc")
+        .header("grpc-accept-encoding", "gzip")
+        .body(Full::new(frame(0, &message("list").encode_to_vec())))
+        .unwrap();
+    poll_fn(|cx| Service::<http::Request<Full<Bytes>>>::poll_ready(&mut svc, cx))
+        .await
+        .unwrap();
+    let response = svc.call(request).await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    // The cached message is compressed like the stream, the other one is marked uncompressed.
+    assert_eq!(compression_flags(&body), [1, 0]);
+}
+
+fn frame(compression_flag: u8, message: &[u8]) -> Bytes {
+    let mut frame = vec![compression_flag];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(message);
+    frame.into()
+}
+
+fn compression_flags(mut body: &[u8]) -> Vec<u8> {
+    let mut flags = Vec::new();
+    while !body.is_empty() {
+        let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+        flags.push(body[0]);
+        body = &body[5 + len..];
+    }
+    flags
+}
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use super::{Attributes, Method, Service};
 use crate::{
     format_method_name, format_method_path, format_service_name, generate_deprecated,
-    generate_doc_comments, naive_snake_case,
+    generate_doc_comments, naive_snake_case, pre_encoded_codec, pre_encoded_message,
 };
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -95,6 +95,20 @@ pub(crate) fn generate_internal<T: Service>(
                     #service_ident::new(InterceptedService::new(inner, interceptor))
                 }
 
+                pub fn with_async_interceptor<F>(inner: T, interceptor: F) -> #service_ident<AsyncInterceptedService<T, F>>
+                where
+                    F: tonic::service::AsyncInterceptor + Clone + std::marker::Send + 'static,
+                    F::Future: std::marker::Send,
+                    T: tonic::codegen::Service<
+                        http::Request<tonic::body::BoxBody>,
+                        Response = http::Response<<T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody>
+                    > + Clone + std::marker::Send + 'static,
+                    <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Future: std::marker::Send,
+                    <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+                {
+                    #service_ident::new(AsyncInterceptedService::new(inner, interceptor))
+                }
+
                 /// Compress requests with the given encoding.
                 ///
                 /// This requires the server to support it otherwise it might respond with an
@@ -105,6 +119,23 @@ pub(crate) fn generate_internal<T: Service>(
                     self
                 }
 
+                /// Compress requests with the given encoding and level.
+                ///
+                /// This requires the server to support it otherwise it might respond with an
+                /// error.
+                #[must_use]
+                pub fn send_compressed_with_level(mut self, encoding: CompressionEncoding, level: CompressionLevel) -> Self {
+                    self.inner = self.inner.send_compressed_with_level(encoding, level);
+                    self
+                }
+
+                /// Send requests smaller than `min_size` bytes uncompressed.
+                #[must_use]
+                pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+                    self.inner = self.inner.send_compressed_min_size(min_size);
+                    self
+                }
+
                 /// Enable decompressing responses.
                 #[must_use]
                 pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
@@ -130,6 +161,15 @@ pub(crate) fn generate_internal<T: Service>(
                     self
                 }
 
+                /// Leave `margin` of a propagated deadline before calls time out.
+                ///
+                /// Default: zero
+                #[must_use]
+                pub fn deadline_margin(mut self, margin: std::time::Duration) -> Self {
+                    self.inner = self.inner.deadline_margin(margin);
+                    self
+                }
+
                 #methods
             }
         }
@@ -228,6 +268,8 @@ fn generate_unary<T: Service>(
     let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
     let ident = format_ident!("{}", method.name());
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, quote!(#codec_name::default()), &request, &response);
+    let request = pre_encoded_message(method, request);
     let service_name = format_service_name(service, emit_package);
     let path = format_method_path(service, method, emit_package);
     let method_name = method.identifier();
@@ -240,7 +282,7 @@ fn generate_unary<T: Service>(
            self.inner.ready().await.map_err(|e| {
                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
            })?;
-           let codec = #codec_name::default();
+           let codec = #codec;
            let path = http::uri::PathAndQuery::from_static(#path);
            let mut req = request.into_request();
            req.extensions_mut().insert(GrpcMethod::new(#service_name, #method_name));
@@ -259,6 +301,8 @@ fn generate_server_streaming<T: Service>(
     let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
     let ident = format_ident!("{}", method.name());
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, quote!(#codec_name::default()), &request, &response);
+    let request = pre_encoded_message(method, request);
     let service_name = format_service_name(service, emit_package);
     let path = format_method_path(service, method, emit_package);
     let method_name = method.identifier();
@@ -271,7 +315,7 @@ fn generate_server_streaming<T: Service>(
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
-            let codec = #codec_name::default();
+            let codec = #codec;
             let path = http::uri::PathAndQuery::from_static(#path);
             let mut req = request.into_request();
             req.extensions_mut().insert(GrpcMethod::new(#service_name, #method_name));
@@ -290,6 +334,8 @@ fn generate_client_streaming<T: Service>(
     let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
     let ident = format_ident!("{}", method.name());
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, quote!(#codec_name::default()), &request, &response);
+    let request = pre_encoded_message(method, request);
     let service_name = format_service_name(service, emit_package);
     let path = format_method_path(service, method, emit_package);
     let method_name = method.identifier();
@@ -302,7 +348,7 @@ fn generate_client_streaming<T: Service>(
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
-            let codec = #codec_name::default();
+            let codec = #codec;
             let path = http::uri::PathAndQuery::from_static(#path);
             let mut req = request.into_streaming_request();
             req.extensions_mut().insert(GrpcMethod::new(#service_name, #method_name));
@@ -321,6 +367,8 @@ fn generate_streaming<T: Service>(
     let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
     let ident = format_ident!("{}", method.name());
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, quote!(#codec_name::default()), &request, &response);
+    let request = pre_encoded_message(method, request);
     let service_name = format_service_name(service, emit_package);
     let path = format_method_path(service, method, emit_package);
     let method_name = method.identifier();
@@ -333,7 +381,7 @@ fn generate_streaming<T: Service>(
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
-            let codec = #codec_name::default();
+            let codec = #codec;
             let path = http::uri::PathAndQuery::from_static(#path);
             let mut req = request.into_streaming_request();
             req.extensions_mut().insert(GrpcMethod::new(#service_name,#method_name));
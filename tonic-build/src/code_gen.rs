@@ -14,6 +14,7 @@ pub struct CodeGenBuilder {
     disable_comments: HashSet<String>,
     use_arc_self: bool,
     generate_default_stubs: bool,
+    generate_middleware: bool,
 }
 
 impl CodeGenBuilder {
@@ -71,6 +72,13 @@ impl CodeGenBuilder {
         self
     }
 
+    /// Enable or disable generating a middleware trait with hooks that see the messages of each
+    /// method of a service.
+    pub fn generate_middleware(&mut self, generate_middleware: bool) -> &mut Self {
+        self.generate_middleware = generate_middleware;
+        self
+    }
+
     /// Generate client code based on `Service`.
     ///
     /// This takes some `Service` and will generate a `TokenStream` that contains
@@ -101,6 +109,7 @@ impl CodeGenBuilder {
             &self.disable_comments,
             self.use_arc_self,
             self.generate_default_stubs,
+            self.generate_middleware,
         )
     }
 }
@@ -115,6 +124,7 @@ impl Default for CodeGenBuilder {
             disable_comments: HashSet::default(),
             use_arc_self: false,
             generate_default_stubs: false,
+            generate_middleware: false,
         }
     }
 }
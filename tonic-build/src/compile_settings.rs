@@ -2,6 +2,10 @@
 pub(crate) struct CompileSettings {
     #[cfg(feature = "prost")]
     pub(crate) codec_path: String,
+    #[cfg(feature = "prost")]
+    pub(crate) server_codec_paths: Vec<String>,
+    #[cfg(feature = "prost")]
+    pub(crate) pre_encoded: bool,
 }
 
 impl Default for CompileSettings {
@@ -9,6 +13,10 @@ impl Default for CompileSettings {
         Self {
             #[cfg(feature = "prost")]
             codec_path: "tonic::codec::ProstCodec".to_string(),
+            #[cfg(feature = "prost")]
+            server_codec_paths: Vec::new(),
+            #[cfg(feature = "prost")]
+            pre_encoded: false,
         }
     }
 }
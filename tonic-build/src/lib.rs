@@ -77,7 +77,7 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
 use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream};
-use quote::TokenStreamExt;
+use quote::{quote, TokenStreamExt};
 
 /// Prost generator
 #[cfg(feature = "prost")]
@@ -143,6 +143,10 @@ pub trait Method {
     fn identifier(&self) -> &str;
     /// Path to the codec.
     fn codec_path(&self) -> &str;
+    /// Paths to additional codecs servers accept, selected by the content subtype of requests.
+    fn server_codec_paths(&self) -> &[String] {
+        &[]
+    }
     /// Method is streamed by client.
     fn client_streaming(&self) -> bool;
     /// Method is streamed by server.
@@ -153,6 +157,11 @@ pub trait Method {
     fn deprecated(&self) -> bool {
         false
     }
+    /// Messages sent by generated code are wrapped in `tonic::codec::EncodeItem`, which allows
+    /// sending them already encoded.
+    fn pre_encoded(&self) -> bool {
+        false
+    }
     /// Type name of request and response.
     fn request_response_name(
         &self,
@@ -297,6 +306,30 @@ fn generate_doc_comments<T: AsRef<str>>(comments: &[T]) -> TokenStream {
     stream
 }
 
+// Wrap the type of messages sent by a method in `EncodeItem` if they may be pre-encoded
+fn pre_encoded_message<T: Method>(method: &T, message: TokenStream) -> TokenStream {
+    if method.pre_encoded() {
+        quote!(tonic::codec::EncodeItem<#message>)
+    } else {
+        message
+    }
+}
+
+// Pin the message types of the codec of a method sending pre-encoded messages, which can't be
+// inferred from an `EncodeItem`
+fn pre_encoded_codec<T: Method>(
+    method: &T,
+    codec: TokenStream,
+    encode: &TokenStream,
+    decode: &TokenStream,
+) -> TokenStream {
+    if method.pre_encoded() {
+        quote!(tonic::codegen::pin_codec::<#encode, #decode, _>(#codec))
+    } else {
+        codec
+    }
+}
+
 // Checks whether a path pattern matches a given path.
 pub(crate) fn match_name(pattern: &str, path: &str) -> bool {
     if pattern.is_empty() {
@@ -177,6 +177,10 @@ pub struct Method {
     deprecated: bool,
     /// The path to the codec to use for this method
     codec_path: String,
+    /// The paths to additional codecs servers accept for this method
+    server_codec_paths: Vec<String>,
+    /// Identifies if sent messages may be pre-encoded.
+    pre_encoded: bool,
 }
 
 impl Method {
@@ -201,6 +205,14 @@ impl crate::Method for Method {
         &self.codec_path
     }
 
+    fn server_codec_paths(&self) -> &[String] {
+        &self.server_codec_paths
+    }
+
+    fn pre_encoded(&self) -> bool {
+        self.pre_encoded
+    }
+
     fn client_streaming(&self) -> bool {
         self.client_streaming
     }
@@ -272,6 +284,10 @@ pub struct MethodBuilder {
     deprecated: bool,
     /// The path to the codec to use for this method
     codec_path: Option<String>,
+    /// The paths to additional codecs servers accept for this method
+    server_codec_paths: Vec<String>,
+    /// Identifies if sent messages may be pre-encoded.
+    pre_encoded: bool,
 }
 
 impl MethodBuilder {
@@ -322,6 +338,15 @@ impl MethodBuilder {
         self
     }
 
+    /// Add the path to a `Codec` that servers accept for this method besides `codec_path`,
+    /// selected by the content subtype of the request.
+    ///
+    /// Currently the codegen assumes that this type implements `Default`.
+    pub fn server_codec_path(mut self, codec_path: impl AsRef<str>) -> Self {
+        self.server_codec_paths.push(codec_path.as_ref().to_owned());
+        self
+    }
+
     /// Sets if the Method request from the client is streamed.
     pub fn client_streaming(mut self) -> Self {
         self.client_streaming = true;
@@ -334,6 +359,13 @@ impl MethodBuilder {
         self
     }
 
+    /// Sets if the Method sends messages wrapped in `tonic::codec::EncodeItem`, so they can be
+    /// pre-encoded.
+    pub fn pre_encoded(mut self) -> Self {
+        self.pre_encoded = true;
+        self
+    }
+
     /// Build a Method
     ///
     /// Panics if `name`, `route_name`, `input_type`, `output_type`, or `codec_path` weren't set.
@@ -348,6 +380,8 @@ impl MethodBuilder {
             server_streaming: self.server_streaming,
             deprecated: self.deprecated,
             codec_path: self.codec_path.unwrap(),
+            server_codec_paths: self.server_codec_paths,
+            pre_encoded: self.pre_encoded,
         }
     }
 }
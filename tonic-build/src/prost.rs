@@ -41,6 +41,7 @@ pub fn configure() -> Builder {
         disable_comments: HashSet::default(),
         use_arc_self: false,
         generate_default_stubs: false,
+        generate_middleware: false,
         compile_settings: CompileSettings::default(),
         skip_debug: HashSet::default(),
     }
@@ -148,6 +149,14 @@ impl crate::Method for TonicBuildMethod {
         &self.settings.codec_path
     }
 
+    fn server_codec_paths(&self) -> &[String] {
+        &self.settings.server_codec_paths
+    }
+
+    fn pre_encoded(&self) -> bool {
+        self.settings.pre_encoded
+    }
+
     fn client_streaming(&self) -> bool {
         self.prost_method.client_streaming
     }
@@ -228,6 +237,7 @@ impl prost_build::ServiceGenerator for ServiceGenerator {
                 .disable_comments(self.builder.disable_comments.clone())
                 .use_arc_self(self.builder.use_arc_self)
                 .generate_default_stubs(self.builder.generate_default_stubs)
+                .generate_middleware(self.builder.generate_middleware)
                 .generate_server(
                     &TonicBuildService::new(service.clone(), self.builder.compile_settings.clone()),
                     &self.builder.proto_path,
@@ -310,6 +320,7 @@ pub struct Builder {
     pub(crate) disable_comments: HashSet<String>,
     pub(crate) use_arc_self: bool,
     pub(crate) generate_default_stubs: bool,
+    pub(crate) generate_middleware: bool,
     pub(crate) compile_settings: CompileSettings,
     pub(crate) skip_debug: HashSet<String>,
 
@@ -584,6 +595,20 @@ impl Builder {
         self
     }
 
+    /// Enable or disable generating a middleware trait for each service, along with an
+    /// implementation of the service trait that passes the messages of every method to it.
+    ///
+    /// The middleware trait of a service `Greeter` is named `GreeterMiddleware`. Its hooks see the
+    /// `GrpcMethod` and typed request and response messages, including each item of streams, and
+    /// can reject them with a `Status`. Servers using a middleware are created with
+    /// `GreeterServer::with_middleware`.
+    ///
+    /// This defaults to `false`.
+    pub fn generate_middleware(mut self, enable: bool) -> Self {
+        self.generate_middleware = enable;
+        self
+    }
+
     /// Override the default codec.
     ///
     /// If set, writes `{codec_path}::default()` in generated code wherever a codec is created.
@@ -594,6 +619,43 @@ impl Builder {
         self
     }
 
+    /// Add a codec that generated servers accept besides the one set by
+    /// [`codec_path`](Self::codec_path).
+    ///
+    /// Servers select the codec for each request by the subtype of its `content-type`, as in
+    /// `application/grpc+json`, and reject subtypes none of the codecs accept. Generated clients
+    /// keep using `codec_path`.
+    pub fn server_codec_path(mut self, codec_path: impl Into<String>) -> Self {
+        self.compile_settings
+            .server_codec_paths
+            .push(codec_path.into());
+        self
+    }
+
+    /// Enable or disable sending pre-encoded messages from generated clients and servers.
+    ///
+    /// If enabled, server methods return responses and client methods take requests wrapped in
+    /// `tonic::codec::EncodeItem`, which holds either a message or a `tonic::codec::EncodedBytes`
+    /// of it, sent without invoking the codec. This is useful to send cached messages.
+    ///
+    /// This defaults to `false`.
+    pub fn pre_encoded_messages(mut self, enable: bool) -> Self {
+        self.compile_settings.pre_encoded = enable;
+        self
+    }
+
+    /// Generate clients and servers that use `tonic::codec::JsonCodec`, exchanging messages as
+    /// `application/grpc+json`.
+    ///
+    /// This derives `serde::Serialize` and `serde::Deserialize` for every generated type, so the
+    /// crate including the generated code must depend on `serde` (with the `derive` feature) and
+    /// enable the `json` feature of `tonic`. Well-known types from `prost-types` do not implement
+    /// the serde traits and can't be used by messages compiled this way.
+    pub fn json_codec(self) -> Self {
+        self.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+            .codec_path("tonic::codec::JsonCodec")
+    }
+
     /// Skips generating `impl Debug` for types
     pub fn skip_debug(mut self, path: impl AsRef<str>) -> Self {
         self.skip_debug.insert(path.as_ref().to_string());
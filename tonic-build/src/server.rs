@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use super::{Attributes, Method, Service};
 use crate::{
     format_method_name, format_method_path, format_service_name, generate_doc_comment,
-    generate_doc_comments, naive_snake_case,
+    generate_doc_comments, naive_snake_case, pre_encoded_codec, pre_encoded_message,
 };
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
@@ -19,6 +19,7 @@ pub(crate) fn generate_internal<T: Service>(
     disable_comments: &HashSet<String>,
     use_arc_self: bool,
     generate_default_stubs: bool,
+    generate_middleware: bool,
 ) -> TokenStream {
     let methods = generate_methods(
         service,
@@ -46,6 +47,33 @@ pub(crate) fn generate_internal<T: Service>(
     // Transport based implementations
     let service_name = format_service_name(service, emit_package);
 
+    let (generated_middleware, with_middleware) = if generate_middleware {
+        let middleware_trait = quote::format_ident!("{}Middleware", service.name());
+        let middleware_service = quote::format_ident!("{}WithMiddleware", service.name());
+        let generated_middleware = generate_middleware_trait(
+            service,
+            emit_package,
+            proto_path,
+            compile_well_known_types,
+            &server_trait,
+            &middleware_trait,
+            &middleware_service,
+            use_arc_self,
+            generate_default_stubs,
+        );
+        let with_middleware = quote! {
+            pub fn with_middleware<M>(inner: T, middleware: M) -> #server_service<#middleware_service<T, M>>
+            where
+                M: #middleware_trait,
+            {
+                #server_service::new(#middleware_service::new(inner, middleware))
+            }
+        };
+        (generated_middleware, with_middleware)
+    } else {
+        (TokenStream::new(), TokenStream::new())
+    };
+
     let service_doc = if disable_comments.contains(&service_name) {
         TokenStream::new()
     } else {
@@ -70,6 +98,20 @@ pub(crate) fn generate_internal<T: Service>(
             self.send_compression_encodings.enable(encoding);
             self
         }
+
+        /// Compress responses with the given encoding and level, if the client supports it.
+        #[must_use]
+        pub fn send_compressed_with_level(mut self, encoding: CompressionEncoding, level: CompressionLevel) -> Self {
+            self.send_compression_encodings.enable_with_level(encoding, level);
+            self
+        }
+
+        /// Send responses smaller than `min_size` bytes uncompressed.
+        #[must_use]
+        pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+            self.send_compression_encodings.set_min_size(min_size);
+            self
+        }
     };
 
     let configure_max_message_size_methods = quote! {
@@ -108,6 +150,8 @@ pub(crate) fn generate_internal<T: Service>(
 
             #generated_trait
 
+            #generated_middleware
+
             #service_doc
             #(#struct_attributes)*
             #[derive(Debug)]
@@ -141,6 +185,15 @@ pub(crate) fn generate_internal<T: Service>(
                     InterceptedService::new(Self::new(inner), interceptor)
                 }
 
+                pub fn with_async_interceptor<F>(inner: T, interceptor: F) -> AsyncInterceptedService<Self, F>
+                where
+                    F: tonic::service::AsyncInterceptor,
+                {
+                    AsyncInterceptedService::new(Self::new(inner), interceptor)
+                }
+
+                #with_middleware
+
                 #configure_compression_methods
 
                 #configure_max_message_size_methods
@@ -180,8 +233,8 @@ pub(crate) fn generate_internal<T: Service>(
                     let inner = self.inner.clone();
                     Self {
                         inner,
-                        accept_compression_encodings: self.accept_compression_encodings,
-                        send_compression_encodings: self.send_compression_encodings,
+                        accept_compression_encodings: self.accept_compression_encodings.clone(),
+                        send_compression_encodings: self.send_compression_encodings.clone(),
                         max_decoding_message_size: self.max_decoding_message_size,
                         max_encoding_message_size: self.max_encoding_message_size,
                     }
@@ -227,6 +280,204 @@ fn generate_trait<T: Service>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+fn generate_middleware_trait<T: Service>(
+    service: &T,
+    emit_package: bool,
+    proto_path: &str,
+    compile_well_known_types: bool,
+    server_trait: &Ident,
+    middleware_trait: &Ident,
+    middleware_service: &Ident,
+    use_arc_self: bool,
+    generate_default_stubs: bool,
+) -> TokenStream {
+    let service_name = format_service_name(service, emit_package);
+    let mut hooks = TokenStream::new();
+    let mut methods = TokenStream::new();
+
+    for method in service.methods() {
+        let name = quote::format_ident!("{}", method.name());
+        let request_hook = quote::format_ident!("{}_request", method.name());
+        let response_hook = quote::format_ident!("{}_response", method.name());
+        let method_name = method.identifier();
+
+        let (req_message, res_plain) =
+            method.request_response_name(proto_path, compile_well_known_types);
+        let res_message = pre_encoded_message(method, res_plain.clone());
+
+        // Pre-encoded responses are only seen by the hook if they hold a message.
+        let inspect_response =
+            |middleware: TokenStream, item: TokenStream, message: TokenStream| {
+                if method.pre_encoded() {
+                    quote! {
+                        if let Some(message) = #item.get_ref() {
+                            #middleware.#response_hook(message)?;
+                        }
+                    }
+                } else {
+                    quote!(#middleware.#response_hook(#message)?;)
+                }
+            };
+        let inspect_stream_response =
+            inspect_response(quote!(middleware), quote!(message), quote!(&message));
+        let inspect_unary_response = inspect_response(
+            quote!(self.middleware),
+            quote!(response.get_ref()),
+            quote!(response.get_ref()),
+        );
+
+        let request_hook_doc = generate_doc_comment(format!(
+            " Intercept each request message of the {} method.",
+            method_name
+        ));
+        let response_hook_doc = generate_doc_comment(format!(
+            " Intercept each response message of the {} method.",
+            method_name
+        ));
+        hooks.extend(quote! {
+            #request_hook_doc
+            fn #request_hook(&self, request: &#req_message) -> std::result::Result<(), tonic::Status> {
+                self.on_request(&GrpcMethod::new(#service_name, #method_name), request)
+            }
+
+            #response_hook_doc
+            fn #response_hook(&self, response: &#res_plain) -> std::result::Result<(), tonic::Status> {
+                self.on_response(&GrpcMethod::new(#service_name, #method_name), response)
+            }
+        });
+
+        let self_param = if use_arc_self {
+            quote!(self: std::sync::Arc<Self>)
+        } else {
+            quote!(&self)
+        };
+        let inner_arg = if use_arc_self {
+            quote!(Arc::clone(&self.inner))
+        } else {
+            quote!(&self.inner)
+        };
+
+        let (request, intercept_request) = if method.client_streaming() {
+            (
+                quote!(tonic::Streaming<#req_message>),
+                quote! {
+                    let middleware = Arc::clone(&self.middleware);
+                    let request = request.map(|stream| {
+                        stream.inspect_messages(move |message| middleware.#request_hook(message))
+                    });
+                },
+            )
+        } else {
+            (
+                quote!(#req_message),
+                quote!(self.middleware.#request_hook(request.get_ref())?;),
+            )
+        };
+
+        let method = if method.server_streaming() {
+            let (stream_type, response_stream) = if generate_default_stubs {
+                (TokenStream::new(), quote!(BoxStream<#res_message>))
+            } else {
+                let stream = quote::format_ident!("{}Stream", method.identifier());
+                (
+                    quote!(type #stream = BoxStream<#res_message>;),
+                    quote!(Self::#stream),
+                )
+            };
+
+            quote! {
+                #stream_type
+
+                async fn #name(#self_param, request: tonic::Request<#request>)
+                    -> std::result::Result<tonic::Response<#response_stream>, tonic::Status> {
+                    #intercept_request
+                    let response = <T as #server_trait>::#name(#inner_arg, request).await?;
+                    let middleware = Arc::clone(&self.middleware);
+                    Ok(response.map(|stream| {
+                        let stream = tokio_stream::StreamExt::map(stream, move |message| {
+                            let message = message?;
+                            #inspect_stream_response
+                            Ok(message)
+                        });
+                        Box::pin(stream) as BoxStream<#res_message>
+                    }))
+                }
+            }
+        } else {
+            quote! {
+                async fn #name(#self_param, request: tonic::Request<#request>)
+                    -> std::result::Result<tonic::Response<#res_message>, tonic::Status> {
+                    #intercept_request
+                    let response = <T as #server_trait>::#name(#inner_arg, request).await?;
+                    #inspect_unary_response
+                    Ok(response)
+                }
+            }
+        };
+
+        methods.extend(method);
+    }
+
+    let trait_doc = generate_doc_comment(format!(
+        " Generated trait containing hooks that see the messages of each gRPC method, for use with {}.",
+        middleware_service
+    ));
+    let service_doc = generate_doc_comment(format!(
+        " An implementation of {} passing its messages to a {}.",
+        server_trait, middleware_trait
+    ));
+
+    quote! {
+        #trait_doc
+        ///
+        /// The hooks of every method call `on_request` and `on_response` by default, so a single
+        /// implementation of those can cover the whole service. An error returned by a hook is
+        /// returned in place of the message. Responses sent already encoded are not passed to
+        /// the hooks.
+        pub trait #middleware_trait : std::marker::Send + std::marker::Sync + 'static {
+            /// Intercept each request message of the service.
+            fn on_request<M>(&self, method: &GrpcMethod<'_>, request: &M) -> std::result::Result<(), tonic::Status>
+            where
+                M: std::marker::Send + std::marker::Sync + 'static,
+            {
+                Ok(())
+            }
+
+            /// Intercept each response message of the service.
+            fn on_response<M>(&self, method: &GrpcMethod<'_>, response: &M) -> std::result::Result<(), tonic::Status>
+            where
+                M: std::marker::Send + std::marker::Sync + 'static,
+            {
+                Ok(())
+            }
+
+            #hooks
+        }
+
+        #service_doc
+        #[derive(Debug)]
+        pub struct #middleware_service<T, M> {
+            inner: Arc<T>,
+            middleware: Arc<M>,
+        }
+
+        impl<T, M> #middleware_service<T, M> {
+            pub fn new(inner: T, middleware: M) -> Self {
+                Self {
+                    inner: Arc::new(inner),
+                    middleware: Arc::new(middleware),
+                }
+            }
+        }
+
+        #[async_trait]
+        impl<T: #server_trait, M: #middleware_trait> #server_trait for #middleware_service<T, M> {
+            #methods
+        }
+    }
+}
+
 fn generate_trait_methods<T: Service>(
     service: &T,
     emit_package: bool,
@@ -243,6 +494,7 @@ fn generate_trait_methods<T: Service>(
 
         let (req_message, res_message) =
             method.request_response_name(proto_path, compile_well_known_types);
+        let res_message = pre_encoded_message(method, res_message);
 
         let method_doc =
             if disable_comments.contains(&format_method_name(service, method, emit_package)) {
@@ -432,6 +684,18 @@ fn generate_methods<T: Service>(
     stream
 }
 
+fn generate_codec<T: Method>(method: &T) -> TokenStream {
+    std::iter::once(method.codec_path())
+        .chain(method.server_codec_paths().iter().map(String::as_str))
+        .rev()
+        .map(|codec_path| {
+            let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
+            quote!(#codec_name::default())
+        })
+        .reduce(|codec, preferred| quote!(tonic::codec::NegotiatedCodec::new(#preferred, #codec)))
+        .unwrap()
+}
+
 fn generate_unary<T: Method>(
     method: &T,
     proto_path: &str,
@@ -440,11 +704,13 @@ fn generate_unary<T: Method>(
     server_trait: Ident,
     use_arc_self: bool,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
+    let codec = generate_codec(method);
 
     let service_ident = quote::format_ident!("{}Svc", method.identifier());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, codec, &response, &request);
+    let response = pre_encoded_message(method, response);
 
     let inner_arg = if use_arc_self {
         quote!(inner)
@@ -469,14 +735,14 @@ fn generate_unary<T: Method>(
             }
         }
 
-        let accept_compression_encodings = self.accept_compression_encodings;
-        let send_compression_encodings = self.send_compression_encodings;
+        let accept_compression_encodings = self.accept_compression_encodings.clone();
+        let send_compression_encodings = self.send_compression_encodings.clone();
         let max_decoding_message_size = self.max_decoding_message_size;
         let max_encoding_message_size = self.max_encoding_message_size;
         let inner = self.inner.clone();
         let fut = async move {
             let method = #service_ident(inner);
-            let codec = #codec_name::default();
+            let codec = #codec;
 
             let mut grpc = tonic::server::Grpc::new(codec)
                 .apply_compression_config(accept_compression_encodings, send_compression_encodings)
@@ -499,11 +765,13 @@ fn generate_server_streaming<T: Method>(
     use_arc_self: bool,
     generate_default_stubs: bool,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
+    let codec = generate_codec(method);
 
     let service_ident = quote::format_ident!("{}Svc", method.identifier());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, codec, &response, &request);
+    let response = pre_encoded_message(method, response);
 
     let response_stream = if !generate_default_stubs {
         let stream = quote::format_ident!("{}Stream", method.identifier());
@@ -536,14 +804,14 @@ fn generate_server_streaming<T: Method>(
             }
         }
 
-        let accept_compression_encodings = self.accept_compression_encodings;
-        let send_compression_encodings = self.send_compression_encodings;
+        let accept_compression_encodings = self.accept_compression_encodings.clone();
+        let send_compression_encodings = self.send_compression_encodings.clone();
         let max_decoding_message_size = self.max_decoding_message_size;
         let max_encoding_message_size = self.max_encoding_message_size;
         let inner = self.inner.clone();
         let fut = async move {
             let method = #service_ident(inner);
-            let codec = #codec_name::default();
+            let codec = #codec;
 
             let mut grpc = tonic::server::Grpc::new(codec)
                 .apply_compression_config(accept_compression_encodings, send_compression_encodings)
@@ -567,8 +835,10 @@ fn generate_client_streaming<T: Method>(
 ) -> TokenStream {
     let service_ident = quote::format_ident!("{}Svc", method.identifier());
 
+    let codec = generate_codec(method);
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
-    let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
+    let codec = pre_encoded_codec(method, codec, &response, &request);
+    let response = pre_encoded_message(method, response);
 
     let inner_arg = if use_arc_self {
         quote!(inner)
@@ -594,14 +864,14 @@ fn generate_client_streaming<T: Method>(
             }
         }
 
-        let accept_compression_encodings = self.accept_compression_encodings;
-        let send_compression_encodings = self.send_compression_encodings;
+        let accept_compression_encodings = self.accept_compression_encodings.clone();
+        let send_compression_encodings = self.send_compression_encodings.clone();
         let max_decoding_message_size = self.max_decoding_message_size;
         let max_encoding_message_size = self.max_encoding_message_size;
         let inner = self.inner.clone();
         let fut = async move {
             let method = #service_ident(inner);
-            let codec = #codec_name::default();
+            let codec = #codec;
 
             let mut grpc = tonic::server::Grpc::new(codec)
                 .apply_compression_config(accept_compression_encodings, send_compression_encodings)
@@ -624,11 +894,13 @@ fn generate_streaming<T: Method>(
     use_arc_self: bool,
     generate_default_stubs: bool,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(method.codec_path()).unwrap();
+    let codec = generate_codec(method);
 
     let service_ident = quote::format_ident!("{}Svc", method.identifier());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+    let codec = pre_encoded_codec(method, codec, &response, &request);
+    let response = pre_encoded_message(method, response);
 
     let response_stream = if !generate_default_stubs {
         let stream = quote::format_ident!("{}Stream", method.identifier());
@@ -662,14 +934,14 @@ fn generate_streaming<T: Method>(
             }
         }
 
-        let accept_compression_encodings = self.accept_compression_encodings;
-        let send_compression_encodings = self.send_compression_encodings;
+        let accept_compression_encodings = self.accept_compression_encodings.clone();
+        let send_compression_encodings = self.send_compression_encodings.clone();
         let max_decoding_message_size = self.max_decoding_message_size;
         let max_encoding_message_size = self.max_encoding_message_size;
         let inner = self.inner.clone();
         let fut = async move {
             let method = #service_ident(inner);
-            let codec = #codec_name::default();
+            let codec = #codec;
 
             let mut grpc = tonic::server::Grpc::new(codec)
                 .apply_compression_config(accept_compression_encodings, send_compression_encodings)
@@ -104,6 +104,30 @@ pub mod health_client {
         {
             HealthClient::new(InterceptedService::new(inner, interceptor))
         }
+        pub fn with_async_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> HealthClient<AsyncInterceptedService<T, F>>
+        where
+            F: tonic::service::AsyncInterceptor + Clone + std::marker::Send + 'static,
+            F::Future: std::marker::Send,
+            T: tonic::codegen::Service<
+                    http::Request<tonic::body::BoxBody>,
+                    Response = http::Response<
+                        <T as tonic::client::GrpcService<
+                            tonic::body::BoxBody,
+                        >>::ResponseBody,
+                    >,
+                > + Clone + std::marker::Send + 'static,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Future: std::marker::Send,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            HealthClient::new(AsyncInterceptedService::new(inner, interceptor))
+        }
         /// Compress requests with the given encoding.
         ///
         /// This requires the server to support it otherwise it might respond with an
@@ -113,6 +137,25 @@ pub mod health_client {
             self.inner = self.inner.send_compressed(encoding);
             self
         }
+        /// Compress requests with the given encoding and level.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed_with_level(
+            mut self,
+            encoding: CompressionEncoding,
+            level: CompressionLevel,
+        ) -> Self {
+            self.inner = self.inner.send_compressed_with_level(encoding, level);
+            self
+        }
+        /// Send requests smaller than `min_size` bytes uncompressed.
+        #[must_use]
+        pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+            self.inner = self.inner.send_compressed_min_size(min_size);
+            self
+        }
         /// Enable decompressing responses.
         #[must_use]
         pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
@@ -135,6 +178,14 @@ pub mod health_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
+        /// Leave `margin` of a propagated deadline before calls time out.
+        ///
+        /// Default: zero
+        #[must_use]
+        pub fn deadline_margin(mut self, margin: std::time::Duration) -> Self {
+            self.inner = self.inner.deadline_margin(margin);
+            self
+        }
         /// If the requested service is unknown, the call will fail with status
         /// NOT_FOUND.
         pub async fn check(
@@ -280,6 +331,15 @@ pub mod health_server {
         {
             InterceptedService::new(Self::new(inner), interceptor)
         }
+        pub fn with_async_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> AsyncInterceptedService<Self, F>
+        where
+            F: tonic::service::AsyncInterceptor,
+        {
+            AsyncInterceptedService::new(Self::new(inner), interceptor)
+        }
         /// Enable decompressing requests with the given encoding.
         #[must_use]
         pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
@@ -292,6 +352,22 @@ pub mod health_server {
             self.send_compression_encodings.enable(encoding);
             self
         }
+        /// Compress responses with the given encoding and level, if the client supports it.
+        #[must_use]
+        pub fn send_compressed_with_level(
+            mut self,
+            encoding: CompressionEncoding,
+            level: CompressionLevel,
+        ) -> Self {
+            self.send_compression_encodings.enable_with_level(encoding, level);
+            self
+        }
+        /// Send responses smaller than `min_size` bytes uncompressed.
+        #[must_use]
+        pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+            self.send_compression_encodings.set_min_size(min_size);
+            self
+        }
         /// Limits the maximum size of a decoded message.
         ///
         /// Default: `4MB`
@@ -349,8 +425,12 @@ pub mod health_server {
                             Box::pin(fut)
                         }
                     }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
+                    let accept_compression_encodings = self
+                        .accept_compression_encodings
+                        .clone();
+                    let send_compression_encodings = self
+                        .send_compression_encodings
+                        .clone();
                     let max_decoding_message_size = self.max_decoding_message_size;
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
@@ -395,8 +475,12 @@ pub mod health_server {
                             Box::pin(fut)
                         }
                     }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
+                    let accept_compression_encodings = self
+                        .accept_compression_encodings
+                        .clone();
+                    let send_compression_encodings = self
+                        .send_compression_encodings
+                        .clone();
                     let max_decoding_message_size = self.max_decoding_message_size;
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
@@ -444,8 +528,8 @@ pub mod health_server {
             let inner = self.inner.clone();
             Self {
                 inner,
-                accept_compression_encodings: self.accept_compression_encodings,
-                send_compression_encodings: self.send_compression_encodings,
+                accept_compression_encodings: self.accept_compression_encodings.clone(),
+                send_compression_encodings: self.send_compression_encodings.clone(),
                 max_decoding_message_size: self.max_decoding_message_size,
                 max_encoding_message_size: self.max_encoding_message_size,
             }
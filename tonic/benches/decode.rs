@@ -1,12 +1,16 @@
 use bencher::{benchmark_group, benchmark_main, Bencher};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use http_body::{Body, Frame, SizeHint};
+use prost::Message;
 use std::{
     fmt::{Error, Formatter},
     pin::Pin,
     task::{Context, Poll},
 };
-use tonic::{codec::DecodeBuf, codec::Decoder, Status, Streaming};
+use tonic::{
+    codec::{Codec, DecodeBuf, Decoder, ProstCodec},
+    Status, Streaming,
+};
 
 macro_rules! bench {
     ($name:ident, $message_size:expr, $chunk_size:expr, $message_count:expr) => {
@@ -38,6 +42,33 @@ macro_rules! bench {
     };
 }
 
+// decodes a single protobuf message holding a `$message_size` payload of type `$payload`
+macro_rules! bench_prost {
+    ($name:ident, $payload:ty, $message_size:expr, $chunk_size:expr) => {
+        fn $name(b: &mut Bencher) {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .build()
+                .expect("runtime");
+
+            let payload = make_prost_payload($message_size);
+            let body = MockBody::new(payload, $chunk_size);
+            b.bytes = body.len() as u64;
+
+            b.iter(|| {
+                rt.block_on(async {
+                    let decoder = ProstCodec::<$payload, $payload>::default().decoder();
+                    let mut stream = Streaming::new_request(decoder, body.clone(), None, None);
+
+                    let msg = stream.message().await.unwrap().unwrap();
+                    assert_eq!($message_size, msg.len());
+
+                    assert!(stream.message().await.unwrap().is_none());
+                })
+            })
+        }
+    };
+}
+
 #[derive(Clone)]
 struct MockBody {
     data: Bytes,
@@ -122,6 +153,17 @@ fn make_payload(message_length: usize, message_count: usize) -> Bytes {
     buf.freeze()
 }
 
+fn make_prost_payload(payload_length: usize) -> Bytes {
+    let msg = Bytes::from(vec![97u8; payload_length]).encode_to_vec();
+
+    let mut buf = BytesMut::with_capacity(msg.len() + 5);
+    buf.put_u8(0);
+    buf.put_u32(msg.len() as u32);
+    buf.put(&msg[..]);
+
+    buf.freeze()
+}
+
 // change body chunk size only
 bench!(chunk_size_100, 1_000, 100, 1);
 bench!(chunk_size_500, 1_000, 500, 1);
@@ -137,6 +179,12 @@ bench!(message_count_1, 500, 505, 1);
 bench!(message_count_10, 500, 505, 10);
 bench!(message_count_20, 500, 505, 20);
 
+// `Bytes` payloads reference the received frame, `Vec<u8>` payloads are copied out of it
+bench_prost!(prost_bytes_single_frame, Bytes, 1_000_000, usize::MAX);
+bench_prost!(prost_bytes_chunked, Bytes, 1_000_000, 16_384);
+bench_prost!(prost_vec_single_frame, Vec<u8>, 1_000_000, usize::MAX);
+bench_prost!(prost_vec_chunked, Vec<u8>, 1_000_000, 16_384);
+
 benchmark_group!(chunk_size, chunk_size_100, chunk_size_500, chunk_size_1005);
 
 benchmark_group!(
@@ -153,4 +201,12 @@ benchmark_group!(
     message_count_20
 );
 
-benchmark_main!(chunk_size, message_size, message_count);
+benchmark_group!(
+    prost_payload,
+    prost_bytes_single_frame,
+    prost_bytes_chunked,
+    prost_vec_single_frame,
+    prost_vec_chunked
+);
+
+benchmark_main!(chunk_size, message_size, message_count, prost_payload);
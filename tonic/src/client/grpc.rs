@@ -1,19 +1,21 @@
-use crate::codec::compression::{CompressionEncoding, EnabledCompressionEncodings};
-use crate::codec::EncodeBody;
-use crate::metadata::GRPC_CONTENT_TYPE;
+use crate::codec::compression::{
+    CompressionEncoding, CompressionLevel, EnabledCompressionEncodings, SendCompression,
+};
+use crate::codec::{content_type, CallMessageLimits, EncodeBody, EncodeItem};
 use crate::{
     body::BoxBody,
     client::GrpcService,
     codec::{Codec, Decoder, Streaming},
-    request::SanitizeHeaders,
-    Code, Request, Response, Status,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::{duration_to_grpc_timeout, try_parse_grpc_timeout, SanitizeHeaders},
+    Code, Deadline, Request, Response, Status,
 };
 use http::{
     header::{HeaderValue, CONTENT_TYPE, TE},
     uri::{PathAndQuery, Uri},
 };
 use http_body::Body;
-use std::{fmt, future, pin::pin};
+use std::{fmt, future, pin::pin, time::Duration};
 use tokio_stream::{Stream, StreamExt};
 
 /// A gRPC client dispatcher.
@@ -40,10 +42,16 @@ struct GrpcConfig {
     accept_compression_encodings: EnabledCompressionEncodings,
     /// The compression encoding that will be applied to requests.
     send_compression_encodings: Option<CompressionEncoding>,
+    /// The level requests are compressed with.
+    send_compression_level: CompressionLevel,
+    /// Requests smaller than this many bytes are sent uncompressed.
+    send_compression_min_size: usize,
     /// Limits the maximum size of a decoded message.
     max_decoding_message_size: Option<usize>,
     /// Limits the maximum size of an encoded message.
     max_encoding_message_size: Option<usize>,
+    /// Subtracted from the time left until a propagated deadline.
+    deadline_margin: Duration,
 }
 
 impl<T> Grpc<T> {
@@ -62,9 +70,12 @@ impl<T> Grpc<T> {
             config: GrpcConfig {
                 origin,
                 send_compression_encodings: None,
+                send_compression_level: CompressionLevel::Default,
+                send_compression_min_size: 0,
                 accept_compression_encodings: EnabledCompressionEncodings::default(),
                 max_decoding_message_size: None,
                 max_encoding_message_size: None,
+                deadline_margin: Duration::ZERO,
             },
         }
     }
@@ -96,10 +107,85 @@ impl<T> Grpc<T> {
     /// # };
     /// ```
     pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        encoding.assert_valid_name();
         self.config.send_compression_encodings = Some(encoding);
         self
     }
 
+    /// Compress requests with the provided encoding, at the given [`CompressionLevel`].
+    ///
+    /// Requires the server to accept the specified encoding, otherwise it might return an error.
+    ///
+    /// # Example
+    ///
+    /// The most common way of using this is through a client generated by tonic-build:
+    ///
+    /// ```rust
+    /// use tonic::transport::Channel;
+    /// # enum CompressionEncoding { Gzip }
+    /// # enum CompressionLevel { Fastest }
+    /// # struct TestClient<T>(T);
+    /// # impl<T> TestClient<T> {
+    /// #     fn new(channel: T) -> Self { Self(channel) }
+    /// #     fn send_compressed_with_level(self, _: CompressionEncoding, _: CompressionLevel) -> Self { self }
+    /// # }
+    ///
+    /// # async {
+    /// let channel = Channel::builder("127.0.0.1:3000".parse().unwrap())
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let client = TestClient::new(channel)
+    ///     .send_compressed_with_level(CompressionEncoding::Gzip, CompressionLevel::Fastest);
+    /// # };
+    /// ```
+    pub fn send_compressed_with_level(
+        mut self,
+        encoding: CompressionEncoding,
+        level: CompressionLevel,
+    ) -> Self {
+        encoding.assert_valid_name();
+        self.config.send_compression_encodings = Some(encoding);
+        self.config.send_compression_level = level;
+        self
+    }
+
+    /// Send request messages smaller than `min_size` bytes uncompressed, even if compression is
+    /// enabled.
+    ///
+    /// Default is 0, compressing every message.
+    ///
+    /// # Example
+    ///
+    /// The most common way of using this is through a client generated by tonic-build:
+    ///
+    /// ```rust
+    /// use tonic::transport::Channel;
+    /// # enum CompressionEncoding { Gzip }
+    /// # struct TestClient<T>(T);
+    /// # impl<T> TestClient<T> {
+    /// #     fn new(channel: T) -> Self { Self(channel) }
+    /// #     fn send_compressed(self, _: CompressionEncoding) -> Self { self }
+    /// #     fn send_compressed_min_size(self, _: usize) -> Self { self }
+    /// # }
+    ///
+    /// # async {
+    /// let channel = Channel::builder("127.0.0.1:3000".parse().unwrap())
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let client = TestClient::new(channel)
+    ///     .send_compressed(CompressionEncoding::Gzip)
+    ///     .send_compressed_min_size(1024);
+    /// # };
+    /// ```
+    pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+        self.config.send_compression_min_size = min_size;
+        self
+    }
+
     /// Enable accepting compressed responses.
     ///
     /// Requires the server to also support sending compressed responses.
@@ -191,6 +277,44 @@ impl<T> Grpc<T> {
         self
     }
 
+    /// Leave `margin` of a propagated [`Deadline`] for the caller.
+    ///
+    /// When a request carries a [`Deadline`] extension, or is sent while a server
+    /// handler with a deadline is running, the time that remains minus `margin` is
+    /// sent as the `grpc-timeout` of the call, unless the request already sets a
+    /// shorter timeout. The margin leaves the handler time to act on the result of
+    /// the call before its own deadline expires. If no time remains, the call fails
+    /// with `DeadlineExceeded` without being sent.
+    ///
+    /// Default: zero
+    ///
+    /// # Example
+    ///
+    /// The most common way of using this is through a client generated by tonic-build:
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use tonic::transport::Channel;
+    /// # struct TestClient<T>(T);
+    /// # impl<T> TestClient<T> {
+    /// #     fn new(channel: T) -> Self { Self(channel) }
+    /// #     fn deadline_margin(self, _: Duration) -> Self { self }
+    /// # }
+    ///
+    /// # async {
+    /// let channel = Channel::builder("127.0.0.1:3000".parse().unwrap())
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let client = TestClient::new(channel).deadline_margin(Duration::from_millis(50));
+    /// # };
+    /// ```
+    pub fn deadline_margin(mut self, margin: Duration) -> Self {
+        self.config.deadline_margin = margin;
+        self
+    }
+
     /// Check if the inner [`GrpcService`] is able to accept a  new request.
     ///
     /// This will call [`GrpcService::poll_ready`] until it returns ready or
@@ -214,8 +338,8 @@ impl<T> Grpc<T> {
         T: GrpcService<BoxBody>,
         T::ResponseBody: Body + Send + 'static,
         <T::ResponseBody as Body>::Error: Into<crate::BoxError>,
-        C: Codec<Encode = M1, Decode = M2>,
-        M1: Send + Sync + 'static,
+        C: Codec<Decode = M2>,
+        M1: Into<EncodeItem<C::Encode>> + Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
         let request = request.map(|m| tokio_stream::once(m));
@@ -234,8 +358,8 @@ impl<T> Grpc<T> {
         T::ResponseBody: Body + Send + 'static,
         <T::ResponseBody as Body>::Error: Into<crate::BoxError>,
         S: Stream<Item = M1> + Send + 'static,
-        C: Codec<Encode = M1, Decode = M2>,
-        M1: Send + Sync + 'static,
+        C: Codec<Decode = M2>,
+        M1: Into<EncodeItem<C::Encode>> + Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
         let (mut parts, body, extensions) =
@@ -270,8 +394,8 @@ impl<T> Grpc<T> {
         T: GrpcService<BoxBody>,
         T::ResponseBody: Body + Send + 'static,
         <T::ResponseBody as Body>::Error: Into<crate::BoxError>,
-        C: Codec<Encode = M1, Decode = M2>,
-        M1: Send + Sync + 'static,
+        C: Codec<Decode = M2>,
+        M1: Into<EncodeItem<C::Encode>> + Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
         let request = request.map(|m| tokio_stream::once(m));
@@ -290,22 +414,28 @@ impl<T> Grpc<T> {
         T::ResponseBody: Body + Send + 'static,
         <T::ResponseBody as Body>::Error: Into<crate::BoxError>,
         S: Stream<Item = M1> + Send + 'static,
-        C: Codec<Encode = M1, Decode = M2>,
-        M1: Send + Sync + 'static,
+        C: Codec<Decode = M2>,
+        M1: Into<EncodeItem<C::Encode>> + Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
+        let limits = CallMessageLimits::new();
+
         let request = request
             .map(|s| {
-                EncodeBody::new_client(
+                EncodeBody::client(
                     codec.encoder(),
                     s.map(Ok),
-                    self.config.send_compression_encodings,
+                    self.config.send_compression(),
                     self.config.max_encoding_message_size,
+                    Some(limits.clone()),
                 )
             })
             .map(BoxBody::new);
 
-        let request = self.config.prepare_request(request, path);
+        let mut request = self
+            .config
+            .prepare_request(request, path, codec.content_subtype())?;
+        request.extensions_mut().insert(limits.clone());
 
         let response = self
             .inner
@@ -315,7 +445,7 @@ impl<T> Grpc<T> {
 
         let decoder = codec.decoder();
 
-        self.create_response(decoder, response)
+        self.create_response(decoder, response, &limits)
     }
 
     // Keeping this code in a separate function from Self::streaming lets functions that return the
@@ -324,6 +454,7 @@ impl<T> Grpc<T> {
         &self,
         decoder: impl Decoder<Item = M2, Error = Status> + Send + 'static,
         response: http::Response<T::ResponseBody>,
+        limits: &CallMessageLimits,
     ) -> Result<Response<Streaming<M2>>, Status>
     where
         T: GrpcService<BoxBody>,
@@ -332,7 +463,7 @@ impl<T> Grpc<T> {
     {
         let encoding = CompressionEncoding::from_encoding_header(
             response.headers(),
-            self.config.accept_compression_encodings,
+            &self.config.accept_compression_encodings,
         )?;
 
         let status_code = response.status();
@@ -357,7 +488,7 @@ impl<T> Grpc<T> {
                     body,
                     status_code,
                     encoding,
-                    self.config.max_decoding_message_size,
+                    limits.response(self.config.max_decoding_message_size),
                 )
             } else {
                 Streaming::new_empty(decoder, body)
@@ -369,11 +500,21 @@ impl<T> Grpc<T> {
 }
 
 impl GrpcConfig {
+    fn send_compression(&self) -> Option<SendCompression> {
+        self.send_compression_encodings
+            .map(|encoding| SendCompression {
+                encoding,
+                level: self.send_compression_level,
+                min_size: self.send_compression_min_size,
+            })
+    }
+
     fn prepare_request(
         &self,
         request: Request<BoxBody>,
         path: PathAndQuery,
-    ) -> http::Request<BoxBody> {
+        content_subtype: Option<&str>,
+    ) -> Result<http::Request<BoxBody>, Status> {
         let mut parts = self.origin.clone().into_parts();
 
         match &parts.path_and_query {
@@ -406,9 +547,8 @@ impl GrpcConfig {
         // Set the content type
         request
             .headers_mut()
-            .insert(CONTENT_TYPE, GRPC_CONTENT_TYPE);
+            .insert(CONTENT_TYPE, content_type(content_subtype));
 
-        #[cfg(any(feature = "gzip", feature = "zstd"))]
         if let Some(encoding) = self.send_compression_encodings {
             request.headers_mut().insert(
                 crate::codec::compression::ENCODING_HEADER,
@@ -418,7 +558,7 @@ impl GrpcConfig {
 
         if let Some(header_value) = self
             .accept_compression_encodings
-            .into_accept_encoding_header_value()
+            .accept_encoding_header_value()
         {
             request.headers_mut().insert(
                 crate::codec::compression::ACCEPT_ENCODING_HEADER,
@@ -426,7 +566,33 @@ impl GrpcConfig {
             );
         }
 
-        request
+        // Propagate the deadline of the call being handled, if any
+        if let Some(deadline) = request
+            .extensions()
+            .get::<Deadline>()
+            .copied()
+            .or_else(Deadline::current)
+        {
+            let remaining = deadline.remaining().saturating_sub(self.deadline_margin);
+            if remaining.is_zero() {
+                // The call could only fail on the server, don't send it
+                return Err(Status::deadline_exceeded(
+                    "propagated deadline has already expired",
+                ));
+            }
+            let timeout = match try_parse_grpc_timeout(request.headers()) {
+                Ok(Some(timeout)) => timeout.min(remaining),
+                _ => remaining,
+            };
+            request.headers_mut().insert(
+                GRPC_TIMEOUT_HEADER,
+                duration_to_grpc_timeout(timeout)
+                    .parse()
+                    .expect("grpc-timeout is a valid header value"),
+            );
+        }
+
+        Ok(request)
     }
 }
 
@@ -437,9 +603,12 @@ impl<T: Clone> Clone for Grpc<T> {
             config: GrpcConfig {
                 origin: self.config.origin.clone(),
                 send_compression_encodings: self.config.send_compression_encodings,
-                accept_compression_encodings: self.config.accept_compression_encodings,
+                send_compression_level: self.config.send_compression_level,
+                send_compression_min_size: self.config.send_compression_min_size,
+                accept_compression_encodings: self.config.accept_compression_encodings.clone(),
                 max_encoding_message_size: self.config.max_encoding_message_size,
                 max_decoding_message_size: self.config.max_decoding_message_size,
+                deadline_margin: self.config.deadline_margin,
             },
         }
     }
@@ -458,6 +627,16 @@ impl<T: fmt::Debug> fmt::Debug for Grpc<T> {
             &self.config.send_compression_encodings,
         );
 
+        f.field(
+            "send_compression_level",
+            &self.config.send_compression_level,
+        );
+
+        f.field(
+            "send_compression_min_size",
+            &self.config.send_compression_min_size,
+        );
+
         f.field(
             "accept_compression_encodings",
             &self.config.accept_compression_encodings,
@@ -473,6 +652,8 @@ impl<T: fmt::Debug> fmt::Debug for Grpc<T> {
             &self.config.max_encoding_message_size,
         );
 
+        f.field("deadline_margin", &self.config.deadline_margin);
+
         f.finish()
     }
 }
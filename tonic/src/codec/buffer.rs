@@ -4,19 +4,44 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 /// A specialized buffer to decode gRPC messages from.
 #[derive(Debug)]
 pub struct DecodeBuf<'a> {
-    buf: &'a mut BytesMut,
+    buf: Source<'a>,
     len: usize,
 }
 
+/// The bytes a [`DecodeBuf`] reads from.
+#[derive(Debug)]
+enum Source<'a> {
+    Borrowed(&'a mut BytesMut),
+    Owned(Bytes),
+}
+
 /// A specialized buffer to encode gRPC messages into.
 #[derive(Debug)]
 pub struct EncodeBuf<'a> {
     buf: &'a mut BytesMut,
+    // Chunks appended to the message without copying, with their offsets into `buf`.
+    chunks: Option<&'a mut Vec<(usize, Bytes)>>,
 }
 
+/// Chunks shorter than this are copied into the buffer, which is cheaper than sending them as
+/// separate body frames.
+const MIN_APPEND_LEN: usize = 8 * 1024;
+
 impl<'a> DecodeBuf<'a> {
     pub(crate) fn new(buf: &'a mut BytesMut, len: usize) -> Self {
-        DecodeBuf { buf, len }
+        DecodeBuf {
+            buf: Source::Borrowed(buf),
+            len,
+        }
+    }
+}
+
+impl DecodeBuf<'static> {
+    pub(crate) fn from_bytes(buf: Bytes) -> Self {
+        DecodeBuf {
+            len: buf.len(),
+            buf: Source::Owned(buf),
+        }
     }
 }
 
@@ -28,7 +53,10 @@ impl Buf for DecodeBuf<'_> {
 
     #[inline]
     fn chunk(&self) -> &[u8] {
-        let ret = self.buf.chunk();
+        let ret = match &self.buf {
+            Source::Borrowed(buf) => buf.chunk(),
+            Source::Owned(buf) => buf.chunk(),
+        };
 
         if ret.len() > self.len {
             &ret[..self.len]
@@ -40,7 +68,10 @@ impl Buf for DecodeBuf<'_> {
     #[inline]
     fn advance(&mut self, cnt: usize) {
         assert!(cnt <= self.len);
-        self.buf.advance(cnt);
+        match &mut self.buf {
+            Source::Borrowed(buf) => buf.advance(cnt),
+            Source::Owned(buf) => buf.advance(cnt),
+        }
         self.len -= cnt;
     }
 
@@ -48,13 +79,23 @@ impl Buf for DecodeBuf<'_> {
     fn copy_to_bytes(&mut self, len: usize) -> Bytes {
         assert!(len <= self.len);
         self.len -= len;
-        self.buf.copy_to_bytes(len)
+        match &mut self.buf {
+            Source::Borrowed(buf) => buf.copy_to_bytes(len),
+            Source::Owned(buf) => buf.copy_to_bytes(len),
+        }
     }
 }
 
 impl<'a> EncodeBuf<'a> {
     pub(crate) fn new(buf: &'a mut BytesMut) -> Self {
-        EncodeBuf { buf }
+        EncodeBuf { buf, chunks: None }
+    }
+
+    pub(crate) fn vectored(buf: &'a mut BytesMut, chunks: &'a mut Vec<(usize, Bytes)>) -> Self {
+        EncodeBuf {
+            buf,
+            chunks: Some(chunks),
+        }
     }
 }
 
@@ -68,6 +109,18 @@ impl EncodeBuf<'_> {
     pub fn reserve(&mut self, additional: usize) {
         self.buf.reserve(additional);
     }
+
+    /// Appends `bytes` to the message without copying them where possible.
+    ///
+    /// Large chunks are sent as separate body frames referencing `bytes`, which avoids copying
+    /// big payloads. They are copied when the message is compressed, since compression needs the
+    /// whole message in one buffer. [`put`](BufMut::put) appends large `Bytes` this way too.
+    pub fn append_bytes(&mut self, bytes: Bytes) {
+        match &mut self.chunks {
+            Some(chunks) if bytes.len() >= MIN_APPEND_LEN => chunks.push((self.buf.len(), bytes)),
+            _ => self.buf.put(bytes),
+        }
+    }
 }
 
 unsafe impl BufMut for EncodeBuf<'_> {
@@ -87,11 +140,17 @@ unsafe impl BufMut for EncodeBuf<'_> {
     }
 
     #[inline]
-    fn put<T: Buf>(&mut self, src: T)
+    fn put<T: Buf>(&mut self, mut src: T)
     where
         Self: Sized,
     {
-        self.buf.put(src)
+        if self.chunks.is_some() && src.remaining() >= MIN_APPEND_LEN {
+            // doesn't copy when `src` is `Bytes`
+            let bytes = src.copy_to_bytes(src.remaining());
+            self.append_bytes(bytes)
+        } else {
+            self.buf.put(src)
+        }
     }
 
     #[inline]
@@ -131,6 +190,22 @@ mod tests {
         assert!(!buf.has_remaining());
     }
 
+    #[test]
+    fn decode_buf_from_bytes() {
+        let payload = Bytes::from(vec![0u8; 20]);
+        let mut buf = DecodeBuf::from_bytes(payload.clone());
+
+        assert_eq!(buf.remaining(), 20);
+        assert_eq!(buf.chunk().len(), 20);
+
+        buf.advance(10);
+        let bytes = buf.copy_to_bytes(10);
+        assert!(!buf.has_remaining());
+
+        // the bytes reference the payload instead of being copied
+        assert_eq!(bytes.as_ptr(), payload[10..].as_ptr());
+    }
+
     #[test]
     fn encode_buf() {
         let mut bytes = BytesMut::with_capacity(100);
@@ -143,4 +218,21 @@ mod tests {
         buf.put_u8(b'a');
         assert_eq!(buf.remaining_mut(), initial - 20 - 1);
     }
+
+    #[test]
+    fn encode_buf_vectored() {
+        let mut bytes = BytesMut::new();
+        let mut chunks = Vec::new();
+        let mut buf = EncodeBuf::vectored(&mut bytes, &mut chunks);
+
+        let large = Bytes::from(vec![1u8; MIN_APPEND_LEN]);
+        buf.put_u8(b'a');
+        buf.put(large.clone());
+        buf.append_bytes(Bytes::from_static(b"small"));
+
+        assert_eq!(&bytes[..], b"asmall");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].0, 1);
+        assert_eq!(chunks[0].1.as_ptr(), large.as_ptr());
+    }
 }
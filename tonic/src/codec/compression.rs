@@ -2,7 +2,9 @@ use crate::{metadata::MetadataValue, Status};
 use bytes::{Buf, BufMut, BytesMut};
 #[cfg(feature = "gzip")]
 use flate2::read::{GzDecoder, GzEncoder};
-use std::fmt;
+#[cfg(feature = "deflate")]
+use flate2::read::{ZlibDecoder, ZlibEncoder};
+use std::{fmt, io, sync::Arc};
 #[cfg(feature = "zstd")]
 use zstd::stream::read::{Decoder, Encoder};
 
@@ -11,41 +13,73 @@ pub(crate) const ACCEPT_ENCODING_HEADER: &str = "grpc-accept-encoding";
 
 /// Struct used to configure which encodings are enabled on a server or channel.
 ///
-/// Represents an ordered list of compression encodings that are enabled.
-#[derive(Debug, Default, Clone, Copy)]
+/// Represents an ordered list of compression encodings that are enabled. It acts as the registry
+/// of encodings a client or server negotiates with its peer: only encodings enabled here are
+/// advertised in `grpc-accept-encoding` and accepted in `grpc-encoding`.
+///
+/// The list is shared between clones, so cloning it is cheap.
+#[derive(Debug, Default, Clone)]
 pub struct EnabledCompressionEncodings {
-    inner: [Option<CompressionEncoding>; 2],
+    inner: Option<Arc<[EnabledEncoding]>>,
+    min_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EnabledEncoding {
+    encoding: CompressionEncoding,
+    level: CompressionLevel,
 }
 
 impl EnabledCompressionEncodings {
     /// Enable a [`CompressionEncoding`].
     ///
     /// Adds the new encoding to the end of the encoding list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name of a custom encoding is not a valid header token.
     pub fn enable(&mut self, encoding: CompressionEncoding) {
-        for e in self.inner.iter_mut() {
-            match e {
-                Some(e) if *e == encoding => return,
-                None => {
-                    *e = Some(encoding);
-                    return;
-                }
-                _ => continue,
-            }
+        self.enable_with_level(encoding, CompressionLevel::Default);
+    }
+
+    /// Enable a [`CompressionEncoding`], compressing with the given [`CompressionLevel`].
+    ///
+    /// Adds the new encoding to the end of the encoding list, or updates its level if it is
+    /// already enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name of a custom encoding is not a valid header token.
+    pub fn enable_with_level(&mut self, encoding: CompressionEncoding, level: CompressionLevel) {
+        encoding.assert_valid_name();
+
+        let mut entries = self.entries().to_vec();
+        match entries.iter_mut().find(|e| e.encoding == encoding) {
+            Some(e) => e.level = level,
+            None => entries.push(EnabledEncoding { encoding, level }),
         }
+        self.inner = Some(entries.into());
+    }
+
+    /// Set the size in bytes below which messages are sent uncompressed.
+    ///
+    /// Only has an effect on the encodings used for sending messages. Default is 0, compressing
+    /// every message.
+    pub fn set_min_size(&mut self, min_size: usize) {
+        self.min_size = Some(min_size);
     }
 
     /// Remove the last [`CompressionEncoding`].
     pub fn pop(&mut self) -> Option<CompressionEncoding> {
-        self.inner
-            .iter_mut()
-            .rev()
-            .find(|entry| entry.is_some())?
-            .take()
+        let (last, rest) = self.entries().split_last()?;
+        let encoding = last.encoding;
+        self.inner = (!rest.is_empty()).then(|| rest.into());
+        Some(encoding)
     }
 
-    pub(crate) fn into_accept_encoding_header_value(self) -> Option<http::HeaderValue> {
+    pub(crate) fn accept_encoding_header_value(&self) -> Option<http::HeaderValue> {
         let mut value = BytesMut::new();
-        for encoding in self.inner.into_iter().flatten() {
+        for encoding in self.iter() {
             value.put_slice(encoding.as_str().as_bytes());
             value.put_u8(b',');
         }
@@ -60,25 +94,189 @@ impl EnabledCompressionEncodings {
 
     /// Check if a [`CompressionEncoding`] is enabled.
     pub fn is_enabled(&self, encoding: CompressionEncoding) -> bool {
-        self.inner.contains(&Some(encoding))
+        self.iter().any(|e| e == encoding)
     }
 
     /// Check if any [`CompressionEncoding`]s are enabled.
     pub fn is_empty(&self) -> bool {
-        self.inner.iter().all(|e| e.is_none())
+        self.entries().is_empty()
+    }
+
+    /// Iterate over the enabled [`CompressionEncoding`]s, in order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = CompressionEncoding> + '_ {
+        self.entries().iter().map(|e| e.encoding)
+    }
+
+    /// Merge the encodings, levels and minimum size of `other` into these.
+    ///
+    /// The levels and minimum size set in `other` take priority, even if the minimum size is
+    /// smaller than this one.
+    pub(crate) fn extend(&mut self, other: EnabledCompressionEncodings) {
+        if self.is_empty() {
+            self.inner = other.inner;
+        } else {
+            for e in other.entries() {
+                self.enable_with_level(e.encoding, e.level);
+            }
+        }
+        self.min_size = other.min_size.or(self.min_size);
+    }
+
+    /// How messages are sent with the given enabled encoding.
+    pub(crate) fn send_compression(&self, encoding: CompressionEncoding) -> SendCompression {
+        let level = self
+            .entries()
+            .iter()
+            .find(|e| e.encoding == encoding)
+            .map_or(CompressionLevel::Default, |e| e.level);
+
+        SendCompression {
+            encoding,
+            level,
+            min_size: self.min_size.unwrap_or(0),
+        }
+    }
+
+    /// Find the enabled encoding with the given name.
+    fn find(&self, name: &[u8]) -> Option<CompressionEncoding> {
+        self.iter()
+            .find(|encoding| encoding.as_str().as_bytes() == name)
+    }
+
+    fn entries(&self) -> &[EnabledEncoding] {
+        self.inner.as_deref().unwrap_or_default()
+    }
+}
+
+/// How a stream of messages is compressed when sent.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SendCompression {
+    pub(crate) encoding: CompressionEncoding,
+    pub(crate) level: CompressionLevel,
+    /// Messages smaller than this many bytes are sent uncompressed.
+    pub(crate) min_size: usize,
+}
+
+impl From<CompressionEncoding> for SendCompression {
+    fn from(encoding: CompressionEncoding) -> Self {
+        Self {
+            encoding,
+            level: CompressionLevel::Default,
+            min_size: 0,
+        }
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct CompressionSettings {
     pub(crate) encoding: CompressionEncoding,
+    pub(crate) level: CompressionLevel,
     /// buffer_growth_interval controls memory growth for internal buffers to balance resizing cost against memory waste.
     /// The default buffer growth interval is 8 kilobytes.
     pub(crate) buffer_growth_interval: usize,
 }
 
+/// How much effort to spend compressing messages, trading speed for size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompressionLevel {
+    /// The fastest level of the encoding.
+    Fastest,
+    /// The default level of the encoding.
+    #[default]
+    Default,
+    /// The level producing the smallest output.
+    Best,
+    /// A level specific to the encoding, clamped to the range it supports: 0 to 9 for `gzip` and
+    /// `deflate`, and the zstd levels for `zstd`.
+    Precise(i32),
+}
+
+#[cfg(any(feature = "gzip", feature = "deflate"))]
+impl CompressionLevel {
+    fn into_flate2(self) -> flate2::Compression {
+        match self {
+            CompressionLevel::Fastest => flate2::Compression::fast(),
+            CompressionLevel::Default => flate2::Compression::new(6),
+            CompressionLevel::Best => flate2::Compression::best(),
+            CompressionLevel::Precise(level) => flate2::Compression::new(level.clamp(0, 9) as u32),
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl CompressionLevel {
+    fn into_zstd(self) -> i32 {
+        let range = zstd::compression_level_range();
+        match self {
+            CompressionLevel::Fastest => 1,
+            CompressionLevel::Default => zstd::DEFAULT_COMPRESSION_LEVEL,
+            CompressionLevel::Best => *range.end(),
+            CompressionLevel::Precise(level) => level.clamp(*range.start(), *range.end()),
+        }
+    }
+}
+
+/// A compression algorithm messages can be encoded with.
+///
+/// Implement this trait to use an encoding tonic doesn't ship with, such as `snappy` or `lz4`,
+/// and enable it on generated clients and servers as [`CompressionEncoding::Custom`]:
+///
+/// ```
+/// use std::io;
+/// use tonic::codec::{CompressionEncoding, CompressionLevel, Compressor};
+///
+/// /// An encoding that stores messages as they are.
+/// struct Stored;
+///
+/// impl Compressor for Stored {
+///     fn name(&self) -> &'static str {
+///         "x-stored"
+///     }
+///
+///     fn compress(
+///         &self,
+///         input: &[u8],
+///         output: &mut dyn io::Write,
+///         _level: CompressionLevel,
+///     ) -> io::Result<()> {
+///         output.write_all(input)
+///     }
+///
+///     fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+///         output.write_all(input)
+///     }
+/// }
+///
+/// const STORED: CompressionEncoding = CompressionEncoding::Custom(&Stored);
+/// ```
+pub trait Compressor: Send + Sync + 'static {
+    /// The name of the encoding, as sent in the `grpc-encoding` and `grpc-accept-encoding`
+    /// headers.
+    ///
+    /// The name must be a valid header token, such as `x-snappy`, enabling an encoding with any
+    /// other name panics.
+    fn name(&self) -> &'static str;
+
+    /// Compress `input` into `output`, at the given level.
+    fn compress(
+        &self,
+        input: &[u8],
+        output: &mut dyn io::Write,
+        level: CompressionLevel,
+    ) -> io::Result<()>;
+
+    /// Decompress `input` into `output`.
+    ///
+    /// Writing to `output` fails once the decompressed message exceeds the maximum message size,
+    /// and that error should be returned as is.
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()>;
+}
+
 /// The compression encodings Tonic supports.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Two encodings are equal if they have the same name.
+#[derive(Clone, Copy)]
 #[non_exhaustive]
 pub enum CompressionEncoding {
     #[allow(missing_docs)]
@@ -87,20 +285,18 @@ pub enum CompressionEncoding {
     #[allow(missing_docs)]
     #[cfg(feature = "zstd")]
     Zstd,
+    /// The zlib format, named `deflate` in gRPC.
+    #[cfg(feature = "deflate")]
+    Deflate,
+    /// An encoding implemented by a [`Compressor`].
+    Custom(&'static dyn Compressor),
 }
 
 impl CompressionEncoding {
-    pub(crate) const ENCODINGS: &'static [CompressionEncoding] = &[
-        #[cfg(feature = "gzip")]
-        CompressionEncoding::Gzip,
-        #[cfg(feature = "zstd")]
-        CompressionEncoding::Zstd,
-    ];
-
     /// Based on the `grpc-accept-encoding` header, pick an encoding to use.
     pub(crate) fn from_accept_encoding_header(
         map: &http::HeaderMap,
-        enabled_encodings: EnabledCompressionEncodings,
+        enabled_encodings: &EnabledCompressionEncodings,
     ) -> Option<Self> {
         if enabled_encodings.is_empty() {
             return None;
@@ -109,35 +305,25 @@ impl CompressionEncoding {
         let header_value = map.get(ACCEPT_ENCODING_HEADER)?;
         let header_value_str = header_value.to_str().ok()?;
 
-        split_by_comma(header_value_str).find_map(|value| match value {
-            #[cfg(feature = "gzip")]
-            "gzip" => Some(CompressionEncoding::Gzip),
-            #[cfg(feature = "zstd")]
-            "zstd" => Some(CompressionEncoding::Zstd),
-            _ => None,
-        })
+        split_by_comma(header_value_str).find_map(|value| enabled_encodings.find(value.as_bytes()))
     }
 
     /// Get the value of `grpc-encoding` header. Returns an error if the encoding isn't supported.
     pub(crate) fn from_encoding_header(
         map: &http::HeaderMap,
-        enabled_encodings: EnabledCompressionEncodings,
+        enabled_encodings: &EnabledCompressionEncodings,
     ) -> Result<Option<Self>, Status> {
         let Some(header_value) = map.get(ENCODING_HEADER) else {
             return Ok(None);
         };
 
         match header_value.as_bytes() {
-            #[cfg(feature = "gzip")]
-            b"gzip" if enabled_encodings.is_enabled(CompressionEncoding::Gzip) => {
-                Ok(Some(CompressionEncoding::Gzip))
-            }
-            #[cfg(feature = "zstd")]
-            b"zstd" if enabled_encodings.is_enabled(CompressionEncoding::Zstd) => {
-                Ok(Some(CompressionEncoding::Zstd))
-            }
             b"identity" => Ok(None),
             other => {
+                if let Some(encoding) = enabled_encodings.find(other) {
+                    return Ok(Some(encoding));
+                }
+
                 // NOTE: Workaround for lifetime limitation. Resolved at Rust 1.79.
                 // https://blog.rust-lang.org/2024/06/13/Rust-1.79.0.html#extending-automatic-temporary-lifetime-extension
                 let other_debug_string;
@@ -154,7 +340,7 @@ impl CompressionEncoding {
                 ));
 
                 let header_value = enabled_encodings
-                    .into_accept_encoding_header_value()
+                    .accept_encoding_header_value()
                     .map(MetadataValue::unchecked_from_header_value)
                     .unwrap_or_else(|| MetadataValue::from_static("identity"));
                 status
@@ -167,17 +353,59 @@ impl CompressionEncoding {
     }
 
     pub(crate) fn as_str(self) -> &'static str {
+        self.compressor().name()
+    }
+
+    pub(crate) fn into_header_value(self) -> http::HeaderValue {
+        // Only encodings with valid names can be enabled.
+        http::HeaderValue::from_static(self.as_str())
+    }
+
+    /// Panics if the name of the encoding can't be sent in the compression headers.
+    pub(crate) fn assert_valid_name(self) {
+        let name = self.as_str();
+        let is_token = !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+
+        assert!(is_token, "invalid compression encoding name `{name}`");
+    }
+
+    fn compressor(self) -> &'static dyn Compressor {
         match self {
             #[cfg(feature = "gzip")]
-            CompressionEncoding::Gzip => "gzip",
+            CompressionEncoding::Gzip => &GzipCompressor,
             #[cfg(feature = "zstd")]
-            CompressionEncoding::Zstd => "zstd",
+            CompressionEncoding::Zstd => &ZstdCompressor,
+            #[cfg(feature = "deflate")]
+            CompressionEncoding::Deflate => &DeflateCompressor,
+            CompressionEncoding::Custom(compressor) => compressor,
         }
     }
+}
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
-    pub(crate) fn into_header_value(self) -> http::HeaderValue {
-        http::HeaderValue::from_static(self.as_str())
+impl PartialEq for CompressionEncoding {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for CompressionEncoding {}
+
+impl fmt::Debug for CompressionEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "gzip")]
+            CompressionEncoding::Gzip => f.write_str("Gzip"),
+            #[cfg(feature = "zstd")]
+            CompressionEncoding::Zstd => f.write_str("Zstd"),
+            #[cfg(feature = "deflate")]
+            CompressionEncoding::Deflate => f.write_str("Deflate"),
+            CompressionEncoding::Custom(compressor) => {
+                f.debug_tuple("Custom").field(&compressor.name()).finish()
+            }
+        }
     }
 }
 
@@ -191,9 +419,89 @@ fn split_by_comma(s: &str) -> impl Iterator<Item = &str> {
     s.split(',').map(|s| s.trim())
 }
 
+#[cfg(feature = "gzip")]
+struct GzipCompressor;
+
+#[cfg(feature = "gzip")]
+impl Compressor for GzipCompressor {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+
+    fn compress(
+        &self,
+        input: &[u8],
+        output: &mut dyn io::Write,
+        level: CompressionLevel,
+    ) -> io::Result<()> {
+        let mut gzip_encoder = GzEncoder::new(input, level.into_flate2());
+        io::copy(&mut gzip_encoder, output)?;
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        let mut gzip_decoder = GzDecoder::new(input);
+        io::copy(&mut gzip_decoder, output)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+struct ZstdCompressor;
+
+#[cfg(feature = "zstd")]
+impl Compressor for ZstdCompressor {
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+
+    fn compress(
+        &self,
+        input: &[u8],
+        output: &mut dyn io::Write,
+        level: CompressionLevel,
+    ) -> io::Result<()> {
+        let mut zstd_encoder = Encoder::new(input, level.into_zstd())?;
+        io::copy(&mut zstd_encoder, output)?;
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        let mut zstd_decoder = Decoder::new(input)?;
+        io::copy(&mut zstd_decoder, output)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "deflate")]
+struct DeflateCompressor;
+
+#[cfg(feature = "deflate")]
+impl Compressor for DeflateCompressor {
+    fn name(&self) -> &'static str {
+        "deflate"
+    }
+
+    fn compress(
+        &self,
+        input: &[u8],
+        output: &mut dyn io::Write,
+        level: CompressionLevel,
+    ) -> io::Result<()> {
+        let mut zlib_encoder = ZlibEncoder::new(input, level.into_flate2());
+        io::copy(&mut zlib_encoder, output)?;
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        let mut zlib_decoder = ZlibDecoder::new(input);
+        io::copy(&mut zlib_decoder, output)?;
+        Ok(())
+    }
+}
+
 /// Compress `len` bytes from `decompressed_buf` into `out_buf`.
 /// buffer_size_increment is a hint to control the growth of out_buf versus the cost of resizing it.
-#[allow(unused_variables, unreachable_code)]
 pub(crate) fn compress(
     settings: CompressionSettings,
     decompressed_buf: &mut BytesMut,
@@ -204,68 +512,83 @@ pub(crate) fn compress(
     let capacity = ((len / buffer_growth_interval) + 1) * buffer_growth_interval;
     out_buf.reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
-    let mut out_writer = out_buf.writer();
-
-    match settings.encoding {
-        #[cfg(feature = "gzip")]
-        CompressionEncoding::Gzip => {
-            let mut gzip_encoder = GzEncoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                flate2::Compression::new(6),
-            );
-            std::io::copy(&mut gzip_encoder, &mut out_writer)?;
-        }
-        #[cfg(feature = "zstd")]
-        CompressionEncoding::Zstd => {
-            let mut zstd_encoder = Encoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                zstd::DEFAULT_COMPRESSION_LEVEL,
-            )?;
-            std::io::copy(&mut zstd_encoder, &mut out_writer)?;
-        }
-    }
+    settings.encoding.compressor().compress(
+        &decompressed_buf[0..len],
+        &mut out_buf.writer(),
+        settings.level,
+    )?;
 
     decompressed_buf.advance(len);
 
     Ok(())
 }
 
-/// Decompress `len` bytes from `compressed_buf` into `out_buf`.
-#[allow(unused_variables, unreachable_code)]
+/// Decompress `compressed` into `out_buf`, failing with [`MessageTooLarge`] as soon as the
+/// decompressed message exceeds `max_len` bytes.
 pub(crate) fn decompress(
     settings: CompressionSettings,
-    compressed_buf: &mut BytesMut,
+    compressed: &[u8],
     out_buf: &mut BytesMut,
-    len: usize,
+    max_len: usize,
 ) -> Result<(), std::io::Error> {
     let buffer_growth_interval = settings.buffer_growth_interval;
-    let estimate_decompressed_len = len * 2;
+    let estimate_decompressed_len = (compressed.len() * 2).min(max_len);
     let capacity =
         ((estimate_decompressed_len / buffer_growth_interval) + 1) * buffer_growth_interval;
     out_buf.reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
-    let mut out_writer = out_buf.writer();
+    let mut writer = LimitedWriter {
+        inner: out_buf.writer(),
+        remaining: max_len,
+        limit: max_len,
+    };
+    settings
+        .encoding
+        .compressor()
+        .decompress(compressed, &mut writer)?;
 
-    match settings.encoding {
-        #[cfg(feature = "gzip")]
-        CompressionEncoding::Gzip => {
-            let mut gzip_decoder = GzDecoder::new(&compressed_buf[0..len]);
-            std::io::copy(&mut gzip_decoder, &mut out_writer)?;
-        }
-        #[cfg(feature = "zstd")]
-        CompressionEncoding::Zstd => {
-            let mut zstd_decoder = Decoder::new(&compressed_buf[0..len])?;
-            std::io::copy(&mut zstd_decoder, &mut out_writer)?;
-        }
+    Ok(())
+}
+
+/// Error returned by the writer passed to [`Compressor::decompress`] once the decompressed
+/// message exceeds the maximum message size.
+#[derive(Debug)]
+pub(crate) struct MessageTooLarge {
+    pub(crate) limit: usize,
+}
+
+impl fmt::Display for MessageTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "decompressed message length exceeds the limit of {} bytes",
+            self.limit
+        )
     }
+}
 
-    compressed_buf.advance(len);
+impl std::error::Error for MessageTooLarge {}
 
-    Ok(())
+/// Writer that fails once more than `limit` bytes are written to it.
+struct LimitedWriter<W> {
+    inner: W,
+    remaining: usize,
+    limit: usize,
+}
+
+impl<W: io::Write> io::Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.remaining {
+            return Err(io::Error::other(MessageTooLarge { limit: self.limit }));
+        }
+        let written = self.inner.write(buf)?;
+        self.remaining -= written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -286,11 +609,59 @@ mod tests {
 
     use super::*;
 
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn enabled(inner: &[Option<CompressionEncoding>]) -> EnabledCompressionEncodings {
+        let mut encodings = EnabledCompressionEncodings::default();
+        for encoding in inner.iter().flatten() {
+            encodings.enable(*encoding);
+        }
+        encodings
+    }
+
+    struct Named(&'static str);
+
+    impl Compressor for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn compress(
+            &self,
+            input: &[u8],
+            output: &mut dyn io::Write,
+            _: CompressionLevel,
+        ) -> io::Result<()> {
+            output.write_all(input)
+        }
+
+        fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+            output.write_all(input)
+        }
+    }
+
+    #[test]
+    fn enable_custom_encoding() {
+        let mut encodings = EnabledCompressionEncodings::default();
+        encodings.enable(CompressionEncoding::Custom(&Named("x-snappy")));
+
+        assert_eq!(
+            encodings.accept_encoding_header_value().unwrap(),
+            HeaderValue::from_static("x-snappy,identity"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid compression encoding name `x snappy`")]
+    fn enable_custom_encoding_with_invalid_name() {
+        let mut encodings = EnabledCompressionEncodings::default();
+        encodings.enable(CompressionEncoding::Custom(&Named("x snappy")));
+    }
+
     #[test]
     fn convert_none_into_header_value() {
         let encodings = EnabledCompressionEncodings::default();
 
-        assert!(encodings.into_accept_encoding_header_value().is_none());
+        assert!(encodings.accept_encoding_header_value().is_none());
     }
 
     #[test]
@@ -298,17 +669,13 @@ mod tests {
     fn convert_gzip_into_header_value() {
         const GZIP: HeaderValue = HeaderValue::from_static("gzip,identity");
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [Some(CompressionEncoding::Gzip), None],
-        };
+        let encodings = enabled(&[Some(CompressionEncoding::Gzip), None]);
 
-        assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), GZIP);
+        assert_eq!(encodings.accept_encoding_header_value().unwrap(), GZIP);
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [None, Some(CompressionEncoding::Gzip)],
-        };
+        let encodings = enabled(&[None, Some(CompressionEncoding::Gzip)]);
 
-        assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), GZIP);
+        assert_eq!(encodings.accept_encoding_header_value().unwrap(), GZIP);
     }
 
     #[test]
@@ -316,44 +683,76 @@ mod tests {
     fn convert_zstd_into_header_value() {
         const ZSTD: HeaderValue = HeaderValue::from_static("zstd,identity");
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [Some(CompressionEncoding::Zstd), None],
-        };
+        let encodings = enabled(&[Some(CompressionEncoding::Zstd), None]);
 
-        assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), ZSTD);
+        assert_eq!(encodings.accept_encoding_header_value().unwrap(), ZSTD);
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [None, Some(CompressionEncoding::Zstd)],
-        };
+        let encodings = enabled(&[None, Some(CompressionEncoding::Zstd)]);
 
-        assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), ZSTD);
+        assert_eq!(encodings.accept_encoding_header_value().unwrap(), ZSTD);
     }
 
     #[test]
     #[cfg(all(feature = "gzip", feature = "zstd"))]
     fn convert_gzip_and_zstd_into_header_value() {
-        let encodings = EnabledCompressionEncodings {
-            inner: [
-                Some(CompressionEncoding::Gzip),
-                Some(CompressionEncoding::Zstd),
-            ],
-        };
+        let encodings = enabled(&[
+            Some(CompressionEncoding::Gzip),
+            Some(CompressionEncoding::Zstd),
+        ]);
 
         assert_eq!(
-            encodings.into_accept_encoding_header_value().unwrap(),
+            encodings.accept_encoding_header_value().unwrap(),
             HeaderValue::from_static("gzip,zstd,identity"),
         );
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [
-                Some(CompressionEncoding::Zstd),
-                Some(CompressionEncoding::Gzip),
-            ],
-        };
+        let encodings = enabled(&[
+            Some(CompressionEncoding::Zstd),
+            Some(CompressionEncoding::Gzip),
+        ]);
 
         assert_eq!(
-            encodings.into_accept_encoding_header_value().unwrap(),
+            encodings.accept_encoding_header_value().unwrap(),
             HeaderValue::from_static("zstd,gzip,identity"),
         );
     }
+
+    #[test]
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    fn send_compression_uses_level_of_encoding() {
+        let mut encodings = EnabledCompressionEncodings::default();
+        encodings.enable(CompressionEncoding::Gzip);
+        encodings.enable_with_level(CompressionEncoding::Zstd, CompressionLevel::Fastest);
+        encodings.enable_with_level(CompressionEncoding::Gzip, CompressionLevel::Best);
+        encodings.set_min_size(1024);
+
+        let gzip = encodings.send_compression(CompressionEncoding::Gzip);
+        assert_eq!(gzip.level, CompressionLevel::Best);
+        assert_eq!(gzip.min_size, 1024);
+
+        let zstd = encodings.send_compression(CompressionEncoding::Zstd);
+        assert_eq!(zstd.level, CompressionLevel::Fastest);
+
+        assert_eq!(
+            encodings.accept_encoding_header_value().unwrap(),
+            HeaderValue::from_static("gzip,zstd,identity"),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn extend_prefers_min_size_of_other() {
+        let mut encodings = EnabledCompressionEncodings::default();
+        encodings.enable(CompressionEncoding::Gzip);
+        encodings.set_min_size(1024);
+
+        let mut other = EnabledCompressionEncodings::default();
+        other.set_min_size(16);
+        encodings.extend(other);
+        let gzip = encodings.send_compression(CompressionEncoding::Gzip);
+        assert_eq!(gzip.min_size, 16);
+
+        encodings.extend(EnabledCompressionEncodings::default());
+        let gzip = encodings.send_compression(CompressionEncoding::Gzip);
+        assert_eq!(gzip.min_size, 16);
+    }
 }
@@ -1,7 +1,9 @@
-use super::compression::{decompress, CompressionEncoding, CompressionSettings};
+use super::compression::{
+    decompress, CompressionEncoding, CompressionLevel, CompressionSettings, MessageTooLarge,
+};
 use super::{BufferSettings, DecodeBuf, Decoder, DEFAULT_MAX_RECV_MESSAGE_SIZE, HEADER_SIZE};
 use crate::{body::BoxBody, metadata::MetadataMap, Code, Status};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use http::{HeaderMap, StatusCode};
 use http_body::Body;
 use http_body_util::BodyExt;
@@ -20,14 +22,21 @@ use tracing::{debug, trace};
 /// to fetch the message stream and trailing metadata
 pub struct Streaming<T> {
     decoder: Box<dyn Decoder<Item = T, Error = Status> + Send + 'static>,
+    inspect: Option<Inspect<T>>,
     inner: StreamingInner,
 }
 
+type Inspect<T> = Box<dyn FnMut(&T) -> Result<(), Status> + Send + 'static>;
+
 struct StreamingInner {
     body: BoxBody,
     state: State,
     direction: Direction,
     buf: BytesMut,
+    // The unread part of the last data frame. It's only copied into `buf` when a message spans
+    // several frames, so messages within a frame are decoded without copying. Large messages
+    // gathered in `buf` are split off it as well, smaller ones are decoded in place.
+    frame: Bytes,
     trailers: Option<HeaderMap>,
     decompress_buf: BytesMut,
     encoding: Option<CompressionEncoding>,
@@ -46,6 +55,14 @@ enum State {
     Error(Option<Status>),
 }
 
+/// A message read from the body.
+enum Chunk<'a> {
+    /// An uncompressed message, referencing the received bytes.
+    Bytes(Bytes),
+    /// A message in a buffer that is reused for the following messages.
+    Buffered(DecodeBuf<'a>),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum Direction {
     Request,
@@ -124,6 +141,7 @@ impl<T> Streaming<T> {
         let buffer_size = decoder.buffer_settings().buffer_size;
         Self {
             decoder: Box::new(decoder),
+            inspect: None,
             inner: StreamingInner {
                 body: body
                     .map_frame(|frame| frame.map_data(|mut buf| buf.copy_to_bytes(buf.remaining())))
@@ -132,6 +150,7 @@ impl<T> Streaming<T> {
                 state: State::ReadHeader,
                 direction,
                 buf: BytesMut::with_capacity(buffer_size),
+                frame: Bytes::new(),
                 trailers: None,
                 decompress_buf: BytesMut::new(),
                 encoding,
@@ -142,16 +161,25 @@ impl<T> Streaming<T> {
 }
 
 impl StreamingInner {
+    /// The received data that hasn't been decoded yet, either in `frame` or in `buf`.
+    fn data(&mut self) -> &mut dyn Buf {
+        if self.frame.has_remaining() {
+            &mut self.frame
+        } else {
+            &mut self.buf
+        }
+    }
+
     fn decode_chunk(
         &mut self,
         buffer_settings: BufferSettings,
-    ) -> Result<Option<DecodeBuf<'_>>, Status> {
+    ) -> Result<Option<Chunk<'_>>, Status> {
         if let State::ReadHeader = self.state {
-            if self.buf.remaining() < HEADER_SIZE {
+            if self.data().remaining() < HEADER_SIZE {
                 return Ok(None);
             }
 
-            let compression_encoding = match self.buf.get_u8() {
+            let compression_encoding = match self.data().get_u8() {
                 0 => None,
                 1 => {
                     {
@@ -180,7 +208,7 @@ impl StreamingInner {
                 }
             };
 
-            let len = self.buf.get_u32() as usize;
+            let len = self.data().get_u32() as usize;
             let limit = self
                 .max_message_size
                 .unwrap_or(DEFAULT_MAX_RECV_MESSAGE_SIZE);
@@ -193,7 +221,9 @@ impl StreamingInner {
                 ));
             }
 
-            self.buf.reserve(len);
+            if self.frame.remaining() < len {
+                self.buf.reserve(len);
+            }
 
             self.state = State::ReadBody {
                 compression: compression_encoding,
@@ -204,39 +234,62 @@ impl StreamingInner {
         if let State::ReadBody { len, compression } = self.state {
             // if we haven't read enough of the message then return and keep
             // reading
-            if self.buf.remaining() < len || self.buf.len() < len {
+            let message = if self.frame.remaining() >= len {
+                self.frame.split_to(len)
+            } else if self.buf.remaining() < len {
                 return Ok(None);
-            }
-
-            let decode_buf = if let Some(encoding) = compression {
-                self.decompress_buf.clear();
-
-                if let Err(err) = decompress(
-                    CompressionSettings {
-                        encoding,
-                        buffer_growth_interval: buffer_settings.buffer_size,
-                    },
-                    &mut self.buf,
-                    &mut self.decompress_buf,
-                    len,
-                ) {
-                    let message = if let Direction::Response(status) = self.direction {
-                        format!(
-                            "Error decompressing: {}, while receiving response with status: {}",
-                            err, status
-                        )
-                    } else {
-                        format!("Error decompressing: {}, while sending request", err)
-                    };
-                    return Err(Status::internal(message));
-                }
-                let decompressed_len = self.decompress_buf.len();
-                DecodeBuf::new(&mut self.decompress_buf, decompressed_len)
+            } else if compression.is_none() && len <= buffer_settings.buffer_size {
+                // Handing out a small message gathered from several frames would keep `buf` from
+                // reusing its memory, so it's decoded in place instead.
+                self.state = State::ReadHeader;
+                return Ok(Some(Chunk::Buffered(DecodeBuf::new(&mut self.buf, len))));
             } else {
-                DecodeBuf::new(&mut self.buf, len)
+                self.buf.split_to(len).freeze()
             };
 
-            return Ok(Some(decode_buf));
+            self.state = State::ReadHeader;
+
+            let Some(encoding) = compression else {
+                return Ok(Some(Chunk::Bytes(message)));
+            };
+
+            self.decompress_buf.clear();
+
+            if let Err(err) = decompress(
+                CompressionSettings {
+                    encoding,
+                    level: CompressionLevel::Default,
+                    buffer_growth_interval: buffer_settings.buffer_size,
+                },
+                &message,
+                &mut self.decompress_buf,
+                self.max_message_size
+                    .unwrap_or(DEFAULT_MAX_RECV_MESSAGE_SIZE),
+            ) {
+                if let Some(err) = err
+                    .get_ref()
+                    .and_then(|err| err.downcast_ref::<MessageTooLarge>())
+                {
+                    return Err(Status::resource_exhausted(format!(
+                        "Error decompressing: {}",
+                        err
+                    )));
+                }
+                let message = if let Direction::Response(status) = self.direction {
+                    format!(
+                        "Error decompressing: {}, while receiving response with status: {}",
+                        err, status
+                    )
+                } else {
+                    format!("Error decompressing: {}, while sending request", err)
+                };
+                return Err(Status::internal(message));
+            }
+            let decompressed_len = self.decompress_buf.len();
+            return Ok(Some(Chunk::Buffered(DecodeBuf::new(
+                &mut self.decompress_buf,
+                decompressed_len,
+            ))));
         }
 
         Ok(None)
@@ -261,7 +314,20 @@ impl StreamingInner {
         Poll::Ready(if let Some(frame) = chunk {
             match frame {
                 frame if frame.is_data() => {
-                    self.buf.put(frame.into_data().unwrap());
+                    let data = frame.into_data().unwrap();
+
+                    // a message spans the frames, so gather them in `buf`
+                    if self.frame.has_remaining() {
+                        let frame = std::mem::take(&mut self.frame);
+                        self.buf.put(frame);
+                    }
+
+                    if self.buf.has_remaining() {
+                        self.buf.put(data);
+                    } else {
+                        self.frame = data;
+                    }
+
                     Ok(Some(()))
                 }
                 frame if frame.is_trailers() => {
@@ -280,7 +346,7 @@ impl StreamingInner {
             }
         } else {
             // FIXME: improve buf usage.
-            if self.buf.has_remaining() {
+            if self.buf.has_remaining() || self.frame.has_remaining() {
                 trace!("unexpected EOF decoding stream, state: {:?}", self.state);
                 Err(Status::internal("Unexpected EOF decoding stream."))
             } else {
@@ -374,17 +440,52 @@ impl<T> Streaming<T> {
         Ok(None)
     }
 
+    /// Call `f` with each message before it is returned.
+    ///
+    /// If `f` returns an error, that error is returned in place of the message and the stream
+    /// ends.
+    ///
+    /// ```rust
+    /// # use tonic::{Streaming, Status};
+    /// # fn inspect_ex(request: Streaming<String>) -> Streaming<String> {
+    /// request.inspect_messages(|message| {
+    ///     if message.is_empty() {
+    ///         return Err(Status::invalid_argument("empty message"));
+    ///     }
+    ///     Ok(())
+    /// })
+    /// # }
+    /// ```
+    pub fn inspect_messages<F>(mut self, mut f: F) -> Self
+    where
+        F: FnMut(&T) -> Result<(), Status> + Send + 'static,
+        T: 'static,
+    {
+        self.inspect = Some(match self.inspect.take() {
+            Some(mut inspect) => Box::new(move |message| {
+                inspect(message)?;
+                f(message)
+            }),
+            None => Box::new(f),
+        });
+        self
+    }
+
     fn decode_chunk(&mut self) -> Result<Option<T>, Status> {
-        match self.inner.decode_chunk(self.decoder.buffer_settings())? {
-            Some(mut decode_buf) => match self.decoder.decode(&mut decode_buf)? {
-                Some(msg) => {
-                    self.inner.state = State::ReadHeader;
-                    Ok(Some(msg))
-                }
-                None => Ok(None),
-            },
-            None => Ok(None),
+        let message = match self.inner.decode_chunk(self.decoder.buffer_settings())? {
+            Some(Chunk::Bytes(bytes)) => self.decoder.decode_bytes(bytes)?,
+            Some(Chunk::Buffered(mut decode_buf)) => self.decoder.decode(&mut decode_buf)?,
+            None => None,
+        };
+
+        if let (Some(message), Some(inspect)) = (&message, &mut self.inspect) {
+            if let Err(status) = inspect(message) {
+                self.inner.state = State::Error(None);
+                return Err(status);
+            }
         }
+
+        Ok(message)
     }
 }
 
@@ -1,55 +1,211 @@
 use super::compression::{
-    compress, CompressionEncoding, CompressionSettings, SingleMessageCompressionOverride,
+    compress, CompressionEncoding, CompressionSettings, SendCompression,
+    SingleMessageCompressionOverride,
+};
+use super::{
+    BufferSettings, CallMessageLimits, EncodeBuf, Encoder, DEFAULT_MAX_SEND_MESSAGE_SIZE,
+    HEADER_SIZE,
 };
-use super::{BufferSettings, EncodeBuf, Encoder, DEFAULT_MAX_SEND_MESSAGE_SIZE, HEADER_SIZE};
 use crate::Status;
 use bytes::{BufMut, Bytes, BytesMut};
 use http::HeaderMap;
 use http_body::{Body, Frame};
 use pin_project::pin_project;
 use std::{
+    collections::VecDeque,
+    fmt,
+    marker::PhantomData,
     pin::Pin,
     task::{ready, Context, Poll},
 };
 use tokio_stream::{adapters::Fuse, Stream, StreamExt};
 
+/// A message encoded by [`EncodeBody`], along with whether it may be compressed.
+///
+/// Every message converts into an `EncodeItem` compressed like the rest of the stream. Streams of
+/// `EncodeItem`s can mark individual messages to be sent uncompressed, for example because they
+/// hold media that is already compressed, or send messages that are already encoded.
+///
+/// ```
+/// # use tonic::{codec::EncodeItem, Status};
+/// # struct Frame;
+/// # fn media_frame() -> Frame { Frame }
+/// # fn metadata_frame() -> Frame { Frame }
+/// let stream = tokio_stream::iter([
+///     Ok::<_, Status>(EncodeItem::new(metadata_frame())),
+///     Ok(EncodeItem::uncompressed(media_frame())),
+/// ]);
+/// ```
+///
+/// Generated servers and clients only send `EncodeItem`s for methods generated with
+/// `tonic_build::Builder::pre_encoded_messages` enabled, otherwise they take plain messages and
+/// every message of a stream is compressed alike.
+#[derive(Debug, Clone)]
+pub struct EncodeItem<T> {
+    payload: Payload<T>,
+    compression_override: SingleMessageCompressionOverride,
+}
+
+#[derive(Debug, Clone)]
+enum Payload<T> {
+    Message(T),
+    Encoded(EncodedBytes<T>),
+}
+
+impl<T> EncodeItem<T> {
+    /// Wrap a message that is compressed like the rest of the stream.
+    pub fn new(message: T) -> Self {
+        Self {
+            payload: Payload::Message(message),
+            compression_override: SingleMessageCompressionOverride::Inherit,
+        }
+    }
+
+    /// Wrap a message that is sent uncompressed, even if the stream is compressed.
+    pub fn uncompressed(message: T) -> Self {
+        Self {
+            payload: Payload::Message(message),
+            compression_override: SingleMessageCompressionOverride::Disable,
+        }
+    }
+
+    /// Wrap a message that is already encoded, which is sent without invoking the encoder.
+    pub fn encoded(bytes: EncodedBytes<T>) -> Self {
+        Self {
+            payload: Payload::Encoded(bytes),
+            compression_override: SingleMessageCompressionOverride::Inherit,
+        }
+    }
+
+    /// Returns whether the message is sent uncompressed.
+    pub fn is_uncompressed(&self) -> bool {
+        self.compression_override == SingleMessageCompressionOverride::Disable
+    }
+
+    /// Get a reference to the message, or `None` if it is already encoded.
+    pub fn get_ref(&self) -> Option<&T> {
+        match &self.payload {
+            Payload::Message(message) => Some(message),
+            Payload::Encoded(_) => None,
+        }
+    }
+
+    /// Consumes `self`, returning the message, or `None` if it is already encoded.
+    pub fn into_inner(self) -> Option<T> {
+        match self.payload {
+            Payload::Message(message) => Some(message),
+            Payload::Encoded(_) => None,
+        }
+    }
+}
+
+impl<T> From<T> for EncodeItem<T> {
+    fn from(message: T) -> Self {
+        Self::new(message)
+    }
+}
+
+impl<T> From<EncodedBytes<T>> for EncodeItem<T> {
+    fn from(bytes: EncodedBytes<T>) -> Self {
+        Self::encoded(bytes)
+    }
+}
+
+/// A message of type `T` that is already encoded, for example a cached response.
+///
+/// The bytes are sent as they are, so they must hold the message as encoded by the codec of the
+/// call, without the gRPC message header. Convert it into an [`EncodeItem`] to send it in place of
+/// a `T`.
+///
+/// ```
+/// # #[cfg(feature = "prost")] {
+/// use prost::Message;
+/// use tonic::codec::{EncodeItem, EncodedBytes};
+///
+/// // `String` encodes as the `google.protobuf.StringValue` message
+/// let cached = EncodedBytes::<String>::new("cached".to_string().encode_to_vec());
+/// let item = EncodeItem::encoded(cached.clone());
+/// assert_eq!(item.get_ref(), None);
+/// # }
+/// ```
+pub struct EncodedBytes<T> {
+    bytes: Bytes,
+    _pd: PhantomData<fn() -> T>,
+}
+
+impl<T> EncodedBytes<T> {
+    /// Wrap the encoded bytes of a `T`.
+    pub fn new(bytes: impl Into<Bytes>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            _pd: PhantomData,
+        }
+    }
+
+    /// Get a reference to the encoded bytes.
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// Consumes `self`, returning the encoded bytes.
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl<T> Clone for EncodedBytes<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes.clone())
+    }
+}
+
+impl<T> fmt::Debug for EncodedBytes<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EncodedBytes").field(&self.bytes).finish()
+    }
+}
+
 /// Combinator for efficient encoding of messages into reasonably sized buffers.
-/// EncodedBytes encodes ready messages from its delegate stream into a BytesMut,
+/// EncodedMessages encodes ready messages from its delegate stream into a BytesMut,
 /// splitting off and yielding a buffer when either:
 ///  * The delegate stream polls as not ready, or
 ///  * The encoded buffer surpasses YIELD_THRESHOLD.
-#[pin_project(project = EncodedBytesProj)]
+/// Chunks appended by the encoder without copying are yielded as separate buffers.
+#[pin_project(project = EncodedMessagesProj)]
 #[derive(Debug)]
-struct EncodedBytes<T, U> {
+struct EncodedMessages<T, U> {
     #[pin]
     source: Fuse<U>,
     encoder: T,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<SendCompression>,
     max_message_size: Option<usize>,
+    call_limits: Option<CallMessageLimits>,
     buf: BytesMut,
+    chunks: Vec<(usize, Bytes)>,
+    pending: VecDeque<Bytes>,
     uncompression_buf: BytesMut,
     error: Option<Status>,
 }
 
-impl<T: Encoder, U: Stream> EncodedBytes<T, U> {
+impl<T: Encoder, U: Stream> EncodedMessages<T, U> {
     fn new(
         encoder: T,
         source: U,
-        compression_encoding: Option<CompressionEncoding>,
+        compression: Option<SendCompression>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
+        call_limits: Option<CallMessageLimits>,
     ) -> Self {
         let buffer_settings = encoder.buffer_settings();
         let buf = BytesMut::with_capacity(buffer_settings.buffer_size);
 
-        let compression_encoding =
-            if compression_override == SingleMessageCompressionOverride::Disable {
-                None
-            } else {
-                compression_encoding
-            };
+        let compression = if compression_override == SingleMessageCompressionOverride::Disable {
+            None
+        } else {
+            compression
+        };
 
-        let uncompression_buf = if compression_encoding.is_some() {
+        let uncompression_buf = if compression.is_some() {
             BytesMut::with_capacity(buffer_settings.buffer_size)
         } else {
             BytesMut::new()
@@ -58,33 +214,48 @@ impl<T: Encoder, U: Stream> EncodedBytes<T, U> {
         Self {
             source: source.fuse(),
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
+            call_limits,
             buf,
+            chunks: Vec::new(),
+            pending: VecDeque::new(),
             uncompression_buf,
             error: None,
         }
     }
 }
 
-impl<T, U> Stream for EncodedBytes<T, U>
+impl<T, U, I> Stream for EncodedMessages<T, U>
 where
     T: Encoder<Error = Status>,
-    U: Stream<Item = Result<T::Item, Status>>,
+    U: Stream<Item = Result<I, Status>>,
+    I: Into<EncodeItem<T::Item>>,
 {
     type Item = Result<Bytes, Status>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let EncodedBytesProj {
+        let EncodedMessagesProj {
             mut source,
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
+            call_limits,
             buf,
+            chunks,
+            pending,
             uncompression_buf,
             error,
         } = self.project();
         let buffer_settings = encoder.buffer_settings();
+        let max_message_size = match call_limits {
+            Some(limits) => limits.request(*max_message_size),
+            None => *max_message_size,
+        };
+
+        if let Some(chunk) = pending.pop_front() {
+            return Poll::Ready(Some(Ok(chunk)));
+        }
 
         if let Some(status) = error.take() {
             return Poll::Ready(Some(Err(status)));
@@ -99,23 +270,25 @@ where
                     return Poll::Ready(None);
                 }
                 Poll::Pending | Poll::Ready(None) => {
-                    return Poll::Ready(Some(Ok(buf.split_to(buf.len()).freeze())));
+                    return Poll::Ready(Some(Ok(split_chunks(buf, chunks, pending))));
                 }
                 Poll::Ready(Some(Ok(item))) => {
                     if let Err(status) = encode_item(
                         encoder,
                         buf,
+                        chunks,
                         uncompression_buf,
-                        *compression_encoding,
-                        *max_message_size,
+                        *compression,
+                        max_message_size,
                         buffer_settings,
-                        item,
+                        item.into(),
                     ) {
                         return Poll::Ready(Some(Err(status)));
                     }
 
-                    if buf.len() >= buffer_settings.yield_threshold {
-                        return Poll::Ready(Some(Ok(buf.split_to(buf.len()).freeze())));
+                    let appended_len: usize = chunks.iter().map(|(_, chunk)| chunk.len()).sum();
+                    if buf.len() + appended_len >= buffer_settings.yield_threshold {
+                        return Poll::Ready(Some(Ok(split_chunks(buf, chunks, pending))));
                     }
                 }
                 Poll::Ready(Some(Err(status))) => {
@@ -123,67 +296,118 @@ where
                         return Poll::Ready(Some(Err(status)));
                     }
                     *error = Some(status);
-                    return Poll::Ready(Some(Ok(buf.split_to(buf.len()).freeze())));
+                    return Poll::Ready(Some(Ok(split_chunks(buf, chunks, pending))));
                 }
             }
         }
     }
 }
 
+/// Splits off the encoded messages, returning the first buffer and queueing the others.
+fn split_chunks(
+    buf: &mut BytesMut,
+    chunks: &mut Vec<(usize, Bytes)>,
+    pending: &mut VecDeque<Bytes>,
+) -> Bytes {
+    let mut data = buf.split().freeze();
+    let mut offset = 0;
+
+    for (at, chunk) in chunks.drain(..) {
+        if at > offset {
+            pending.push_back(data.split_to(at - offset));
+            offset = at;
+        }
+        pending.push_back(chunk);
+    }
+
+    if !data.is_empty() {
+        pending.push_back(data);
+    }
+
+    pending.pop_front().expect("encoded messages aren't empty")
+}
+
+#[allow(clippy::too_many_arguments)]
 fn encode_item<T>(
     encoder: &mut T,
     buf: &mut BytesMut,
+    chunks: &mut Vec<(usize, Bytes)>,
     uncompression_buf: &mut BytesMut,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<SendCompression>,
     max_message_size: Option<usize>,
     buffer_settings: BufferSettings,
-    item: T::Item,
+    item: EncodeItem<T::Item>,
 ) -> Result<(), Status>
 where
     T: Encoder<Error = Status>,
 {
     let offset = buf.len();
+    let first_chunk = chunks.len();
 
     buf.reserve(HEADER_SIZE);
     unsafe {
         buf.advance_mut(HEADER_SIZE);
     }
 
-    if let Some(encoding) = compression_encoding {
+    let compression = compression.filter(|_| !item.is_uncompressed());
+
+    let mut compressed = false;
+    if let Some(compression) = compression {
         uncompression_buf.clear();
 
-        encoder
-            .encode(item, &mut EncodeBuf::new(uncompression_buf))
-            .map_err(|err| Status::internal(format!("Error encoding: {}", err)))?;
+        match item.payload {
+            Payload::Message(message) => encoder
+                .encode(message, &mut EncodeBuf::new(uncompression_buf))
+                .map_err(|err| Status::internal(format!("Error encoding: {}", err)))?,
+            Payload::Encoded(bytes) => uncompression_buf.extend_from_slice(bytes.as_bytes()),
+        }
 
         let uncompressed_len = uncompression_buf.len();
+        check_message_size(uncompressed_len, max_message_size)?;
 
-        compress(
-            CompressionSettings {
-                encoding,
-                buffer_growth_interval: buffer_settings.buffer_size,
-            },
-            uncompression_buf,
-            buf,
-            uncompressed_len,
-        )
-        .map_err(|err| Status::internal(format!("Error compressing: {}", err)))?;
+        if uncompressed_len < compression.min_size {
+            // too small to be worth compressing, send it as is
+            buf.extend_from_slice(uncompression_buf);
+        } else {
+            compress(
+                CompressionSettings {
+                    encoding: compression.encoding,
+                    level: compression.level,
+                    buffer_growth_interval: buffer_settings.buffer_size,
+                },
+                uncompression_buf,
+                buf,
+                uncompressed_len,
+            )
+            .map_err(|err| Status::internal(format!("Error compressing: {}", err)))?;
+            compressed = true;
+        }
     } else {
-        encoder
-            .encode(item, &mut EncodeBuf::new(buf))
-            .map_err(|err| Status::internal(format!("Error encoding: {}", err)))?;
+        let mut buf = EncodeBuf::vectored(buf, chunks);
+        match item.payload {
+            Payload::Message(message) => encoder
+                .encode(message, &mut buf)
+                .map_err(|err| Status::internal(format!("Error encoding: {}", err)))?,
+            Payload::Encoded(bytes) => buf.append_bytes(bytes.into_bytes()),
+        }
     }
 
+    let appended_len = chunks[first_chunk..]
+        .iter()
+        .map(|(_, chunk)| chunk.len())
+        .sum();
+
     // now that we know length, we can write the header
-    finish_encoding(compression_encoding, max_message_size, &mut buf[offset..])
+    finish_encoding(
+        compressed,
+        max_message_size,
+        appended_len,
+        &mut buf[offset..],
+    )
 }
 
-fn finish_encoding(
-    compression_encoding: Option<CompressionEncoding>,
-    max_message_size: Option<usize>,
-    buf: &mut [u8],
-) -> Result<(), Status> {
-    let len = buf.len() - HEADER_SIZE;
+/// Check the length of a message, before and after compression.
+fn check_message_size(len: usize, max_message_size: Option<usize>) -> Result<(), Status> {
     let limit = max_message_size.unwrap_or(DEFAULT_MAX_SEND_MESSAGE_SIZE);
     if len > limit {
         return Err(Status::out_of_range(format!(
@@ -191,6 +415,17 @@ fn finish_encoding(
             len, limit
         )));
     }
+    Ok(())
+}
+
+fn finish_encoding(
+    compressed: bool,
+    max_message_size: Option<usize>,
+    appended_len: usize,
+    buf: &mut [u8],
+) -> Result<(), Status> {
+    let len = buf.len() - HEADER_SIZE + appended_len;
+    check_message_size(len, max_message_size)?;
 
     if len > u32::MAX as usize {
         return Err(Status::resource_exhausted(format!(
@@ -199,7 +434,7 @@ fn finish_encoding(
     }
     {
         let mut buf = &mut buf[..HEADER_SIZE];
-        buf.put_u8(compression_encoding.is_some() as u8);
+        buf.put_u8(compressed as u8);
         buf.put_u32(len as u32);
     }
 
@@ -217,7 +452,7 @@ enum Role {
 #[derive(Debug)]
 pub struct EncodeBody<T, U> {
     #[pin]
-    inner: EncodedBytes<T, U>,
+    inner: EncodedMessages<T, U>,
     state: EncodeState,
 }
 
@@ -236,14 +471,31 @@ impl<T: Encoder, U: Stream> EncodeBody<T, U> {
         source: U,
         compression_encoding: Option<CompressionEncoding>,
         max_message_size: Option<usize>,
+    ) -> Self {
+        Self::client(
+            encoder,
+            source,
+            compression_encoding.map(Into::into),
+            max_message_size,
+            None,
+        )
+    }
+
+    pub(crate) fn client(
+        encoder: T,
+        source: U,
+        compression: Option<SendCompression>,
+        max_message_size: Option<usize>,
+        call_limits: Option<CallMessageLimits>,
     ) -> Self {
         Self {
-            inner: EncodedBytes::new(
+            inner: EncodedMessages::new(
                 encoder,
                 source,
-                compression_encoding,
+                compression,
                 SingleMessageCompressionOverride::default(),
                 max_message_size,
+                call_limits,
             ),
             state: EncodeState {
                 error: None,
@@ -261,14 +513,31 @@ impl<T: Encoder, U: Stream> EncodeBody<T, U> {
         compression_encoding: Option<CompressionEncoding>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
+    ) -> Self {
+        Self::server(
+            encoder,
+            source,
+            compression_encoding.map(Into::into),
+            compression_override,
+            max_message_size,
+        )
+    }
+
+    pub(crate) fn server(
+        encoder: T,
+        source: U,
+        compression: Option<SendCompression>,
+        compression_override: SingleMessageCompressionOverride,
+        max_message_size: Option<usize>,
     ) -> Self {
         Self {
-            inner: EncodedBytes::new(
+            inner: EncodedMessages::new(
                 encoder,
                 source,
-                compression_encoding,
+                compression,
                 compression_override,
                 max_message_size,
+                None,
             ),
             state: EncodeState {
                 error: None,
@@ -300,10 +569,11 @@ impl EncodeState {
     }
 }
 
-impl<T, U> Body for EncodeBody<T, U>
+impl<T, U, I> Body for EncodeBody<T, U>
 where
     T: Encoder<Error = Status>,
-    U: Stream<Item = Result<T::Item, Status>>,
+    U: Stream<Item = Result<I, Status>>,
+    I: Into<EncodeItem<T::Item>>,
 {
     type Data = Bytes;
     type Error = Status;
@@ -0,0 +1,156 @@
+use super::{BufferSettings, Codec, DecodeBuf, Decoder, Encoder};
+use crate::codec::EncodeBuf;
+use crate::Status;
+use bytes::{Buf, BufMut};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+/// A [`Codec`] that implements `application/grpc+json` via the serde library.
+///
+/// Messages are encoded as JSON with [`serde_json`], so they need to implement
+/// [`Serialize`] and [`Deserialize`](serde::Deserialize). Both ends of a call are sent the
+/// `application/grpc+json` content type, which servers using this codec require.
+#[derive(Debug, Clone)]
+pub struct JsonCodec<T, U> {
+    _pd: PhantomData<(T, U)>,
+}
+
+impl<T, U> JsonCodec<T, U> {
+    /// Create a new `JsonCodec`.
+    pub fn new() -> Self {
+        Self { _pd: PhantomData }
+    }
+}
+
+impl<T, U> Default for JsonCodec<T, U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, U> Codec for JsonCodec<T, U>
+where
+    T: Serialize + Send + 'static,
+    U: DeserializeOwned + Send + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+
+    type Encoder = JsonEncoder<T>;
+    type Decoder = JsonDecoder<U>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        JsonEncoder::new(BufferSettings::default())
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        JsonDecoder::new(BufferSettings::default())
+    }
+
+    fn content_subtype(&self) -> Option<&'static str> {
+        Some("json")
+    }
+}
+
+/// A [`Encoder`] that knows how to encode `T` as JSON.
+#[derive(Debug, Clone, Default)]
+pub struct JsonEncoder<T> {
+    _pd: PhantomData<T>,
+    buffer_settings: BufferSettings,
+}
+
+impl<T> JsonEncoder<T> {
+    /// Get a new encoder with explicit buffer settings
+    pub fn new(buffer_settings: BufferSettings) -> Self {
+        Self {
+            _pd: PhantomData,
+            buffer_settings,
+        }
+    }
+}
+
+impl<T: Serialize> Encoder for JsonEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        serde_json::to_writer(buf.writer(), &item).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        self.buffer_settings
+    }
+}
+
+/// A [`Decoder`] that knows how to decode `U` from JSON.
+#[derive(Debug, Clone, Default)]
+pub struct JsonDecoder<U> {
+    _pd: PhantomData<U>,
+    buffer_settings: BufferSettings,
+}
+
+impl<U> JsonDecoder<U> {
+    /// Get a new decoder with explicit buffer settings
+    pub fn new(buffer_settings: BufferSettings) -> Self {
+        Self {
+            _pd: PhantomData,
+            buffer_settings,
+        }
+    }
+}
+
+impl<U: DeserializeOwned> Decoder for JsonDecoder<U> {
+    type Item = U;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        // Map parse errors to an INTERNAL status code, as per
+        // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+        let item =
+            serde_json::from_reader(buf.reader()).map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Some(item))
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        self.buffer_settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EncodeBody, Streaming};
+    use http_body_util::BodyExt as _;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct HelloRequest {
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let mut codec = JsonCodec::<HelloRequest, HelloRequest>::default();
+        assert_eq!(codec.content_subtype(), Some("json"));
+
+        let messages = ["alice", "bob"].map(|name| {
+            Ok::<_, Status>(HelloRequest {
+                name: name.to_string(),
+            })
+        });
+        let body =
+            EncodeBody::new_client(codec.encoder(), tokio_stream::iter(messages), None, None);
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(
+            &bytes[5..],
+            b"{\"name\":\"alice\"}\0\0\0\0\x0e{\"name\":\"bob\"}"
+        );
+
+        let body = http_body_util::Full::new(bytes);
+        let mut stream = Streaming::new_request(codec.decoder(), body, None, None);
+        assert_eq!(stream.message().await.unwrap().unwrap().name, "alice");
+        assert_eq!(stream.message().await.unwrap().unwrap().name, "bob");
+        assert!(stream.message().await.unwrap().is_none());
+    }
+}
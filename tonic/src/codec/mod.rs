@@ -1,22 +1,38 @@
 //! Generic encoding and decoding.
 //!
-//! This module contains the generic `Codec`, `Encoder` and `Decoder` traits
-//! and a protobuf codec based on prost.
+//! This module contains the generic `Codec`, `Encoder` and `Decoder` traits,
+//! a protobuf codec based on prost and a JSON codec based on serde.
 
 mod buffer;
 pub(crate) mod compression;
 mod decode;
 mod encode;
+#[cfg(feature = "json")]
+mod json;
+mod negotiated;
 #[cfg(feature = "prost")]
 mod prost;
 
-use crate::Status;
-use std::io;
+use crate::{metadata::GRPC_CONTENT_TYPE, Status};
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 pub use self::buffer::{DecodeBuf, EncodeBuf};
-pub use self::compression::{CompressionEncoding, EnabledCompressionEncodings};
+pub use self::compression::{
+    CompressionEncoding, CompressionLevel, Compressor, EnabledCompressionEncodings,
+};
 pub use self::decode::Streaming;
-pub use self::encode::EncodeBody;
+pub use self::encode::{EncodeBody, EncodeItem, EncodedBytes};
+#[cfg(feature = "json")]
+pub use self::json::{JsonCodec, JsonDecoder, JsonEncoder};
+pub use self::negotiated::{Negotiated, NegotiatedCodec};
 #[cfg(feature = "prost")]
 pub use self::prost::ProstCodec;
 
@@ -116,6 +132,111 @@ pub trait Codec {
     fn encoder(&mut self) -> Self::Encoder;
     /// Fetch the decoder.
     fn decoder(&mut self) -> Self::Decoder;
+
+    /// The content subtype of the messages, sent as `application/grpc+{subtype}` in the
+    /// `content-type` header.
+    ///
+    /// The default is `None`, sending `application/grpc` which means protobuf.
+    fn content_subtype(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether servers can use this codec for requests of the given content subtype, which is
+    /// `proto` for `application/grpc`.
+    ///
+    /// Accepted requests are answered with the content type they were sent with. Requests that
+    /// aren't accepted are rejected with HTTP status `415 Unsupported Media Type` and the gRPC
+    /// status `Unimplemented`. By default a codec accepts only its
+    /// [`content_subtype`](Codec::content_subtype), or any subtype if it doesn't have one.
+    fn accept_content_subtype(&mut self, subtype: &str) -> bool {
+        match self.content_subtype() {
+            Some(expected) => expected.eq_ignore_ascii_case(subtype),
+            None => true,
+        }
+    }
+}
+
+/// The `content-type` of messages with the given content subtype.
+pub(crate) fn content_type(subtype: Option<&str>) -> HeaderValue {
+    match subtype {
+        Some(subtype) => HeaderValue::from_str(&format!("application/grpc+{subtype}"))
+            .expect("content subtype is a valid header value"),
+        None => GRPC_CONTENT_TYPE,
+    }
+}
+
+/// The content subtype of a `content-type` header, which is `None` for `application/grpc`,
+/// meaning `proto`.
+///
+/// Returns `None` if the content type isn't gRPC.
+pub(crate) fn content_subtype(headers: &HeaderMap) -> Option<Option<&str>> {
+    let content_type = headers.get(http::header::CONTENT_TYPE)?.to_str().ok()?;
+    let content_type = content_type.split(';').next()?.trim();
+
+    match content_type.strip_prefix("application/grpc")? {
+        "" => Some(None),
+        subtype => subtype.strip_prefix('+').map(Some),
+    }
+}
+
+/// Message size limits of a client call that are only known once the channel routes it, like
+/// those of a service config.
+///
+/// The client stores them in the request extensions before sending the request, and the channel
+/// lowers them before the request body is encoded or the response decoded.
+#[derive(Debug, Clone)]
+pub(crate) struct CallMessageLimits(Arc<MessageLimits>);
+
+#[derive(Debug)]
+struct MessageLimits {
+    request: AtomicUsize,
+    response: AtomicUsize,
+}
+
+impl CallMessageLimits {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(MessageLimits {
+            request: AtomicUsize::new(usize::MAX),
+            response: AtomicUsize::new(usize::MAX),
+        }))
+    }
+
+    /// Lower the limits of the call, keeping smaller ones.
+    #[cfg(feature = "channel")]
+    pub(crate) fn restrict(&self, request: Option<usize>, response: Option<usize>) {
+        if let Some(limit) = request {
+            self.0.request.fetch_min(limit, Ordering::Relaxed);
+        }
+        if let Some(limit) = response {
+            self.0.response.fetch_min(limit, Ordering::Relaxed);
+        }
+    }
+
+    /// The maximum size of request messages, given the limit of the client.
+    pub(crate) fn request(&self, limit: Option<usize>) -> Option<usize> {
+        restrict_limit(
+            limit,
+            self.0.request.load(Ordering::Relaxed),
+            DEFAULT_MAX_SEND_MESSAGE_SIZE,
+        )
+    }
+
+    /// The maximum size of response messages, given the limit of the client.
+    pub(crate) fn response(&self, limit: Option<usize>) -> Option<usize> {
+        restrict_limit(
+            limit,
+            self.0.response.load(Ordering::Relaxed),
+            DEFAULT_MAX_RECV_MESSAGE_SIZE,
+        )
+    }
+}
+
+fn restrict_limit(limit: Option<usize>, call_limit: usize, default: usize) -> Option<usize> {
+    if call_limit == usize::MAX {
+        limit
+    } else {
+        Some(limit.unwrap_or(default).min(call_limit))
+    }
 }
 
 /// Encodes gRPC message types
@@ -152,6 +273,20 @@ pub trait Decoder {
     /// for you.
     fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error>;
 
+    /// Decode a message from an owned buffer holding exactly the bytes of a full message.
+    ///
+    /// Tonic calls this for uncompressed messages, handing over the received bytes without
+    /// gathering them in an intermediate buffer when the message arrived in a single frame.
+    /// Messages spanning several frames are only handed over if they are larger than the
+    /// [buffer size](BufferSettings), smaller ones are decoded in place with
+    /// [`decode`](Decoder::decode) so the buffer can be reused.
+    /// Decoders can keep references into `src`, for example as `Bytes` fields, instead of
+    /// copying the payload. The default implementation decodes `src` with
+    /// [`decode`](Decoder::decode).
+    fn decode_bytes(&mut self, src: Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode(&mut DecodeBuf::from_bytes(src))
+    }
+
     /// Controls how tonic creates and expands decode buffers.
     fn buffer_settings(&self) -> BufferSettings {
         BufferSettings::default()
@@ -0,0 +1,181 @@
+use super::{BufferSettings, Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use crate::Status;
+use bytes::Bytes;
+
+/// A [`Codec`] that lets servers handle requests with either of two codecs, selected by the
+/// content subtype of the request.
+///
+/// The first codec that [accepts](Codec::accept_content_subtype) the request's subtype is used
+/// for the whole call and its subtype is echoed in the response. Clients always use the first
+/// codec. Nesting `NegotiatedCodec`s allows more than two codecs:
+///
+/// ```
+/// # #[cfg(all(feature = "prost", feature = "json"))] {
+/// use tonic::codec::{JsonCodec, NegotiatedCodec, ProstCodec};
+///
+/// # type Message = ();
+/// let codec = NegotiatedCodec::new(
+///     ProstCodec::<Message, Message>::default(),
+///     JsonCodec::<Message, Message>::default(),
+/// );
+/// # let _ = codec;
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NegotiatedCodec<A, B> {
+    first: A,
+    second: B,
+    use_second: bool,
+}
+
+impl<A, B> NegotiatedCodec<A, B> {
+    /// Create a new `NegotiatedCodec`, preferring `first` over `second`.
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            use_second: false,
+        }
+    }
+}
+
+impl<A, B> Codec for NegotiatedCodec<A, B>
+where
+    A: Codec,
+    B: Codec<Encode = A::Encode, Decode = A::Decode>,
+{
+    type Encode = A::Encode;
+    type Decode = A::Decode;
+
+    type Encoder = Negotiated<A::Encoder, B::Encoder>;
+    type Decoder = Negotiated<A::Decoder, B::Decoder>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        if self.use_second {
+            Negotiated::Second(self.second.encoder())
+        } else {
+            Negotiated::First(self.first.encoder())
+        }
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        if self.use_second {
+            Negotiated::Second(self.second.decoder())
+        } else {
+            Negotiated::First(self.first.decoder())
+        }
+    }
+
+    fn content_subtype(&self) -> Option<&'static str> {
+        if self.use_second {
+            self.second.content_subtype()
+        } else {
+            self.first.content_subtype()
+        }
+    }
+
+    fn accept_content_subtype(&mut self, subtype: &str) -> bool {
+        if self.first.accept_content_subtype(subtype) {
+            self.use_second = false;
+            true
+        } else if self.second.accept_content_subtype(subtype) {
+            self.use_second = true;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The [`Encoder`] or [`Decoder`] of the codec selected by a [`NegotiatedCodec`].
+#[derive(Debug, Clone)]
+pub enum Negotiated<A, B> {
+    /// The first codec was selected.
+    First(A),
+    /// The second codec was selected.
+    Second(B),
+}
+
+impl<A, B> Encoder for Negotiated<A, B>
+where
+    A: Encoder<Error = Status>,
+    B: Encoder<Item = A::Item, Error = Status>,
+{
+    type Item = A::Item;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        match self {
+            Negotiated::First(encoder) => encoder.encode(item, dst),
+            Negotiated::Second(encoder) => encoder.encode(item, dst),
+        }
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        match self {
+            Negotiated::First(encoder) => encoder.buffer_settings(),
+            Negotiated::Second(encoder) => encoder.buffer_settings(),
+        }
+    }
+}
+
+impl<A, B> Decoder for Negotiated<A, B>
+where
+    A: Decoder<Error = Status>,
+    B: Decoder<Item = A::Item, Error = Status>,
+{
+    type Item = A::Item;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        match self {
+            Negotiated::First(decoder) => decoder.decode(src),
+            Negotiated::Second(decoder) => decoder.decode(src),
+        }
+    }
+
+    fn decode_bytes(&mut self, src: Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        match self {
+            Negotiated::First(decoder) => decoder.decode_bytes(src),
+            Negotiated::Second(decoder) => decoder.decode_bytes(src),
+        }
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        match self {
+            Negotiated::First(decoder) => decoder.buffer_settings(),
+            Negotiated::Second(decoder) => decoder.buffer_settings(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "prost", feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::codec::{JsonCodec, ProstCodec};
+
+    type TestCodec = NegotiatedCodec<ProstCodec<(), ()>, JsonCodec<(), ()>>;
+
+    #[test]
+    fn selects_codec_by_subtype() {
+        let mut codec = TestCodec::default();
+        assert_eq!(codec.content_subtype(), None);
+
+        assert!(codec.accept_content_subtype("json"));
+        assert_eq!(codec.content_subtype(), Some("json"));
+        assert!(matches!(codec.encoder(), Negotiated::Second(_)));
+
+        assert!(codec.accept_content_subtype("proto"));
+        assert_eq!(codec.content_subtype(), None);
+        assert!(matches!(codec.decoder(), Negotiated::First(_)));
+
+        assert!(!codec.accept_content_subtype("xml"));
+
+        let mut codec = NegotiatedCodec::new(JsonCodec::<(), ()>::default(), TestCodec::default());
+        assert!(codec.accept_content_subtype("proto"));
+        assert!(matches!(
+            codec.encoder(),
+            Negotiated::Second(Negotiated::First(_))
+        ));
+    }
+}
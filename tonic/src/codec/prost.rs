@@ -1,6 +1,7 @@
 use super::{BufferSettings, Codec, DecodeBuf, Decoder, Encoder};
 use crate::codec::EncodeBuf;
 use crate::Status;
+use bytes::Bytes;
 use prost::Message;
 use std::marker::PhantomData;
 
@@ -72,6 +73,10 @@ where
             buffer_settings: BufferSettings::default(),
         }
     }
+
+    fn accept_content_subtype(&mut self, subtype: &str) -> bool {
+        subtype.eq_ignore_ascii_case("proto")
+    }
 }
 
 /// A [`Encoder`] that knows how to encode `T`.
@@ -136,6 +141,15 @@ impl<U: Message + Default> Decoder for ProstDecoder<U> {
         Ok(item)
     }
 
+    fn decode_bytes(&mut self, src: Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        // `Bytes` fields of the message are sliced out of `src` rather than copied
+        let item = Message::decode(src)
+            .map(Option::Some)
+            .map_err(from_decode_error)?;
+
+        Ok(item)
+    }
+
     fn buffer_settings(&self) -> BufferSettings {
         self.buffer_settings
     }
@@ -151,7 +165,7 @@ fn from_decode_error(error: prost::DecodeError) -> crate::Status {
 mod tests {
     use crate::codec::compression::SingleMessageCompressionOverride;
     use crate::codec::{
-        DecodeBuf, Decoder, EncodeBody, EncodeBuf, Encoder, Streaming, HEADER_SIZE,
+        BufferSettings, DecodeBuf, Decoder, EncodeBody, EncodeBuf, Encoder, Streaming, HEADER_SIZE,
     };
     use crate::Status;
     use bytes::{Buf, BufMut, BytesMut};
@@ -189,6 +203,69 @@ mod tests {
         assert_eq!(i, 1);
     }
 
+    #[tokio::test]
+    async fn decode_bytes_without_copying() {
+        use super::ProstDecoder;
+        use bytes::Bytes;
+        use prost::Message;
+
+        let msg = Bytes::from(vec![1u8; LEN]).encode_to_vec();
+
+        let mut buf = BytesMut::new();
+        buf.put_u8(0);
+        buf.put_u32(msg.len() as u32);
+        buf.put(&msg[..]);
+        let frame = buf.freeze();
+
+        let body = http_body_util::Full::new(frame.clone());
+        let mut stream = Streaming::new_request(ProstDecoder::<Bytes>::default(), body, None, None);
+
+        let output_msg = stream.message().await.unwrap().unwrap();
+        assert_eq!(output_msg.len(), LEN);
+
+        // the payload references the received frame
+        let frame_range = frame.as_ptr_range();
+        assert!(frame_range.contains(&output_msg.as_ptr()));
+    }
+
+    #[tokio::test]
+    async fn decode_split_messages_in_place_unless_large() {
+        /// Tells whether a message was handed over as `Bytes`.
+        struct BytesDecoder;
+
+        impl Decoder for BytesDecoder {
+            type Item = bool;
+            type Error = Status;
+
+            fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<bool>, Status> {
+                buf.advance(buf.remaining());
+                Ok(Some(false))
+            }
+
+            fn decode_bytes(&mut self, _: bytes::Bytes) -> Result<Option<bool>, Status> {
+                Ok(Some(true))
+            }
+        }
+
+        async fn decoded_as_bytes(len: usize, partial_len: usize) -> bool {
+            let mut buf = BytesMut::new();
+            buf.put_u8(0);
+            buf.put_u32(len as u32);
+            buf.put_bytes(0, len);
+
+            let body = body::MockBody::new(&buf[..], partial_len, 0);
+            let mut stream = Streaming::new_request(BytesDecoder, body, None, None);
+            stream.message().await.unwrap().unwrap()
+        }
+
+        let buffer_size = BufferSettings::default().buffer_size;
+        // within a single frame
+        assert!(decoded_as_bytes(100, HEADER_SIZE + 100).await);
+        // spanning frames
+        assert!(!decoded_as_bytes(100, HEADER_SIZE + 50).await);
+        assert!(decoded_as_bytes(buffer_size + 1, HEADER_SIZE + 50).await);
+    }
+
     #[tokio::test]
     async fn decode_max_message_size_exceeded() {
         let decoder = MockDecoder::default();
@@ -241,6 +318,75 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn encode_uncompressed_items() {
+        use crate::codec::{CompressionEncoding, EncodeItem};
+
+        let encoder = MockEncoder::default();
+
+        let msg = vec![0u8; 1024];
+        let messages = [
+            Ok::<_, Status>(EncodeItem::new(msg.clone())),
+            Ok(EncodeItem::uncompressed(msg.clone())),
+        ];
+        let source = tokio_stream::iter(messages);
+
+        let body = EncodeBody::new_server(
+            encoder,
+            source,
+            Some(CompressionEncoding::Gzip),
+            SingleMessageCompressionOverride::default(),
+            None,
+        );
+        let mut buf = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(buf.get_u8(), 1);
+        let len = buf.get_u32() as usize;
+        assert!(len < msg.len());
+        buf.advance(len);
+
+        assert_eq!(buf.get_u8(), 0);
+        assert_eq!(buf.get_u32() as usize, msg.len());
+        assert_eq!(&buf[..], &msg[..]);
+    }
+
+    #[tokio::test]
+    async fn encode_bytes_without_copying() {
+        use super::ProstEncoder;
+        use bytes::Bytes;
+
+        let payload = Bytes::from(vec![1u8; LEN]);
+
+        let messages = std::iter::once(Ok::<_, Status>(payload.clone()));
+        let source = tokio_stream::iter(messages);
+
+        let mut body = pin!(EncodeBody::new_server(
+            ProstEncoder::<Bytes>::default(),
+            source,
+            None,
+            SingleMessageCompressionOverride::default(),
+            None,
+        ));
+
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            if let Ok(data) = frame.unwrap().into_data() {
+                frames.push(data);
+            }
+        }
+
+        // the payload is sent as its own frame after the header and field tag
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].as_ptr(), payload.as_ptr());
+
+        let buf = frames.concat();
+        let mut buf = &buf[..];
+        assert_eq!(buf.get_u8(), 0);
+        assert_eq!(buf.get_u32() as usize, buf.remaining());
+        assert_eq!(<Bytes as prost::Message>::decode(buf).unwrap(), payload);
+    }
+
     #[tokio::test]
     async fn encode_max_message_size_exceeded() {
         let encoder = MockEncoder::default();
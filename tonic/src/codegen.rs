@@ -9,13 +9,21 @@ pub use std::sync::Arc;
 pub use std::task::{Context, Poll};
 pub use tower_service::Service;
 pub type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
-pub use crate::codec::{CompressionEncoding, EnabledCompressionEncodings};
+pub use crate::codec::{CompressionEncoding, CompressionLevel, EnabledCompressionEncodings};
 pub use crate::extensions::GrpcMethod;
-pub use crate::service::interceptor::InterceptedService;
+pub use crate::service::interceptor::{AsyncInterceptedService, InterceptedService};
 pub use bytes::Bytes;
 pub use http;
 pub use http_body::Body;
 
+/// Returns `codec`, pinning its message types where they can't be inferred from the messages.
+pub fn pin_codec<E, D, C>(codec: C) -> C
+where
+    C: crate::codec::Codec<Encode = E, Decode = D>,
+{
+    codec
+}
+
 pub type BoxFuture<T, E> = self::Pin<Box<dyn self::Future<Output = Result<T, E>> + Send + 'static>>;
 pub type BoxStream<T> =
     self::Pin<Box<dyn tokio_stream::Stream<Item = Result<T, crate::Status>> + Send + 'static>>;
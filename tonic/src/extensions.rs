@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 /// A gRPC Method info extension.
 #[derive(Debug, Clone)]
 pub struct GrpcMethod<'a> {
@@ -21,3 +23,59 @@ impl<'a> GrpcMethod<'a> {
         self.method
     }
 }
+
+#[cfg(any(feature = "server", feature = "channel"))]
+tokio::task_local! {
+    static CURRENT_DEADLINE: Option<Deadline>;
+}
+
+/// The absolute deadline of a gRPC call.
+///
+/// The server inserts this extension into requests that carry a `grpc-timeout`
+/// header or are subject to a server side timeout, and also makes it available
+/// through [`Deadline::current`] while the handler runs. Clients created with
+/// [`Grpc`](crate::client::Grpc) pick it up from either place and send the time
+/// that remains as the `grpc-timeout` of outgoing calls, so deadlines carry
+/// over to downstream services.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Create a new `Deadline` extension expiring at `instant`.
+    pub fn new(instant: Instant) -> Self {
+        Self(instant)
+    }
+
+    /// The point in time the call expires at.
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// The time left until the call expires, zero if it already has.
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// The deadline of the call the current task is handling, if any.
+    ///
+    /// This is only set while the future returned by the service is polled, it
+    /// does not carry over to spawned tasks or to response streams.
+    pub fn current() -> Option<Self> {
+        #[cfg(any(feature = "server", feature = "channel"))]
+        return CURRENT_DEADLINE
+            .try_with(|deadline| *deadline)
+            .ok()
+            .flatten();
+
+        #[cfg(not(any(feature = "server", feature = "channel")))]
+        None
+    }
+
+    #[cfg(any(feature = "server", feature = "channel"))]
+    pub(crate) fn scope<F: std::future::Future>(
+        deadline: Option<Self>,
+        future: F,
+    ) -> tokio::task::futures::TaskLocalFuture<Option<Self>, F> {
+        CURRENT_DEADLINE.scope(deadline, future)
+    }
+}
@@ -21,6 +21,8 @@
 //!   and `channel` features. Enabled by default.
 //! - `server`: Enables just the full featured server portion of the `transport` feature.
 //! - `channel`: Enables just the full featured channel portion of the `transport` feature.
+//! - `service-config`: Enables parsing the JSON representation of a gRPC service config for
+//!   the `channel` feature. Depends on [`serde_json`]. Not enabled by default.
 //! - `router`: Enables the [`axum`] based service router. Enabled by default.
 //! - `codegen`: Enables all the required exports and optional dependencies required
 //!   for [`tonic-build`]. Enabled by default.
@@ -35,10 +37,14 @@
 //! - `tls-webpki-roots`: Add the standard trust roots from the [`webpki-roots`] crate to
 //!   `rustls`-based gRPC clients. Not enabled by default.
 //! - `prost`: Enables the [`prost`] based gRPC [`Codec`] implementation. Enabled by default.
+//! - `json`: Enables the [`serde`] based JSON [`Codec`] implementation, `codec::JsonCodec`.
+//!   Not enabled by default.
 //! - `gzip`: Enables compressing requests, responses, and streams. Depends on [`flate2`].
 //!   Not enabled by default.
 //! - `zstd`: Enables compressing requests, responses, and streams. Depends on [`zstd`].
 //!   Not enabled by default.
+//! - `deflate`: Enables compressing requests, responses, and streams with the zlib format.
+//!   Depends on [`flate2`]. Not enabled by default.
 //!
 //! # Structure
 //!
@@ -86,6 +92,8 @@
 //! [`webpki-roots`]: https://docs.rs/webpki-roots
 //! [`flate2`]: https://docs.rs/flate2
 //! [`zstd`]: https://docs.rs/zstd
+//! [`serde`]: https://docs.rs/serde
+//! [`serde_json`]: https://docs.rs/serde_json
 
 #![recursion_limit = "256"]
 #![warn(
@@ -126,7 +134,7 @@ pub use async_trait::async_trait;
 
 #[doc(inline)]
 pub use codec::Streaming;
-pub use extensions::GrpcMethod;
+pub use extensions::{Deadline, GrpcMethod};
 pub use http::Extensions;
 pub use request::{IntoRequest, IntoStreamingRequest, Request};
 pub use response::Response;
@@ -119,8 +119,8 @@ impl<T> Response<T> {
     ///
     /// **Note**: This only has effect on responses to unary requests and responses to client to
     /// server streams. Response streams (server to client stream and bidirectional streams) will
-    /// still be compressed according to the configuration of the server.
-    #[cfg(feature = "gzip")]
+    /// still be compressed according to the configuration of the server, unless their items are
+    /// marked with [`EncodeItem::uncompressed`](crate::codec::EncodeItem::uncompressed).
     pub fn disable_compression(&mut self) {
         self.extensions_mut()
             .insert(crate::codec::compression::SingleMessageCompressionOverride::Disable);
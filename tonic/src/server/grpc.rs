@@ -1,7 +1,8 @@
 use crate::codec::compression::{
-    CompressionEncoding, EnabledCompressionEncodings, SingleMessageCompressionOverride,
+    CompressionEncoding, CompressionLevel, EnabledCompressionEncodings,
+    SingleMessageCompressionOverride,
 };
-use crate::codec::EncodeBody;
+use crate::codec::{content_subtype, content_type, EncodeBody, EncodeItem};
 use crate::metadata::GRPC_CONTENT_TYPE;
 use crate::{
     body::BoxBody,
@@ -9,6 +10,7 @@ use crate::{
     server::{ClientStreamingService, ServerStreamingService, StreamingService, UnaryService},
     Request, Status,
 };
+use http::HeaderValue;
 use http_body::Body;
 use std::{fmt, pin::pin};
 use tokio_stream::{Stream, StreamExt};
@@ -121,6 +123,79 @@ where
         self
     }
 
+    /// Enable sending compressed responses, compressing with the given [`CompressionLevel`].
+    ///
+    /// Requires the client to also support receiving compressed responses.
+    ///
+    /// # Example
+    ///
+    /// The most common way of using this is through a server generated by tonic-build:
+    ///
+    /// ```rust
+    /// # enum CompressionEncoding { Gzip }
+    /// # enum CompressionLevel { Fastest }
+    /// # struct Svc;
+    /// # struct ExampleServer<T>(T);
+    /// # impl<T> ExampleServer<T> {
+    /// #     fn new(svc: T) -> Self { Self(svc) }
+    /// #     fn send_compressed_with_level(self, _: CompressionEncoding, _: CompressionLevel) -> Self { self }
+    /// # }
+    /// # #[tonic::async_trait]
+    /// # trait Example {}
+    ///
+    /// #[tonic::async_trait]
+    /// impl Example for Svc {
+    ///     // ...
+    /// }
+    ///
+    /// let service = ExampleServer::new(Svc)
+    ///     .send_compressed_with_level(CompressionEncoding::Gzip, CompressionLevel::Fastest);
+    /// ```
+    pub fn send_compressed_with_level(
+        mut self,
+        encoding: CompressionEncoding,
+        level: CompressionLevel,
+    ) -> Self {
+        self.send_compression_encodings
+            .enable_with_level(encoding, level);
+        self
+    }
+
+    /// Send response messages smaller than `min_size` bytes uncompressed, even if compression is
+    /// enabled.
+    ///
+    /// Default is 0, compressing every message.
+    ///
+    /// # Example
+    ///
+    /// The most common way of using this is through a server generated by tonic-build:
+    ///
+    /// ```rust
+    /// # enum CompressionEncoding { Gzip }
+    /// # struct Svc;
+    /// # struct ExampleServer<T>(T);
+    /// # impl<T> ExampleServer<T> {
+    /// #     fn new(svc: T) -> Self { Self(svc) }
+    /// #     fn send_compressed(self, _: CompressionEncoding) -> Self { self }
+    /// #     fn send_compressed_min_size(self, _: usize) -> Self { self }
+    /// # }
+    /// # #[tonic::async_trait]
+    /// # trait Example {}
+    ///
+    /// #[tonic::async_trait]
+    /// impl Example for Svc {
+    ///     // ...
+    /// }
+    ///
+    /// let service = ExampleServer::new(Svc)
+    ///     .send_compressed(CompressionEncoding::Gzip)
+    ///     .send_compressed_min_size(1024);
+    /// ```
+    pub fn send_compressed_min_size(mut self, min_size: usize) -> Self {
+        self.send_compression_encodings.set_min_size(min_size);
+        self
+    }
+
     /// Limits the maximum size of a decoded message.
     ///
     /// # Example
@@ -189,14 +264,8 @@ where
     ) -> Self {
         let mut this = self;
 
-        for &encoding in CompressionEncoding::ENCODINGS {
-            if accept_encodings.is_enabled(encoding) {
-                this = this.accept_compressed(encoding);
-            }
-            if send_encodings.is_enabled(encoding) {
-                this = this.send_compressed(encoding);
-            }
-        }
+        this.accept_compression_encodings.extend(accept_encodings);
+        this.send_compression_encodings.extend(send_encodings);
 
         this
     }
@@ -226,20 +295,23 @@ where
         req: http::Request<B>,
     ) -> http::Response<BoxBody>
     where
-        S: UnaryService<T::Decode, Response = T::Encode>,
+        S: UnaryService<T::Decode>,
+        S::Response: Into<EncodeItem<T::Encode>> + Send + 'static,
         B: Body + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
         let accept_encoding = CompressionEncoding::from_accept_encoding_header(
             req.headers(),
-            self.send_compression_encodings,
+            &self.send_compression_encodings,
         );
+        let content_type = t!(self.negotiate_content_type(&req));
 
         let request = match self.map_request_unary(req).await {
             Ok(r) => r,
             Err(status) => {
-                return self.map_response::<tokio_stream::Once<Result<T::Encode, Status>>>(
+                return self.map_response::<tokio_stream::Once<Result<T::Encode, Status>>, _>(
                     Err(status),
+                    content_type,
                     accept_encoding,
                     SingleMessageCompressionOverride::default(),
                     self.max_encoding_message_size,
@@ -256,6 +328,7 @@ where
 
         self.map_response(
             response,
+            content_type,
             accept_encoding,
             compression_override,
             self.max_encoding_message_size,
@@ -269,21 +342,24 @@ where
         req: http::Request<B>,
     ) -> http::Response<BoxBody>
     where
-        S: ServerStreamingService<T::Decode, Response = T::Encode>,
+        S: ServerStreamingService<T::Decode>,
+        S::Response: Into<EncodeItem<T::Encode>>,
         S::ResponseStream: Send + 'static,
         B: Body + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
         let accept_encoding = CompressionEncoding::from_accept_encoding_header(
             req.headers(),
-            self.send_compression_encodings,
+            &self.send_compression_encodings,
         );
+        let content_type = t!(self.negotiate_content_type(&req));
 
         let request = match self.map_request_unary(req).await {
             Ok(r) => r,
             Err(status) => {
-                return self.map_response::<S::ResponseStream>(
+                return self.map_response::<S::ResponseStream, _>(
                     Err(status),
+                    content_type,
                     accept_encoding,
                     SingleMessageCompressionOverride::default(),
                     self.max_encoding_message_size,
@@ -295,6 +371,7 @@ where
 
         self.map_response(
             response,
+            content_type,
             accept_encoding,
             // disabling compression of individual stream items must be done on
             // the items themselves
@@ -310,14 +387,16 @@ where
         req: http::Request<B>,
     ) -> http::Response<BoxBody>
     where
-        S: ClientStreamingService<T::Decode, Response = T::Encode>,
+        S: ClientStreamingService<T::Decode>,
+        S::Response: Into<EncodeItem<T::Encode>> + Send + 'static,
         B: Body + Send + 'static,
         B::Error: Into<crate::BoxError> + Send + 'static,
     {
         let accept_encoding = CompressionEncoding::from_accept_encoding_header(
             req.headers(),
-            self.send_compression_encodings,
+            &self.send_compression_encodings,
         );
+        let content_type = t!(self.negotiate_content_type(&req));
 
         let request = t!(self.map_request_streaming(req));
 
@@ -330,6 +409,7 @@ where
 
         self.map_response(
             response,
+            content_type,
             accept_encoding,
             compression_override,
             self.max_encoding_message_size,
@@ -343,15 +423,17 @@ where
         req: http::Request<B>,
     ) -> http::Response<BoxBody>
     where
-        S: StreamingService<T::Decode, Response = T::Encode> + Send,
+        S: StreamingService<T::Decode> + Send,
+        S::Response: Into<EncodeItem<T::Encode>>,
         S::ResponseStream: Send + 'static,
         B: Body + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
         let accept_encoding = CompressionEncoding::from_accept_encoding_header(
             req.headers(),
-            self.send_compression_encodings,
+            &self.send_compression_encodings,
         );
+        let content_type = t!(self.negotiate_content_type(&req));
 
         let request = t!(self.map_request_streaming(req));
 
@@ -359,6 +441,7 @@ where
 
         self.map_response(
             response,
+            content_type,
             accept_encoding,
             SingleMessageCompressionOverride::default(),
             self.max_encoding_message_size,
@@ -420,26 +503,36 @@ where
         Ok(Request::from_http(request))
     }
 
-    fn map_response<B>(
+    fn map_response<B, I>(
         &mut self,
         response: Result<crate::Response<B>, Status>,
+        content_type: HeaderValue,
         accept_encoding: Option<CompressionEncoding>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
     ) -> http::Response<BoxBody>
     where
-        B: Stream<Item = Result<T::Encode, Status>> + Send + 'static,
+        B: Stream<Item = Result<I, Status>> + Send + 'static,
+        I: Into<EncodeItem<T::Encode>>,
     {
-        let response = t!(response);
+        let response = match response {
+            Ok(response) => response,
+            Err(status) => {
+                let mut response = status.into_http();
+                response
+                    .headers_mut()
+                    .insert(http::header::CONTENT_TYPE, content_type);
+                return response;
+            }
+        };
 
         let (mut parts, body) = response.into_http().into_parts();
 
         // Set the content type
         parts
             .headers
-            .insert(http::header::CONTENT_TYPE, GRPC_CONTENT_TYPE);
+            .insert(http::header::CONTENT_TYPE, content_type);
 
-        #[cfg(any(feature = "gzip", feature = "zstd"))]
         if let Some(encoding) = accept_encoding {
             // Set the content encoding
             parts.headers.insert(
@@ -448,10 +541,11 @@ where
             );
         }
 
-        let body = EncodeBody::new_server(
+        let body = EncodeBody::server(
             self.codec.encoder(),
             body,
-            accept_encoding,
+            accept_encoding
+                .map(|encoding| self.send_compression_encodings.send_compression(encoding)),
             compression_override,
             max_message_size,
         );
@@ -459,17 +553,59 @@ where
         http::Response::from_parts(parts, BoxBody::new(body))
     }
 
+    /// Select the codec for a request, returning the `content-type` of the response, which echoes
+    /// the subtype of the request.
+    fn negotiate_content_type<B>(
+        &mut self,
+        request: &http::Request<B>,
+    ) -> Result<HeaderValue, UnsupportedMediaType> {
+        match content_subtype(request.headers()) {
+            Some(subtype)
+                if self
+                    .codec
+                    .accept_content_subtype(subtype.unwrap_or("proto")) =>
+            {
+                Ok(content_type(subtype))
+            }
+            Some(subtype) => Err(UnsupportedMediaType(Status::unimplemented(format!(
+                "Content subtype `{}` isn't supported",
+                subtype.unwrap_or("proto")
+            )))),
+            None => match self.codec.content_subtype() {
+                Some(expected) => Err(UnsupportedMediaType(Status::unimplemented(format!(
+                    "Content type isn't gRPC, expected `{}`",
+                    content_type(Some(expected)).to_str().unwrap()
+                )))),
+                None => Ok(GRPC_CONTENT_TYPE),
+            },
+        }
+    }
+
     fn request_encoding_if_supported<B>(
         &self,
         request: &http::Request<B>,
     ) -> Result<Option<CompressionEncoding>, Status> {
         CompressionEncoding::from_encoding_header(
             request.headers(),
-            self.accept_compression_encodings,
+            &self.accept_compression_encodings,
         )
     }
 }
 
+/// A request with a content type the codec doesn't accept.
+///
+/// It is answered with HTTP status `415 Unsupported Media Type`, so that HTTP clients which don't
+/// look at the `grpc-status` don't mistake the error for a success.
+struct UnsupportedMediaType(Status);
+
+impl UnsupportedMediaType {
+    fn into_http<B: Default>(self) -> http::Response<B> {
+        let mut response = self.0.into_http();
+        *response.status_mut() = http::StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        response
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for Grpc<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut f = f.debug_struct("Grpc");
@@ -1,14 +1,16 @@
 //! gRPC interceptors which are a kind of middleware.
 //!
-//! See [`Interceptor`] for more details.
+//! See [`Interceptor`] and [`AsyncInterceptor`] for more details.
 
-use crate::{request::SanitizeHeaders, Status};
+use crate::{body::BoxBody, metadata::MetadataMap, request::SanitizeHeaders, Status};
+use bytes::Bytes;
+use http_body::{Body, Frame};
 use pin_project::pin_project;
 use std::{
     fmt,
     future::Future,
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll},
 };
 use tower_layer::Layer;
 use tower_service::Service;
@@ -25,7 +27,8 @@ use tower_service::Service;
 /// An interceptor can be used on both the server and client side through the `tonic-build` crate's
 /// generated structs.
 ///
-/// See the [interceptor example][example] for more details.
+/// See the [interceptor example][example] for more details. Interceptors that need to await or to
+/// act on responses can implement [`AsyncInterceptor`] instead.
 ///
 /// If you need more powerful middleware, [tower] is the recommended approach. You can find
 /// examples of how to use tower with tonic [here][tower-example].
@@ -221,9 +224,260 @@ where
     }
 }
 
+/// An asynchronous gRPC interceptor, which can also act on the response.
+///
+/// Like an [`Interceptor`], an `AsyncInterceptor` can add/remove/check items in the `MetadataMap`
+/// of each request and cancel a request with a `Status`, but it does so in a future, for example
+/// to validate a token against an async key store. It can also read and modify the metadata and
+/// trailers of each response.
+///
+/// Any function that satisfies the bound `FnMut(Request<()>) -> Fut`, where `Fut` resolves to a
+/// `Result<Request<()>, Status>`, can be used as an `AsyncInterceptor` that doesn't act on
+/// responses.
+///
+/// An async interceptor can be used on both the server and client side through the
+/// `with_async_interceptor` constructors of the `tonic-build` crate's generated structs. The
+/// interceptor is cloned for each request, so state shared by requests should be kept behind an
+/// `Arc`.
+///
+/// ```
+/// use tonic::{metadata::MetadataMap, service::AsyncInterceptor, Request, Status};
+/// use std::future::Future;
+/// use std::pin::Pin;
+///
+/// #[derive(Clone)]
+/// struct Auth;
+///
+/// impl AsyncInterceptor for Auth {
+///     type Future = Pin<Box<dyn Future<Output = Result<Request<()>, Status>> + Send>>;
+///
+///     fn call(&mut self, request: Request<()>) -> Self::Future {
+///         Box::pin(async move {
+///             match request.metadata().get("authorization") {
+///                 Some(token) if token == "Bearer secret" => Ok(request),
+///                 _ => Err(Status::unauthenticated("invalid token")),
+///             }
+///         })
+///     }
+///
+///     fn on_response(&mut self, metadata: &mut MetadataMap) {
+///         metadata.insert("x-authenticated", "true".parse().unwrap());
+///     }
+/// }
+/// ```
+pub trait AsyncInterceptor {
+    /// The future returned by [`call`](Self::call).
+    type Future: Future<Output = Result<crate::Request<()>, Status>>;
+
+    /// Intercept a request before it is sent, optionally cancelling it.
+    fn call(&mut self, request: crate::Request<()>) -> Self::Future;
+
+    /// Intercept the metadata of a response before it is returned.
+    ///
+    /// This isn't called for requests cancelled by [`call`](Self::call).
+    fn on_response(&mut self, metadata: &mut MetadataMap) {
+        let _ = metadata;
+    }
+
+    /// Intercept the trailers of a response before they are returned.
+    ///
+    /// Responses that only carry a status in their headers don't have trailers.
+    fn on_trailers(&mut self, trailers: &mut MetadataMap) {
+        let _ = trailers;
+    }
+}
+
+impl<F, Fut> AsyncInterceptor for F
+where
+    F: FnMut(crate::Request<()>) -> Fut,
+    Fut: Future<Output = Result<crate::Request<()>, Status>>,
+{
+    type Future = Fut;
+
+    fn call(&mut self, request: crate::Request<()>) -> Self::Future {
+        self(request)
+    }
+}
+
+/// A gRPC async interceptor that can be used as a [`Layer`].
+///
+/// See [`AsyncInterceptor`] for more details.
+#[derive(Debug, Clone, Copy)]
+pub struct AsyncInterceptorLayer<I> {
+    interceptor: I,
+}
+
+impl<I> AsyncInterceptorLayer<I> {
+    /// Create a new async interceptor layer.
+    ///
+    /// See [`AsyncInterceptor`] for more details.
+    pub fn new(interceptor: I) -> Self {
+        Self { interceptor }
+    }
+}
+
+impl<S, I> Layer<S> for AsyncInterceptorLayer<I>
+where
+    I: Clone,
+{
+    type Service = AsyncInterceptedService<S, I>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        AsyncInterceptedService::new(service, self.interceptor.clone())
+    }
+}
+
+/// A service wrapped in an async interceptor middleware.
+///
+/// See [`AsyncInterceptor`] for more details.
+#[derive(Clone, Copy)]
+pub struct AsyncInterceptedService<S, I> {
+    inner: S,
+    interceptor: I,
+}
+
+impl<S, I> AsyncInterceptedService<S, I> {
+    /// Create a new `AsyncInterceptedService` that wraps `S` and intercepts each request and
+    /// response with `I`.
+    pub fn new(service: S, interceptor: I) -> Self {
+        Self {
+            inner: service,
+            interceptor,
+        }
+    }
+}
+
+impl<S, I> fmt::Debug for AsyncInterceptedService<S, I>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncInterceptedService")
+            .field("inner", &self.inner)
+            .field("f", &format_args!("{}", std::any::type_name::<I>()))
+            .finish()
+    }
+}
+
+impl<S, I, ReqBody, ResBody> Service<http::Request<ReqBody>> for AsyncInterceptedService<S, I>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Send,
+    I: AsyncInterceptor + Clone + Send + 'static,
+    I::Future: Send,
+    ReqBody: Send + 'static,
+    ResBody: Body<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<crate::BoxError>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        // As in `InterceptedService`, the interceptor only sees the metadata and extensions of
+        // the request, which is rebuilt with its body once the interceptor resolves.
+        let uri = req.uri().clone();
+        let method = req.method().clone();
+        let version = req.version();
+        let req = crate::Request::from_http(req);
+        let (metadata, extensions, msg) = req.into_parts();
+
+        let mut interceptor = self.interceptor.clone();
+        let intercept = self
+            .interceptor
+            .call(crate::Request::from_parts(metadata, extensions, ()));
+
+        // The service is ready, keep it for this request and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let req = match intercept.await {
+                Ok(req) => req,
+                Err(status) => return Ok(status.into_http()),
+            };
+            let (metadata, extensions, _) = req.into_parts();
+            let req = crate::Request::from_parts(metadata, extensions, msg);
+            let req = req.into_http(uri, method, version, SanitizeHeaders::No);
+
+            let (mut parts, body) = inner.call(req).await?.into_parts();
+
+            let mut metadata = MetadataMap::from_headers(std::mem::take(&mut parts.headers));
+            interceptor.on_response(&mut metadata);
+            parts.headers = metadata.into_headers();
+
+            let body = crate::body::boxed(InterceptedBody {
+                inner: body,
+                interceptor,
+            });
+            Ok(http::Response::from_parts(parts, body))
+        })
+    }
+}
+
+// required to use `AsyncInterceptedService` with `Router`
+impl<S, I> crate::server::NamedService for AsyncInterceptedService<S, I>
+where
+    S: crate::server::NamedService,
+{
+    const NAME: &'static str = S::NAME;
+}
+
+/// A response body passing its trailers to an [`AsyncInterceptor`].
+#[pin_project]
+struct InterceptedBody<B, I> {
+    #[pin]
+    inner: B,
+    interceptor: I,
+}
+
+impl<B, I> Body for InterceptedBody<B, I>
+where
+    B: Body,
+    I: AsyncInterceptor,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = match ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            other => return Poll::Ready(other),
+        };
+
+        match frame.into_trailers() {
+            Ok(trailers) => {
+                let mut trailers = MetadataMap::from_headers(trailers);
+                this.interceptor.on_trailers(&mut trailers);
+                Poll::Ready(Some(Ok(Frame::trailers(trailers.into_headers()))))
+            }
+            Err(frame) => Poll::Ready(Some(Ok(frame))),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use http_body_util::BodyExt;
     use tower::ServiceExt;
 
     #[tokio::test]
@@ -298,4 +552,70 @@ mod tests {
 
         svc.oneshot(request).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn handles_async_intercepted_status_as_response() {
+        let svc = tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, Status>(http::Response::new(BoxBody::default()))
+        });
+
+        let svc = AsyncInterceptedService::new(svc, |_: crate::Request<()>| async {
+            Err(Status::unauthenticated("Blocked by the interceptor"))
+        });
+
+        let request = http::Request::builder().body(()).unwrap();
+        let response = svc.oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "16");
+    }
+
+    #[tokio::test]
+    async fn intercepts_response_metadata_and_trailers() {
+        #[derive(Clone)]
+        struct Tag;
+
+        impl AsyncInterceptor for Tag {
+            type Future = std::future::Ready<Result<crate::Request<()>, Status>>;
+
+            fn call(&mut self, mut request: crate::Request<()>) -> Self::Future {
+                request
+                    .metadata_mut()
+                    .insert("x-request", "tagged".parse().unwrap());
+                std::future::ready(Ok(request))
+            }
+
+            fn on_response(&mut self, metadata: &mut MetadataMap) {
+                metadata.insert("x-response", "tagged".parse().unwrap());
+            }
+
+            fn on_trailers(&mut self, trailers: &mut MetadataMap) {
+                trailers.insert("x-trailer", "tagged".parse().unwrap());
+            }
+        }
+
+        let svc = tower::service_fn(|request: http::Request<()>| async move {
+            assert_eq!(request.headers().get("x-request").unwrap(), "tagged");
+
+            let body = http_body_util::StreamBody::new(tokio_stream::once(Ok::<_, Status>(
+                Frame::trailers(http::HeaderMap::new()),
+            )));
+            Ok::<_, Status>(http::Response::new(body))
+        });
+
+        let svc = AsyncInterceptedService::new(svc, Tag);
+
+        let request = http::Request::builder().body(()).unwrap();
+        let response = svc.oneshot(request).await.unwrap();
+        assert_eq!(response.headers().get("x-response").unwrap(), "tagged");
+
+        let trailers = response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .trailers()
+            .cloned()
+            .unwrap();
+        assert_eq!(trailers.get("x-trailer").unwrap(), "tagged");
+    }
 }
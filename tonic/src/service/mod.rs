@@ -9,7 +9,9 @@ pub(crate) mod router;
 #[allow(deprecated)]
 pub use self::interceptor::interceptor;
 #[doc(inline)]
-pub use self::interceptor::{Interceptor, InterceptorLayer};
+pub use self::interceptor::{
+    AsyncInterceptor, AsyncInterceptorLayer, Interceptor, InterceptorLayer,
+};
 pub use self::layered::{LayerExt, Layered};
 #[doc(inline)]
 #[cfg(feature = "router")]
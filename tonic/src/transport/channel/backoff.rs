@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+const DEFAULT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MULTIPLIER: f64 = 1.6;
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(120);
+const DEFAULT_JITTER: f64 = 0.2;
+const DEFAULT_MIN_CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How a [`Channel`] backs off between attempts to connect to an endpoint.
+///
+/// After a failed attempt the channel waits before connecting again, starting with the initial
+/// delay and multiplying it after every further failure, up to the maximum delay. Every delay is
+/// randomly spread by the jitter so that clients do not reconnect in lockstep. Calls made while
+/// the channel backs off fail right away, unless they
+/// [wait for the channel to be ready](super::Endpoint::wait_for_ready). The delay starts over
+/// once a connection is established.
+///
+/// The defaults follow the [gRPC connection backoff protocol].
+///
+/// ```
+/// # use tonic::transport::{Endpoint, channel::ReconnectBackoff};
+/// # use std::time::Duration;
+/// let backoff = ReconnectBackoff::new()
+///     .initial_delay(Duration::from_millis(100))
+///     .max_delay(Duration::from_secs(10));
+///
+/// let endpoint = Endpoint::from_static("http://[::1]:50051").reconnect_backoff(backoff);
+/// ```
+///
+/// [`Channel`]: super::Channel
+/// [gRPC connection backoff protocol]: https://github.com/grpc/grpc/blob/master/doc/connection-backoff.md
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectBackoff {
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter: f64,
+    min_connect_timeout: Duration,
+}
+
+impl ReconnectBackoff {
+    /// Create a backoff policy with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delay after the first failed attempt.
+    ///
+    /// Default is 1 second.
+    pub fn initial_delay(self, initial_delay: Duration) -> Self {
+        ReconnectBackoff {
+            initial_delay,
+            ..self
+        }
+    }
+
+    /// Set the factor the delay grows by after every failed attempt. Values below 1 are treated
+    /// as 1.
+    ///
+    /// Default is 1.6.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        ReconnectBackoff {
+            multiplier: multiplier.max(1.0),
+            ..self
+        }
+    }
+
+    /// Set the upper bound of the delay, before jitter is applied.
+    ///
+    /// Default is 120 seconds.
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        ReconnectBackoff { max_delay, ..self }
+    }
+
+    /// Set by which fraction every delay is randomly lengthened or shortened, between 0 and 1.
+    ///
+    /// Default is 0.2.
+    pub fn jitter(self, jitter: f64) -> Self {
+        ReconnectBackoff {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Set the least time an attempt to connect is given before it is abandoned.
+    ///
+    /// An attempt may run for as long as the current delay if that is longer.
+    ///
+    /// Default is 20 seconds.
+    pub fn min_connect_timeout(self, min_connect_timeout: Duration) -> Self {
+        ReconnectBackoff {
+            min_connect_timeout,
+            ..self
+        }
+    }
+
+    /// Get the delay after the first failed attempt.
+    pub fn get_initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Get the factor the delay grows by after every failed attempt.
+    pub fn get_multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Get the upper bound of the delay.
+    pub fn get_max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Get the fraction by which delays are randomly spread.
+    pub fn get_jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    /// Get the least time an attempt to connect is given.
+    pub fn get_min_connect_timeout(&self) -> Duration {
+        self.min_connect_timeout
+    }
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: DEFAULT_INITIAL_DELAY,
+            multiplier: DEFAULT_MULTIPLIER,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: DEFAULT_JITTER,
+            min_connect_timeout: DEFAULT_MIN_CONNECT_TIMEOUT,
+        }
+    }
+}
+
+/// The backoff state of a single connection.
+#[derive(Debug, Clone)]
+pub(crate) struct Backoff {
+    policy: ReconnectBackoff,
+    current: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new(policy: ReconnectBackoff) -> Self {
+        let current = policy.initial_delay.min(policy.max_delay);
+        Self { policy, current }
+    }
+
+    /// How long the next attempt to connect is given.
+    pub(crate) fn connect_timeout(&self) -> Duration {
+        self.current.max(self.policy.min_connect_timeout)
+    }
+
+    /// Returns the jittered delay before the next attempt, and grows the delay for the one after.
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let spread = self.policy.jitter * (2.0 * crate::util::fast_random() - 1.0);
+        let delay = self.current.mul_f64(1.0 + spread);
+
+        let max = self.policy.max_delay.as_secs_f64();
+        let next = (self.current.as_secs_f64() * self.policy.multiplier).min(max);
+        self.current = Duration::from_secs_f64(next);
+
+        delay
+    }
+
+    /// Starts over with the initial delay, after a connection was established.
+    pub(crate) fn reset(&mut self) {
+        self.current = self.policy.initial_delay.min(self.policy.max_delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_grow_up_to_max_delay() {
+        let mut backoff = Backoff::new(
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(1))
+                .multiplier(2.0)
+                .max_delay(Duration::from_secs(5))
+                .jitter(0.0),
+        );
+
+        let delays: Vec<_> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn jitter_spreads_delays() {
+        let mut backoff = Backoff::new(
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(10))
+                .multiplier(1.0)
+                .jitter(0.2),
+        );
+
+        for _ in 0..100 {
+            let delay = backoff.next_delay();
+            assert!(delay >= Duration::from_secs(8), "{delay:?}");
+            assert!(delay <= Duration::from_secs(12), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn connect_timeout_is_at_least_min_connect_timeout() {
+        let mut backoff = Backoff::new(
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(10))
+                .multiplier(3.0)
+                .min_connect_timeout(Duration::from_secs(20)),
+        );
+        assert_eq!(backoff.connect_timeout(), Duration::from_secs(20));
+
+        backoff.next_delay();
+        assert_eq!(backoff.connect_timeout(), Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::watch;
+use tokio_stream::{wrappers::WatchStream, Stream};
+
+/// The connectivity state of a [`Channel`].
+///
+/// The states follow the [gRPC connectivity semantics]. A balanced channel is `Ready` as long as
+/// one of its endpoints is, otherwise it reports the most hopeful state of its endpoints, in the
+/// order `Connecting`, `Idle` and `TransientFailure`.
+///
+/// [`Channel`]: super::Channel
+/// [gRPC connectivity semantics]: https://github.com/grpc/grpc/blob/master/doc/connectivity-semantics-and-api.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectivityState {
+    /// There is no connection and none is being established, the channel connects on the next
+    /// call.
+    Idle,
+    /// A connection is being established.
+    Connecting,
+    /// A connection is established and calls are sent right away.
+    Ready,
+    /// The last attempt to connect failed, calls fail unless they wait for the channel to be
+    /// ready.
+    TransientFailure,
+    /// All handles to the channel have been dropped, it won't connect again.
+    Shutdown,
+}
+
+impl ConnectivityState {
+    /// Returns the state of a channel balancing over endpoints in the given states.
+    fn aggregate(states: impl Iterator<Item = ConnectivityState>) -> ConnectivityState {
+        states
+            .max_by_key(|state| match state {
+                ConnectivityState::Ready => 4,
+                ConnectivityState::Connecting => 3,
+                ConnectivityState::Idle => 2,
+                ConnectivityState::TransientFailure => 1,
+                ConnectivityState::Shutdown => 0,
+            })
+            .unwrap_or(ConnectivityState::Idle)
+    }
+}
+
+/// A stream of the [`ConnectivityState`] of a [`Channel`].
+///
+/// The stream yields the current state first and then every change, intermediate states may be
+/// skipped when the stream is not polled in time. It ends once the channel has shut down.
+///
+/// [`Channel`]: super::Channel
+pub struct ConnectivityStateStream {
+    inner: WatchStream<ConnectivityState>,
+}
+
+impl Stream for ConnectivityStateStream {
+    type Item = ConnectivityState;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+impl fmt::Debug for ConnectivityStateStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectivityStateStream").finish()
+    }
+}
+
+/// Tracks the connectivity state of a channel from the states of its subchannels, the
+/// connections to its endpoints.
+#[derive(Clone)]
+pub(crate) struct Connectivity {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    subchannels: Mutex<HashMap<usize, ConnectivityState>>,
+    next_id: AtomicUsize,
+    state: watch::Sender<ConnectivityState>,
+}
+
+impl Connectivity {
+    pub(crate) fn new() -> Self {
+        let (state, _) = watch::channel(ConnectivityState::Idle);
+
+        Self {
+            shared: Arc::new(Shared {
+                subchannels: Mutex::new(HashMap::new()),
+                next_id: AtomicUsize::new(0),
+                state,
+            }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectivityState {
+        *self.shared.state.borrow()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ConnectivityState> {
+        self.shared.state.subscribe()
+    }
+
+    pub(crate) fn stream(&self) -> ConnectivityStateStream {
+        ConnectivityStateStream {
+            inner: WatchStream::new(self.subscribe()),
+        }
+    }
+
+    /// Registers a new subchannel, starting out `Idle`.
+    pub(crate) fn subchannel(&self) -> Subchannel {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let subchannel = Subchannel {
+            entry: Arc::new(Entry {
+                connectivity: self.clone(),
+                id,
+                failed: AtomicBool::new(false),
+            }),
+        };
+        subchannel.set(ConnectivityState::Idle);
+        subchannel
+    }
+
+    /// Marks the channel as shut down, which is final.
+    pub(crate) fn shutdown(&self) {
+        self.shared.state.send_replace(ConnectivityState::Shutdown);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut HashMap<usize, ConnectivityState>)) {
+        let mut subchannels = self.shared.subchannels.lock().unwrap();
+        f(&mut subchannels);
+        let aggregate = ConnectivityState::aggregate(subchannels.values().copied());
+
+        self.shared.state.send_if_modified(|state| {
+            let changed = *state != aggregate && *state != ConnectivityState::Shutdown;
+            if changed {
+                *state = aggregate;
+            }
+            changed
+        });
+    }
+}
+
+impl fmt::Debug for Connectivity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connectivity")
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+/// Reports the state of a single connection to its channel, until all clones are dropped.
+#[derive(Clone)]
+pub(crate) struct Subchannel {
+    entry: Arc<Entry>,
+}
+
+struct Entry {
+    connectivity: Connectivity,
+    id: usize,
+    /// Whether the last attempt to connect failed, kept while connecting again.
+    failed: AtomicBool,
+}
+
+impl Subchannel {
+    pub(crate) fn set(&self, state: ConnectivityState) {
+        match state {
+            ConnectivityState::TransientFailure => self.entry.failed.store(true, Ordering::Relaxed),
+            ConnectivityState::Ready => self.entry.failed.store(false, Ordering::Relaxed),
+            _ => {}
+        }
+
+        let id = self.entry.id;
+        self.entry.connectivity.update(|subchannels| {
+            subchannels.insert(id, state);
+        });
+    }
+
+    /// Returns whether the connection failed and has not been established again since.
+    pub(crate) fn has_failed(&self) -> bool {
+        self.entry.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        self.connectivity.update(|subchannels| {
+            subchannels.remove(&self.id);
+        });
+    }
+}
+
+impl fmt::Debug for Subchannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subchannel")
+            .field("id", &self.entry.id)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_subchannel_states() {
+        let connectivity = Connectivity::new();
+        assert_eq!(connectivity.state(), ConnectivityState::Idle);
+
+        let a = connectivity.subchannel();
+        let b = connectivity.subchannel();
+        a.set(ConnectivityState::TransientFailure);
+        assert_eq!(connectivity.state(), ConnectivityState::Idle);
+
+        b.set(ConnectivityState::Connecting);
+        assert_eq!(connectivity.state(), ConnectivityState::Connecting);
+
+        a.set(ConnectivityState::Ready);
+        assert_eq!(connectivity.state(), ConnectivityState::Ready);
+
+        drop(a);
+        b.set(ConnectivityState::TransientFailure);
+        assert_eq!(connectivity.state(), ConnectivityState::TransientFailure);
+
+        connectivity.shutdown();
+        b.set(ConnectivityState::Ready);
+        assert_eq!(connectivity.state(), ConnectivityState::Shutdown);
+    }
+}
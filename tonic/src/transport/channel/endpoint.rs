@@ -1,14 +1,19 @@
+use super::resolver::{Address, ResolveNow};
 #[cfg(feature = "_tls-any")]
 use super::service::TlsConnector;
 use super::service::{self, Executor, SharedExec};
-use super::Channel;
 #[cfg(feature = "_tls-any")]
 use super::ClientTlsConfig;
+use super::{
+    Channel, HedgingPolicies, LoadBalancingPolicy, ReconnectBackoff, RetryPolicies, ServiceConfig,
+};
 use crate::transport::Error;
 use bytes::Bytes;
 use http::{uri::Uri, HeaderValue};
 use hyper::rt;
 use hyper_util::client::legacy::connect::HttpConnector;
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::{fmt, future::Future, pin::Pin, str::FromStr, time::Duration};
 use tower_service::Service;
 
@@ -35,7 +40,18 @@ pub struct Endpoint {
     pub(crate) http2_keep_alive_while_idle: Option<bool>,
     pub(crate) http2_max_header_list_size: Option<u32>,
     pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
     pub(crate) http2_adaptive_window: Option<bool>,
+    pub(crate) retry_policies: RetryPolicies,
+    pub(crate) hedging_policies: HedgingPolicies,
+    pub(crate) service_config: ServiceConfig,
+    pub(crate) load_balancing_policy: LoadBalancingPolicy,
+    pub(crate) weight: u32,
+    pub(crate) wait_for_ready: bool,
+    pub(crate) reconnect_backoff: Option<ReconnectBackoff>,
+    pub(crate) resolve_now: Option<ResolveNow>,
+    #[cfg(unix)]
+    pub(crate) unix_path: Option<PathBuf>,
     pub(crate) executor: SharedExec,
 }
 
@@ -128,6 +144,10 @@ impl Endpoint {
 
     /// Apply a timeout to each request.
     ///
+    /// The timeout covers the whole call, streamed response messages included. A
+    /// response stream still running when it expires ends with a `DeadlineExceeded`
+    /// status.
+    ///
     /// ```
     /// # use tonic::transport::Endpoint;
     /// # use std::time::Duration;
@@ -166,6 +186,30 @@ impl Endpoint {
         }
     }
 
+    /// Close the connection after it has had no calls in flight for the given duration.
+    ///
+    /// The channel goes back to [`ConnectivityState::Idle`] and connects again on the next call.
+    /// A call is in flight until its response has been read to the end or dropped, so streaming
+    /// calls keep the connection open. This saves the connection and its keepalive pings for channels that
+    /// are rarely used.
+    ///
+    /// Defaults to no timeout, the connection stays open.
+    ///
+    /// ```
+    /// # use tonic::transport::Endpoint;
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.idle_timeout(Duration::from_secs(300));
+    /// ```
+    ///
+    /// [`ConnectivityState::Idle`]: super::ConnectivityState::Idle
+    pub fn idle_timeout(self, dur: Duration) -> Self {
+        Endpoint {
+            idle_timeout: Some(dur),
+            ..self
+        }
+    }
+
     /// Set whether TCP keepalive messages are enabled on accepted connections.
     ///
     /// If `None` is specified, keepalive is disabled, otherwise the duration
@@ -306,6 +350,153 @@ impl Endpoint {
         }
     }
 
+    /// Retry failed requests according to the given [`RetryPolicy`] or [`RetryPolicies`].
+    ///
+    /// A single [`RetryPolicy`] applies to every method, use [`RetryPolicies`] to configure
+    /// policies per service or method.
+    ///
+    /// Default is to never retry.
+    ///
+    /// The policies apply to channels connected to this endpoint, balancing over the addresses
+    /// it resolves to, created by [`Channel::balance_list`] with this as the first endpoint or by
+    /// [`Channel::balance_channel_with_config`] with this as the config.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::{RetryPolicies, RetryPolicy}};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.retry_policy(
+    ///     RetryPolicies::new().method("helloworld.Greeter", "SayHello", RetryPolicy::new(3)),
+    /// );
+    /// ```
+    ///
+    /// [`RetryPolicy`]: super::RetryPolicy
+    pub fn retry_policy(self, policies: impl Into<RetryPolicies>) -> Self {
+        Endpoint {
+            retry_policies: policies.into(),
+            ..self
+        }
+    }
+
+    /// Hedge requests according to the given [`HedgingPolicy`] or [`HedgingPolicies`].
+    ///
+    /// A method can have either a retry or a hedging policy, but not both. Connecting fails with
+    /// an invalid service config error if a method has both, and so do its calls on lazily
+    /// connected or balanced channels.
+    ///
+    /// Default is to never hedge.
+    ///
+    /// The policies apply to the same channels as [retry policies](Endpoint::retry_policy).
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::{HedgingPolicies, HedgingPolicy}};
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.hedging_policy(HedgingPolicies::new().method(
+    ///     "helloworld.Greeter",
+    ///     "SayHello",
+    ///     HedgingPolicy::new(3).hedging_delay(Duration::from_millis(20)),
+    /// ));
+    /// ```
+    ///
+    /// [`HedgingPolicy`]: super::HedgingPolicy
+    pub fn hedging_policy(self, policies: impl Into<HedgingPolicies>) -> Self {
+        Endpoint {
+            hedging_policies: policies.into(),
+            ..self
+        }
+    }
+
+    /// Apply a [`ServiceConfig`] to the calls made through this channel.
+    ///
+    /// This configures per-method timeouts, message size limits and retry or hedging policies.
+    /// It replaces any policies set with [`Endpoint::retry_policy`] or
+    /// [`Endpoint::hedging_policy`].
+    ///
+    /// The service config timeout is an upper bound for the `grpc-timeout` of a call, and a
+    /// message that exceeds a size limit fails the call with [`Code::ResourceExhausted`].
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::{MethodConfig, ServiceConfig}};
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.service_config(ServiceConfig::new().service(
+    ///     "helloworld.Greeter",
+    ///     MethodConfig::new()
+    ///         .timeout(Duration::from_secs(1))
+    ///         .max_request_message_bytes(1024 * 1024),
+    /// ));
+    /// ```
+    ///
+    /// [`Code::ResourceExhausted`]: crate::Code::ResourceExhausted
+    pub fn service_config(self, config: ServiceConfig) -> Self {
+        Endpoint {
+            retry_policies: config.retry_policies(),
+            hedging_policies: config.hedging_policies(),
+            service_config: config,
+            ..self
+        }
+    }
+
+    /// Sets whether calls wait for the channel to be ready instead of failing while it cannot
+    /// connect.
+    ///
+    /// By default a call fails with [`Code::Unavailable`] when the connection to the server
+    /// cannot be established. With wait-for-ready, the call keeps waiting for the channel to
+    /// connect until the call deadline, if any, expires. The
+    /// [`wait_for_ready`](super::MethodConfig::wait_for_ready) option of a
+    /// [`ServiceConfig`] overrides this per method.
+    ///
+    /// Default is `false`.
+    ///
+    /// [`Code::Unavailable`]: crate::Code::Unavailable
+    pub fn wait_for_ready(self, enabled: bool) -> Self {
+        Endpoint {
+            wait_for_ready: enabled,
+            ..self
+        }
+    }
+
+    /// Sets how the channel backs off between attempts to connect.
+    ///
+    /// By default the channel attempts to connect again on the next call after an attempt
+    /// failed, without any delay.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::ReconnectBackoff};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.reconnect_backoff(ReconnectBackoff::new());
+    /// ```
+    pub fn reconnect_backoff(self, backoff: ReconnectBackoff) -> Self {
+        Endpoint {
+            reconnect_backoff: Some(backoff),
+            ..self
+        }
+    }
+
+    /// Sets the [`LoadBalancingPolicy`] of channels balancing over the addresses this endpoint
+    /// resolves to with [`Channel::balance_target`] or [`Channel::balance_resolver`].
+    ///
+    /// Default is [`LoadBalancingPolicy::PowerOfTwoChoices`].
+    pub fn load_balancing_policy(self, policy: LoadBalancingPolicy) -> Self {
+        Endpoint {
+            load_balancing_policy: policy,
+            ..self
+        }
+    }
+
+    /// Sets the weight of this endpoint under [`LoadBalancingPolicy::WeightedRoundRobin`].
+    ///
+    /// An endpoint with weight 3 receives three times as many requests as an endpoint with
+    /// weight 1. A weight of 0 is treated as 1.
+    ///
+    /// Default is 1.
+    pub fn weight(self, weight: u32) -> Self {
+        Endpoint {
+            weight: weight.max(1),
+            ..self
+        }
+    }
+
     /// Sets the executor used to spawn async tasks.
     ///
     /// Uses `tokio::spawn` by default.
@@ -325,6 +516,48 @@ impl Endpoint {
         )
     }
 
+    /// Fails if a method has both a retry and a hedging policy.
+    pub(crate) fn check_policies(&self) -> Result<(), Error> {
+        match self
+            .retry_policies
+            .conflicting_method(&self.hedging_policies)
+        {
+            Some(path) => Err(super::retry::conflicting_policies(&path)),
+            None => Ok(()),
+        }
+    }
+
+    /// Derive the endpoint of a resolved address from this template endpoint.
+    ///
+    /// The requests keep the origin of the template and connection failures are reported to
+    /// the resolver through `resolve_now`.
+    pub(crate) fn for_address(&self, address: &Address, resolve_now: &ResolveNow) -> Endpoint {
+        let scheme = self.uri.scheme_str().unwrap_or("http");
+        let authority = match address {
+            Address::Tcp(addr) => addr.to_string(),
+            #[cfg(unix)]
+            Address::Unix(_) => "localhost".into(),
+        };
+        let uri = Uri::builder()
+            .scheme(scheme)
+            .authority(authority)
+            .path_and_query("/")
+            .build()
+            .expect("a socket address is a valid authority");
+
+        Endpoint {
+            uri,
+            origin: Some(self.origin.clone().unwrap_or_else(|| self.uri.clone())),
+            resolve_now: Some(resolve_now.clone()),
+            #[cfg(unix)]
+            unix_path: match address {
+                Address::Unix(path) => Some(path.clone()),
+                _ => None,
+            },
+            ..self.clone()
+        }
+    }
+
     /// Create a channel from this config.
     pub async fn connect(&self) -> Result<Channel, Error> {
         let mut http = HttpConnector::new();
@@ -426,6 +659,16 @@ impl Endpoint {
         self.connect_timeout
     }
 
+    /// Get the reconnect backoff policy.
+    pub fn get_reconnect_backoff(&self) -> Option<&ReconnectBackoff> {
+        self.reconnect_backoff.as_ref()
+    }
+
+    /// Get the idle timeout.
+    pub fn get_idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
     /// Get whether TCP keepalive messages are enabled on accepted connections.
     ///
     /// If `None` is specified, keepalive is disabled, otherwise the duration
@@ -457,7 +700,18 @@ impl From<Uri> for Endpoint {
             http2_keep_alive_while_idle: None,
             http2_max_header_list_size: None,
             connect_timeout: None,
+            idle_timeout: None,
             http2_adaptive_window: None,
+            retry_policies: RetryPolicies::new(),
+            hedging_policies: HedgingPolicies::new(),
+            service_config: ServiceConfig::new(),
+            load_balancing_policy: LoadBalancingPolicy::default(),
+            weight: 1,
+            wait_for_ready: false,
+            reconnect_backoff: None,
+            resolve_now: None,
+            #[cfg(unix)]
+            unix_path: None,
             executor: SharedExec::tokio(),
         }
     }
@@ -0,0 +1,439 @@
+//! Client side request hedging driven by gRPC hedging policies.
+//!
+//! See [`HedgingPolicy`] for more details.
+
+use super::{
+    retry::{failed_attempt, parse_deadline, replay_request, Pushback},
+    service::{MethodMap, ReplayBody},
+};
+use crate::{body::BoxBody, Code};
+use http::{Request, Response};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+use tower_layer::Layer;
+use tower_service::Service;
+
+// The default per-RPC buffer limit of grpc-java.
+const DEFAULT_MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A gRPC hedging policy.
+///
+/// Hedging sends the same request more than once without waiting for the previous attempts to
+/// fail: the original request is sent right away and, as long as no attempt has completed, a
+/// duplicate is sent every [`hedging_delay`](HedgingPolicy::hedging_delay) until
+/// [`max_attempts`](HedgingPolicy::new) attempts are in flight. The first attempt that produces
+/// a response is returned and every other attempt is cancelled.
+///
+/// An attempt that fails with one of the [non-fatal status
+/// codes](HedgingPolicy::non_fatal_status_codes) before the server sent response headers does not
+/// complete the call, instead the next hedged attempt, if any, is sent immediately. When every
+/// attempt failed the last failure is returned.
+///
+/// On a load balanced [`Channel`] the duplicates are dispatched by the balancer like any other
+/// request, since the original attempt is still in flight they will usually be sent to a
+/// different, less loaded endpoint.
+///
+/// Like [retries](super::RetryPolicy), hedging buffers the request body so it can be replayed
+/// and stops sending duplicates once a request exceeds
+/// [`max_buffer_size`](HedgingPolicy::max_buffer_size). Hedging is meant for idempotent calls,
+/// usually unary ones, since the server may process the request more than once.
+///
+/// # Example
+///
+/// ```
+/// # use tonic::transport::{Endpoint, channel::HedgingPolicy};
+/// # use tonic::Code;
+/// # use std::time::Duration;
+/// let policy = HedgingPolicy::new(3)
+///     .hedging_delay(Duration::from_millis(20))
+///     .non_fatal_status_codes([Code::Unavailable]);
+///
+/// let endpoint = Endpoint::from_static("http://[::1]:50051").hedging_policy(policy);
+/// ```
+///
+/// [`Channel`]: super::Channel
+#[derive(Debug, Clone)]
+pub struct HedgingPolicy {
+    max_attempts: usize,
+    hedging_delay: Duration,
+    non_fatal_status_codes: Vec<Code>,
+    max_buffer_size: usize,
+}
+
+impl HedgingPolicy {
+    /// Create a new hedging policy that sends at most `max_attempts` attempts, including the
+    /// original one.
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            hedging_delay: Duration::ZERO,
+            non_fatal_status_codes: Vec::new(),
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+        }
+    }
+
+    /// Set how long to wait for a response before sending the next attempt.
+    ///
+    /// Default is zero, which sends all attempts at once.
+    pub fn hedging_delay(self, hedging_delay: Duration) -> Self {
+        HedgingPolicy {
+            hedging_delay,
+            ..self
+        }
+    }
+
+    /// Set the status codes that do not complete the call.
+    ///
+    /// Default is no status code, any response completes the call.
+    pub fn non_fatal_status_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        HedgingPolicy {
+            non_fatal_status_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Set how many bytes of a request are buffered to be able to replay it.
+    ///
+    /// Default is 1 MiB.
+    pub fn max_buffer_size(self, max_buffer_size: usize) -> Self {
+        HedgingPolicy {
+            max_buffer_size,
+            ..self
+        }
+    }
+
+    /// Get the maximum number of attempts.
+    pub fn get_max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Get the delay between two attempts.
+    pub fn get_hedging_delay(&self) -> Duration {
+        self.hedging_delay
+    }
+
+    /// Get the status codes that do not complete the call.
+    pub fn get_non_fatal_status_codes(&self) -> &[Code] {
+        &self.non_fatal_status_codes
+    }
+}
+
+/// A set of [`HedgingPolicy`]s, selected by the called service and method.
+///
+/// A policy for a specific method takes precedence over a policy for its whole service, which
+/// takes precedence over the default policy.
+#[derive(Debug, Clone, Default)]
+pub struct HedgingPolicies {
+    inner: MethodMap<HedgingPolicy>,
+}
+
+impl HedgingPolicies {
+    /// Create an empty set of policies, no call is hedged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy for every method without a more specific policy.
+    pub fn default_policy(mut self, policy: HedgingPolicy) -> Self {
+        self.inner.set_default(policy);
+        self
+    }
+
+    /// Set the policy for every method of `service`, e.g. `helloworld.Greeter`.
+    pub fn service(mut self, service: impl Into<String>, policy: HedgingPolicy) -> Self {
+        self.inner.set_service(service, policy);
+        self
+    }
+
+    /// Set the policy for a single method of `service`, e.g. `SayHello`.
+    pub fn method(mut self, service: &str, method: &str, policy: HedgingPolicy) -> Self {
+        self.inner.set_method(service, method, policy);
+        self
+    }
+
+    pub(crate) fn from_method_map(inner: MethodMap<HedgingPolicy>) -> Self {
+        Self { inner }
+    }
+
+    /// Whether calls to the request path are hedged.
+    pub(crate) fn hedges(&self, path: &str) -> bool {
+        self.inner
+            .get(path)
+            .is_some_and(|policy| policy.max_attempts > 1)
+    }
+
+    pub(crate) fn method_map(&self) -> &MethodMap<HedgingPolicy> {
+        &self.inner
+    }
+}
+
+impl From<HedgingPolicy> for HedgingPolicies {
+    fn from(policy: HedgingPolicy) -> Self {
+        HedgingPolicies::new().default_policy(policy)
+    }
+}
+
+/// Hedge requests according to [`HedgingPolicies`].
+///
+/// This can wrap any client service, for example a load balanced [`Channel`], when the policies
+/// should not be configured through [`Endpoint::hedging_policy`].
+///
+/// ```
+/// # use tonic::transport::{Channel, Endpoint, channel::{Hedge, HedgingPolicy}};
+/// # use std::time::Duration;
+/// # async fn run() {
+/// let endpoints = ["http://[::1]:50051", "http://[::1]:50052"]
+///     .into_iter()
+///     .map(Endpoint::from_static);
+/// let channel = Channel::balance_list(endpoints);
+///
+/// let channel = Hedge::new(
+///     channel,
+///     HedgingPolicy::new(2).hedging_delay(Duration::from_millis(20)),
+/// );
+/// # }
+/// ```
+///
+/// A method should be configured with either a hedging or a [retry](super::RetryPolicy) policy,
+/// but not both.
+///
+/// [`Channel`]: super::Channel
+/// [`Endpoint::hedging_policy`]: super::Endpoint::hedging_policy
+#[derive(Debug, Clone)]
+pub struct HedgeLayer {
+    policies: Arc<HedgingPolicies>,
+}
+
+impl HedgeLayer {
+    /// Create a new hedging layer.
+    pub fn new(policies: impl Into<HedgingPolicies>) -> Self {
+        Self {
+            policies: Arc::new(policies.into()),
+        }
+    }
+}
+
+impl<S> Layer<S> for HedgeLayer {
+    type Service = Hedge<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Hedge {
+            inner,
+            policies: self.policies.clone(),
+        }
+    }
+}
+
+/// A service that hedges requests, see [`HedgeLayer`].
+#[derive(Debug, Clone)]
+pub struct Hedge<S> {
+    inner: S,
+    policies: Arc<HedgingPolicies>,
+}
+
+impl<S> Hedge<S> {
+    /// Wrap `inner`, hedging its requests according to `policies`.
+    pub fn new(inner: S, policies: impl Into<HedgingPolicies>) -> Self {
+        HedgeLayer::new(policies).layer(inner)
+    }
+}
+
+impl<S, ResBody> Service<Request<BoxBody>> for Hedge<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<ResBody>> + Clone,
+    S::Error: Into<crate::BoxError>,
+{
+    type Response = Response<ResBody>;
+    type Error = crate::BoxError;
+    type Future = ResponseFuture<S>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<BoxBody>) -> Self::Future {
+        let policy = match self.policies.inner.get(request.uri().path()) {
+            Some(policy) if policy.max_attempts > 1 => policy,
+            _ => {
+                return ResponseFuture {
+                    future: Some(self.inner.call(request)),
+                    hedging: None,
+                }
+            }
+        };
+
+        let deadline = parse_deadline(request.headers());
+
+        let (parts, body) = request.into_parts();
+        let body = ReplayBody::new(body, policy.max_buffer_size);
+        let future = self.inner.call(Request::from_parts(
+            parts.clone(),
+            BoxBody::new(body.clone()),
+        ));
+
+        // The ready service is used for this call, keep a clone around for the hedges.
+        let service = self.inner.clone();
+
+        ResponseFuture {
+            future: None,
+            hedging: Some(Hedging {
+                service,
+                policy: policy.clone(),
+                parts,
+                body,
+                deadline,
+                attempts: 1,
+                in_flight: vec![Box::pin(future)],
+                next_attempt: Box::pin(tokio::time::sleep(policy.hedging_delay)),
+                stopped: false,
+                last_failure: None,
+            }),
+        }
+    }
+}
+
+/// Response future for [`Hedge`].
+#[pin_project]
+pub struct ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>>,
+{
+    #[pin]
+    future: Option<S::Future>,
+    hedging: Option<Hedging<S>>,
+}
+
+struct Hedging<S>
+where
+    S: Service<Request<BoxBody>>,
+{
+    service: S,
+    policy: HedgingPolicy,
+    parts: http::request::Parts,
+    body: ReplayBody,
+    deadline: Option<Instant>,
+    attempts: usize,
+    in_flight: Vec<Pin<Box<S::Future>>>,
+    next_attempt: Pin<Box<Sleep>>,
+    /// Set once the server asked us to stop hedging or the service failed.
+    stopped: bool,
+    last_failure: Option<Result<S::Response, crate::BoxError>>,
+}
+
+impl<S, ResBody> Future for ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<ResBody>>,
+    S::Error: Into<crate::BoxError>,
+{
+    type Output = Result<Response<ResBody>, crate::BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Some(future) = this.future.as_pin_mut() {
+            return future.poll(cx).map_err(Into::into);
+        }
+
+        this.hedging
+            .as_mut()
+            .expect("response future polled without a request")
+            .poll(cx)
+    }
+}
+
+impl<S, ResBody> Hedging<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<ResBody>>,
+    S::Error: Into<crate::BoxError>,
+{
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<Response<ResBody>, crate::BoxError>> {
+        loop {
+            let mut i = 0;
+            while i < self.in_flight.len() {
+                let Poll::Ready(result) = self.in_flight[i].as_mut().poll(cx) else {
+                    i += 1;
+                    continue;
+                };
+                drop(self.in_flight.swap_remove(i));
+
+                let result = result.map_err(Into::into);
+                let pushback = match failed_attempt(&result) {
+                    Some((status, pushback))
+                        if self.policy.non_fatal_status_codes.contains(&status.code()) =>
+                    {
+                        pushback
+                    }
+                    // Dropping the other attempts cancels them.
+                    _ => return Poll::Ready(result),
+                };
+
+                match pushback {
+                    Pushback::None => self.next_attempt.as_mut().reset(Instant::now()),
+                    Pushback::Delay(delay) => {
+                        self.next_attempt.as_mut().reset(Instant::now() + delay)
+                    }
+                    Pushback::Stop => self.stopped = true,
+                }
+                self.last_failure = Some(result);
+            }
+
+            if self.can_hedge() && self.next_attempt.as_mut().poll(cx).is_ready() {
+                match self.service.poll_ready(cx) {
+                    Poll::Ready(Ok(())) => {
+                        tracing::debug!(attempts = self.attempts, "sending hedged request");
+
+                        let request =
+                            replay_request(&self.parts, &self.body, self.attempts, self.deadline);
+                        self.attempts += 1;
+                        self.in_flight.push(Box::pin(self.service.call(request)));
+
+                        let next = Instant::now() + self.policy.hedging_delay;
+                        self.next_attempt.as_mut().reset(next);
+                        continue;
+                    }
+                    Poll::Ready(Err(err)) => {
+                        if self.in_flight.is_empty() {
+                            return Poll::Ready(Err(err.into()));
+                        }
+                        self.stopped = true;
+                    }
+                    Poll::Pending => {}
+                }
+            }
+
+            if self.in_flight.is_empty() && !self.can_hedge() {
+                let result = self
+                    .last_failure
+                    .take()
+                    .expect("every finished attempt failed");
+                return Poll::Ready(result);
+            }
+
+            return Poll::Pending;
+        }
+    }
+
+    fn can_hedge(&self) -> bool {
+        !self.stopped
+            && self.attempts < self.policy.max_attempts
+            && self.body.is_replayable()
+            && !matches!(self.deadline, Some(deadline) if Instant::now() >= deadline)
+    }
+}
+
+impl<S> fmt::Debug for ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
@@ -0,0 +1,60 @@
+use http::HeaderName;
+
+/// How a balanced [`Channel`] picks the endpoint for a request.
+///
+/// The policy applies to channels created by [`Channel::balance_list_with_policy`],
+/// [`Channel::balance_channel_with_policy`], and, through
+/// [`Endpoint::load_balancing_policy`], by [`Channel::balance_target`] and
+/// [`Channel::balance_resolver`].
+///
+/// ```
+/// # use tonic::transport::{Channel, Endpoint, channel::LoadBalancingPolicy};
+/// # use http::HeaderName;
+/// let endpoints = ["http://[::1]:50051", "http://[::1]:50052"]
+///     .into_iter()
+///     .map(Endpoint::from_static);
+///
+/// // Requests with the same `x-user-id` go to the same endpoint.
+/// let policy = LoadBalancingPolicy::RingHash(HeaderName::from_static("x-user-id"));
+/// # async {
+/// let channel = Channel::balance_list_with_policy(endpoints, policy);
+/// # };
+/// ```
+///
+/// [`Channel`]: super::Channel
+/// [`Channel::balance_list_with_policy`]: super::Channel::balance_list_with_policy
+/// [`Channel::balance_channel_with_policy`]: super::Channel::balance_channel_with_policy
+/// [`Channel::balance_target`]: super::Channel::balance_target
+/// [`Channel::balance_resolver`]: super::Channel::balance_resolver
+/// [`Endpoint::load_balancing_policy`]: super::Endpoint::load_balancing_policy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadBalancingPolicy {
+    /// Pick two ready endpoints at random and use the one with fewer requests in flight.
+    ///
+    /// This is the default.
+    #[default]
+    PowerOfTwoChoices,
+    /// Cycle through the ready endpoints in the order they were added.
+    RoundRobin,
+    /// Send every request to the first endpoint added, moving on to the next one in order when it
+    /// is removed or fails to connect.
+    ///
+    /// An endpoint that failed is skipped until it connects again. If all of them have failed,
+    /// calls go to the first one.
+    PickFirst,
+    /// Cycle through the ready endpoints in proportion to their
+    /// [`weight`](super::Endpoint::weight).
+    WeightedRoundRobin,
+    /// Pick the endpoint by consistent hashing of the value of a request header.
+    ///
+    /// Requests with the same value keep going to the same endpoint while the set of endpoints
+    /// is stable, and only a fraction of the values move when an endpoint is added or removed.
+    /// Requests without the header are spread at random.
+    ///
+    /// The endpoint is picked among the ready ones when the request is sent, so its readiness is
+    /// checked at pick time. Calls fail with [`Code::Unavailable`] if no endpoint is ready by then.
+    ///
+    /// [`Code::Unavailable`]: crate::Code::Unavailable
+    RingHash(HeaderName),
+}
@@ -1,15 +1,34 @@
 //! Client implementation and builder.
 
+mod backoff;
+mod connectivity;
 mod endpoint;
+pub mod hedge;
+mod load_balancing;
+pub mod resolver;
+pub mod retry;
 pub(crate) mod service;
+mod service_config;
 #[cfg(feature = "_tls-any")]
 mod tls;
 
+pub use backoff::ReconnectBackoff;
+pub use connectivity::{ConnectivityState, ConnectivityStateStream};
 pub use endpoint::Endpoint;
+pub use hedge::{Hedge, HedgeLayer, HedgingPolicies, HedgingPolicy};
+pub use load_balancing::LoadBalancingPolicy;
+pub use resolver::Resolver;
+pub use retry::{Retry, RetryLayer, RetryPolicies, RetryPolicy};
+pub use service_config::{MethodConfig, ServiceConfig};
 #[cfg(feature = "_tls-any")]
 pub use tls::ClientTlsConfig;
 
-use self::service::{Connection, DynamicServiceStream, Executor, SharedExec};
+use self::connectivity::Connectivity;
+use self::resolver::{Address, DnsResolver, ResolveNow, StaticResolver, Target};
+use self::service::{
+    ApplyMethodConfig, Balance, Connection, DynamicServiceStream, Executor, MethodMap, SharedExec,
+    WaitForReady,
+};
 use crate::body::BoxBody;
 use bytes::Bytes;
 use http::{
@@ -17,8 +36,9 @@ use http::{
     Request, Response,
 };
 use std::{
+    collections::HashSet,
     fmt,
-    future::Future,
+    future::{poll_fn, Future},
     hash::Hash,
     pin::Pin,
     task::{Context, Poll},
@@ -26,9 +46,10 @@ use std::{
 use tokio::sync::mpsc::{channel, Sender};
 
 use hyper::rt;
-use tower::balance::p2c::Balance;
+use pin_project::pin_project;
+use tower::balance::p2c;
 use tower::{
-    buffer::{future::ResponseFuture as BufferResponseFuture, Buffer},
+    buffer::Buffer,
     discover::{Change, Discover},
     util::BoxService,
     Service,
@@ -63,14 +84,22 @@ const DEFAULT_BUFFER_SIZE: usize = 1024;
 /// cloning the `Channel` type is cheap and encouraged.
 #[derive(Clone)]
 pub struct Channel {
-    svc: Buffer<Request<BoxBody>, BoxFuture<'static, Result<Response<BoxBody>, crate::BoxError>>>,
+    svc: ChannelService,
+    connectivity: Connectivity,
 }
 
+type ChannelService = ApplyMethodConfig<Retry<Hedge<WaitForReady<BufferedService>>>>;
+
+type BufferedService =
+    Buffer<Request<BoxBody>, BoxFuture<'static, Result<Response<BoxBody>, crate::BoxError>>>;
+
 /// A future that resolves to an HTTP response.
 ///
 /// This is returned by the `Service::call` on [`Channel`].
+#[pin_project]
 pub struct ResponseFuture {
-    inner: BufferResponseFuture<BoxFuture<'static, Result<Response<BoxBody>, crate::BoxError>>>,
+    #[pin]
+    inner: <ChannelService as Service<Request<BoxBody>>>::Future,
 }
 
 impl Channel {
@@ -101,12 +130,67 @@ impl Channel {
         Ok(Self::builder(uri))
     }
 
+    /// Get the current [`ConnectivityState`] of this channel.
+    ///
+    /// ```
+    /// # use tonic::transport::{Channel, channel::ConnectivityState};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let channel = Channel::from_static("http://[::1]:50051").connect_lazy();
+    /// assert_eq!(channel.state(), ConnectivityState::Idle);
+    /// # }
+    /// ```
+    pub fn state(&self) -> ConnectivityState {
+        self.connectivity.state()
+    }
+
+    /// Watch the [`ConnectivityState`] of this channel.
+    ///
+    /// The returned stream yields the current state and then every change, see
+    /// [`ConnectivityStateStream`].
+    pub fn watch_state(&self) -> ConnectivityStateStream {
+        self.connectivity.stream()
+    }
+
     /// Balance a list of [`Endpoint`]'s.
     ///
     /// This creates a [`Channel`] that will load balance across all the
     /// provided endpoints.
+    ///
+    /// The retry and hedging policies, wait-for-ready and service config of the channel are
+    /// taken from the first endpoint.
     pub fn balance_list(list: impl Iterator<Item = Endpoint>) -> Self {
-        let (channel, tx) = Self::balance_channel(DEFAULT_BUFFER_SIZE);
+        Self::balance_list_with_policy(list, LoadBalancingPolicy::default())
+    }
+
+    /// Balance a list of [`Endpoint`]'s with the given [`LoadBalancingPolicy`].
+    ///
+    /// This creates a [`Channel`] that will load balance across all the
+    /// provided endpoints.
+    ///
+    /// The retry and hedging policies, wait-for-ready and service config of the channel are
+    /// taken from the first endpoint.
+    pub fn balance_list_with_policy(
+        list: impl Iterator<Item = Endpoint>,
+        policy: LoadBalancingPolicy,
+    ) -> Self {
+        let mut list = list.peekable();
+        let config = list.peek().cloned();
+
+        let (tx, rx) = channel(DEFAULT_BUFFER_SIZE);
+        let connectivity = Connectivity::new();
+        let svc = Self::balance_buffered(
+            DynamicServiceStream::new(rx, connectivity.clone()),
+            &connectivity,
+            DEFAULT_BUFFER_SIZE,
+            &SharedExec::tokio(),
+            policy,
+        );
+        let channel = match config {
+            Some(config) => Self::with_config(svc, &config, connectivity),
+            None => Self::without_config(svc, connectivity),
+        };
+
         list.for_each(|endpoint| {
             tx.try_send(Change::Insert(endpoint.uri.clone(), endpoint))
                 .unwrap();
@@ -118,6 +202,10 @@ impl Channel {
     /// Balance a list of [`Endpoint`]'s.
     ///
     /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    ///
+    /// Only the connection settings of the endpoints are used. Their retry and hedging policies,
+    /// wait-for-ready and service config are not applied, use
+    /// [`Channel::balance_channel_with_config`] to configure the channel.
     pub fn balance_channel<K>(capacity: usize) -> (Self, Sender<Change<K, Endpoint>>)
     where
         K: Hash + Eq + Send + Clone + 'static,
@@ -125,11 +213,75 @@ impl Channel {
         Self::balance_channel_with_executor(capacity, SharedExec::tokio())
     }
 
+    /// Balance a list of [`Endpoint`]'s, configuring the channel like `config`.
+    ///
+    /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    ///
+    /// The retry and hedging policies, wait-for-ready, service config, load balancing policy,
+    /// buffer size and executor of the channel are taken from `config`, as
+    /// [`Channel::balance_list`] does with its first endpoint. Only the connection settings of
+    /// the endpoints that are sent are used.
+    ///
+    /// ```
+    /// # use tonic::transport::{Channel, Endpoint, channel::RetryPolicy};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let config = Endpoint::from_static("http://[::1]:50051").retry_policy(RetryPolicy::new(3));
+    /// let (channel, tx) = Channel::balance_channel_with_config::<usize>(10, &config);
+    /// # drop((channel, tx));
+    /// # }
+    /// ```
+    pub fn balance_channel_with_config<K>(
+        capacity: usize,
+        config: &Endpoint,
+    ) -> (Self, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        let (tx, rx) = channel(capacity);
+        let connectivity = Connectivity::new();
+        let svc = Self::balance_buffered(
+            DynamicServiceStream::new(rx, connectivity.clone()),
+            &connectivity,
+            config.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE),
+            &config.executor,
+            config.load_balancing_policy.clone(),
+        );
+        (Self::with_config(svc, config, connectivity), tx)
+    }
+
+    /// Balance a list of [`Endpoint`]'s with the given [`LoadBalancingPolicy`].
+    ///
+    /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    ///
+    /// See [`Channel::balance_channel`] for which settings of the endpoints are used.
+    pub fn balance_channel_with_policy<K>(
+        capacity: usize,
+        policy: LoadBalancingPolicy,
+    ) -> (Self, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        let (tx, rx) = channel(capacity);
+        let connectivity = Connectivity::new();
+        let list = DynamicServiceStream::new(rx, connectivity.clone());
+        let channel = Self::balance(
+            list,
+            connectivity,
+            DEFAULT_BUFFER_SIZE,
+            SharedExec::tokio(),
+            policy,
+        );
+        (channel, tx)
+    }
+
     /// Balance a list of [`Endpoint`]'s.
     ///
     /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
     ///
     /// The [`Channel`] will use the given executor to spawn async tasks.
+    ///
+    /// See [`Channel::balance_channel`] for which settings of the endpoints are used.
     pub fn balance_channel_with_executor<K, E>(
         capacity: usize,
         executor: E,
@@ -139,8 +291,131 @@ impl Channel {
         E: Executor<Pin<Box<dyn Future<Output = ()> + Send>>> + Send + Sync + 'static,
     {
         let (tx, rx) = channel(capacity);
-        let list = DynamicServiceStream::new(rx);
-        (Self::balance(list, DEFAULT_BUFFER_SIZE, executor), tx)
+        let connectivity = Connectivity::new();
+        let list = DynamicServiceStream::new(rx, connectivity.clone());
+        let policy = LoadBalancingPolicy::default();
+        let channel = Self::balance(list, connectivity, DEFAULT_BUFFER_SIZE, executor, policy);
+        (channel, tx)
+    }
+
+    /// Balance over the addresses a gRPC [target] resolves to.
+    ///
+    /// The target is resolved with the built-in resolver for its scheme:
+    ///
+    /// - `dns:///example.com:443` or `example.com:443` with a [`DnsResolver`], which
+    ///   periodically resolves the name again and whenever a connection fails.
+    /// - `unix:///tmp/grpc.sock` to connect to a Unix domain socket.
+    /// - `static:10.0.0.1:50051,10.0.0.2:50051` for a fixed list of addresses.
+    ///
+    /// The connection to every address is configured like `endpoint`, and requests keep the
+    /// origin of `endpoint`. Use [`Channel::balance_resolver`] for other schemes.
+    ///
+    /// ```no_run
+    /// # use tonic::transport::{Channel, Endpoint};
+    /// let endpoint = Endpoint::from_static("http://example.com");
+    /// let channel = Channel::balance_target("dns:///example.com:50051", endpoint)?;
+    /// # Ok::<(), tonic::transport::Error>(())
+    /// ```
+    ///
+    /// [target]: https://github.com/grpc/grpc/blob/master/doc/naming.md
+    pub fn balance_target(target: &str, endpoint: Endpoint) -> Result<Self, super::Error> {
+        let target = target.parse::<Target>()?;
+        endpoint.check_policies()?;
+
+        match target.scheme() {
+            "dns" => Ok(Self::balance_resolved(target, endpoint, DnsResolver::new())),
+            "unix" | "static" => Ok(Self::balance_resolved(
+                target,
+                endpoint,
+                StaticResolver::new(),
+            )),
+            _ => Err(super::Error::new_invalid_uri()),
+        }
+    }
+
+    /// Balance over the addresses a gRPC target resolves to with a custom [`Resolver`].
+    ///
+    /// See [`Channel::balance_target`] for how `endpoint` is used.
+    pub fn balance_resolver<R>(
+        target: &str,
+        endpoint: Endpoint,
+        resolver: R,
+    ) -> Result<Self, super::Error>
+    where
+        R: Resolver,
+    {
+        let target = target.parse::<Target>()?;
+        endpoint.check_policies()?;
+
+        Ok(Self::balance_resolved(target, endpoint, resolver))
+    }
+
+    fn balance_resolved<R: Resolver>(target: Target, endpoint: Endpoint, resolver: R) -> Self {
+        let resolve_now = ResolveNow::new();
+        let mut addresses = resolver.resolve(&target, resolve_now.clone());
+        let (tx, rx) = channel(DEFAULT_BUFFER_SIZE);
+
+        let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        let executor = endpoint.executor.clone();
+        let connectivity = Connectivity::new();
+        let svc = Self::balance_buffered(
+            DynamicServiceStream::new(rx, connectivity.clone()),
+            &connectivity,
+            buffer_size,
+            &executor,
+            endpoint.load_balancing_policy.clone(),
+        );
+        let channel = Self::with_config(svc, &endpoint, connectivity);
+
+        executor.execute(Box::pin(async move {
+            let mut current = HashSet::<Address>::new();
+            let mut closed = std::pin::pin!(tx.closed());
+
+            loop {
+                // Stop resolving once the channel is gone.
+                let next = poll_fn(|cx| match closed.as_mut().poll(cx) {
+                    Poll::Ready(()) => Poll::Ready(None),
+                    Poll::Pending => addresses.as_mut().poll_next(cx),
+                })
+                .await;
+
+                let resolved = match next {
+                    Some(Ok(resolved)) if !resolved.is_empty() => {
+                        resolved.into_iter().collect::<HashSet<_>>()
+                    }
+                    Some(Ok(_)) => {
+                        tracing::debug!("no addresses resolved for {}", target);
+                        continue;
+                    }
+                    Some(Err(error)) => {
+                        tracing::debug!("failed to resolve {}: {}", target, error);
+                        continue;
+                    }
+                    None => return,
+                };
+
+                for address in current.difference(&resolved) {
+                    if tx.send(Change::Remove(address.clone())).await.is_err() {
+                        return;
+                    }
+                }
+
+                for address in resolved.difference(&current) {
+                    let endpoint = endpoint.for_address(address, &resolve_now);
+                    if tx
+                        .send(Change::Insert(address.clone(), endpoint))
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+
+                current = resolved;
+            }
+        }));
+
+        channel
     }
 
     /// Create a new [`Channel`] using a custom connector to the provided [Endpoint].
@@ -155,13 +430,15 @@ impl Channel {
     {
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let config = endpoint.clone();
+        let connectivity = Connectivity::new();
 
-        let svc = Connection::lazy(connector, endpoint);
+        let svc = Connection::lazy(connector, endpoint, &connectivity);
         let (svc, worker) = Buffer::pair(svc, buffer_size);
 
-        executor.execute(worker);
+        Self::spawn_worker(&executor, worker, &connectivity);
 
-        Channel { svc }
+        Self::with_config(svc, &config, connectivity)
     }
 
     /// Connect to the provided [`Endpoint`] using the provided connector, and return a new [`Channel`].
@@ -174,32 +451,112 @@ impl Channel {
         C::Future: Unpin + Send,
         C::Response: rt::Read + rt::Write + Unpin + Send + 'static,
     {
+        endpoint.check_policies()?;
+
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let config = endpoint.clone();
+        let connectivity = Connectivity::new();
 
-        let svc = Connection::connect(connector, endpoint)
+        let svc = Connection::connect(connector, endpoint, &connectivity)
             .await
             .map_err(super::Error::from_source)?;
         let (svc, worker) = Buffer::pair(svc, buffer_size);
-        executor.execute(worker);
+        Self::spawn_worker(&executor, worker, &connectivity);
 
-        Ok(Channel { svc })
+        Ok(Self::with_config(svc, &config, connectivity))
     }
 
-    pub(crate) fn balance<D, E>(discover: D, buffer_size: usize, executor: E) -> Self
+    pub(crate) fn balance<D, E>(
+        discover: D,
+        connectivity: Connectivity,
+        buffer_size: usize,
+        executor: E,
+        policy: LoadBalancingPolicy,
+    ) -> Self
     where
         D: Discover<Service = Connection> + Unpin + Send + 'static,
         D::Error: Into<crate::BoxError>,
         D::Key: Hash + Send + Clone,
         E: Executor<BoxFuture<'static, ()>> + Send + Sync + 'static,
     {
-        let svc = Balance::new(discover);
+        let svc = Self::balance_buffered(discover, &connectivity, buffer_size, &executor, policy);
+
+        Self::without_config(svc, connectivity)
+    }
+
+    fn balance_buffered<D, E>(
+        discover: D,
+        connectivity: &Connectivity,
+        buffer_size: usize,
+        executor: &E,
+        policy: LoadBalancingPolicy,
+    ) -> BufferedService
+    where
+        D: Discover<Service = Connection> + Unpin + Send + 'static,
+        D::Error: Into<crate::BoxError>,
+        D::Key: Hash + Send + Clone,
+        E: Executor<BoxFuture<'static, ()>>,
+    {
+        let svc = match policy {
+            LoadBalancingPolicy::PowerOfTwoChoices => BoxService::new(p2c::Balance::new(discover)),
+            policy => BoxService::new(Balance::new(discover, policy)),
+        };
 
-        let svc = BoxService::new(svc);
         let (svc, worker) = Buffer::pair(svc, buffer_size);
-        executor.execute(Box::pin(worker));
+        Self::spawn_worker(executor, worker, connectivity);
+
+        svc
+    }
+
+    /// Runs the worker of the channel buffer, the channel shuts down once it finishes.
+    fn spawn_worker<E, F>(executor: &E, worker: F, connectivity: &Connectivity)
+    where
+        E: Executor<BoxFuture<'static, ()>>,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let connectivity = connectivity.clone();
+        executor.execute(Box::pin(async move {
+            worker.await;
+            connectivity.shutdown();
+        }));
+    }
 
-        Channel { svc }
+    /// Wraps `svc` without retries, hedging, wait-for-ready or method configuration.
+    fn without_config(svc: BufferedService, connectivity: Connectivity) -> Self {
+        Channel {
+            svc: ApplyMethodConfig::new(
+                Retry::new(
+                    Hedge::new(
+                        WaitForReady::new(svc, false, connectivity.clone()),
+                        HedgingPolicies::new(),
+                    ),
+                    RetryPolicies::new(),
+                ),
+                MethodMap::new(),
+            ),
+            connectivity,
+        }
+    }
+
+    /// Applies the retry, hedging, wait-for-ready and method configuration of `endpoint`.
+    ///
+    /// Calls to a method with both a retry and a hedging policy fail.
+    fn with_config(svc: BufferedService, endpoint: &Endpoint, connectivity: Connectivity) -> Self {
+        Channel {
+            svc: ApplyMethodConfig::new(
+                Retry::with_hedging(
+                    Hedge::new(
+                        WaitForReady::new(svc, endpoint.wait_for_ready, connectivity.clone()),
+                        endpoint.hedging_policies.clone(),
+                    ),
+                    endpoint.retry_policies.clone(),
+                    endpoint.hedging_policies.clone(),
+                ),
+                endpoint.service_config.method_configs().clone(),
+            ),
+            connectivity,
+        }
     }
 }
 
@@ -222,8 +579,9 @@ impl Service<http::Request<BoxBody>> for Channel {
 impl Future for ResponseFuture {
     type Output = Result<Response<BoxBody>, super::Error>;
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.inner)
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project()
+            .inner
             .poll(cx)
             .map_err(super::Error::from_source)
     }
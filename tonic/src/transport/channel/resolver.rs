@@ -0,0 +1,408 @@
+//! Name resolution for load balanced channels.
+//!
+//! A [`Resolver`] turns a [`Target`] such as `dns:///example.com:443` into the set of addresses
+//! a [`Channel`] balances over, and keeps that set up to date. Use
+//! [`Channel::balance_target`] for the built-in resolvers or [`Channel::balance_resolver`] to
+//! plug in your own.
+//!
+//! [`Channel`]: super::Channel
+//! [`Channel::balance_target`]: super::Channel::balance_target
+//! [`Channel::balance_resolver`]: super::Channel::balance_resolver
+
+use crate::transport::Error;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::Notify, time::Instant};
+use tokio_stream::Stream;
+
+// The default port of gRPC targets without an explicit port.
+const DEFAULT_PORT: u16 = 443;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stream of address sets produced by a [`Resolver`].
+pub type AddressStream = Pin<Box<dyn Stream<Item = Result<Vec<Address>, crate::BoxError>> + Send>>;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Resolves a [`Target`] into the addresses to connect to.
+///
+/// Every item of the returned stream is the complete set of addresses for the target, the
+/// channel connects to new addresses and drops the connections to addresses that are gone. An
+/// error or an empty set is logged and the channel keeps using the previous addresses.
+///
+/// ```
+/// use tonic::transport::channel::resolver::{Address, AddressStream, ResolveNow, Resolver, Target};
+///
+/// /// Always resolves to the same address.
+/// struct Fixed(std::net::SocketAddr);
+///
+/// impl Resolver for Fixed {
+///     fn resolve(&self, _target: &Target, _resolve_now: ResolveNow) -> AddressStream {
+///         Box::pin(tokio_stream::once(Ok(vec![Address::Tcp(self.0)])))
+///     }
+/// }
+/// ```
+pub trait Resolver: Send + Sync + 'static {
+    /// Start resolving `target`.
+    ///
+    /// `resolve_now` is signalled when the channel suspects the current addresses are stale,
+    /// for example because a connection failed.
+    fn resolve(&self, target: &Target, resolve_now: ResolveNow) -> AddressStream;
+}
+
+impl<R: Resolver + ?Sized> Resolver for Arc<R> {
+    fn resolve(&self, target: &Target, resolve_now: ResolveNow) -> AddressStream {
+        (**self).resolve(target, resolve_now)
+    }
+}
+
+/// An address a [`Target`] resolves to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// A TCP socket address.
+    Tcp(SocketAddr),
+    /// The path of a Unix domain socket.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// A signal from the channel asking its [`Resolver`] to resolve again.
+#[derive(Clone, Default)]
+pub struct ResolveNow {
+    notify: Arc<Notify>,
+}
+
+impl ResolveNow {
+    /// Create a new signal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait until the channel asks for a new resolution.
+    ///
+    /// A request made while nobody is waiting is remembered for the next call.
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    pub(crate) fn notify(&self) {
+        self.notify.notify_one();
+    }
+}
+
+impl fmt::Debug for ResolveNow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolveNow").finish()
+    }
+}
+
+/// A parsed gRPC [target], `scheme://authority/endpoint` or `scheme:endpoint`.
+///
+/// A target without a known scheme, e.g. `example.com:443`, is resolved with the `dns` scheme.
+///
+/// ```
+/// # use tonic::transport::channel::resolver::Target;
+/// let target: Target = "dns:///example.com:443".parse().unwrap();
+/// assert_eq!(target.scheme(), "dns");
+/// assert_eq!(target.endpoint(), "example.com:443");
+///
+/// let target: Target = "unix:///tmp/grpc.sock".parse().unwrap();
+/// assert_eq!(target.endpoint(), "/tmp/grpc.sock");
+/// ```
+///
+/// [target]: https://github.com/grpc/grpc/blob/master/doc/naming.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    scheme: String,
+    authority: String,
+    endpoint: String,
+}
+
+impl Target {
+    /// Get the scheme, e.g. `dns`.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Get the authority, usually empty.
+    pub fn authority(&self) -> &str {
+        &self.authority
+    }
+
+    /// Get the name to resolve, e.g. `example.com:443`.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let target = match s.split_once(':') {
+            Some(("unix", path)) => Target {
+                scheme: "unix".into(),
+                authority: String::new(),
+                endpoint: path.strip_prefix("//").unwrap_or(path).into(),
+            },
+            Some((scheme, rest)) if is_scheme(scheme, rest) => {
+                let (authority, endpoint) = match rest.strip_prefix("//") {
+                    Some(rest) => rest.split_once('/').unwrap_or((rest, "")),
+                    None => ("", rest),
+                };
+
+                Target {
+                    scheme: scheme.into(),
+                    authority: authority.into(),
+                    endpoint: endpoint.into(),
+                }
+            }
+            _ => Target {
+                scheme: "dns".into(),
+                authority: String::new(),
+                endpoint: s.into(),
+            },
+        };
+
+        if target.endpoint.is_empty() {
+            return Err(Error::new_invalid_uri());
+        }
+
+        Ok(target)
+    }
+}
+
+fn is_scheme(scheme: &str, rest: &str) -> bool {
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    // `localhost:50051` is a host and port, not a scheme.
+    valid && (matches!(scheme, "dns" | "static") || rest.starts_with("//"))
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}/{}", self.scheme, self.authority, self.endpoint)
+    }
+}
+
+/// The built-in resolver for `dns` targets.
+///
+/// The target endpoint is resolved with the system resolver, using port 443 when the target has
+/// no port. The addresses are refreshed every [`refresh_interval`](DnsResolver::refresh_interval)
+/// and whenever the channel asks for it, for example after a connection failed, but no more
+/// than once per [`min_refresh_interval`](DnsResolver::min_refresh_interval).
+#[derive(Debug, Clone)]
+pub struct DnsResolver {
+    refresh_interval: Duration,
+    min_refresh_interval: Duration,
+}
+
+impl DnsResolver {
+    /// Create a new DNS resolver.
+    pub fn new() -> Self {
+        Self {
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            min_refresh_interval: DEFAULT_MIN_REFRESH_INTERVAL,
+        }
+    }
+
+    /// Set how often the addresses are refreshed.
+    ///
+    /// Default is 30 seconds.
+    pub fn refresh_interval(self, refresh_interval: Duration) -> Self {
+        DnsResolver {
+            refresh_interval,
+            ..self
+        }
+    }
+
+    /// Set the minimum time between two lookups.
+    ///
+    /// Default is 1 second.
+    pub fn min_refresh_interval(self, min_refresh_interval: Duration) -> Self {
+        DnsResolver {
+            min_refresh_interval,
+            ..self
+        }
+    }
+}
+
+impl Default for DnsResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver for DnsResolver {
+    fn resolve(&self, target: &Target, resolve_now: ResolveNow) -> AddressStream {
+        let host = host_and_port(target.endpoint());
+
+        Box::pin(DnsStream {
+            lookup: Box::pin(lookup(host.clone())),
+            host,
+            resolver: self.clone(),
+            resolve_now,
+        })
+    }
+}
+
+struct DnsStream {
+    lookup: BoxFuture<Result<Vec<Address>, crate::BoxError>>,
+    host: String,
+    resolver: DnsResolver,
+    resolve_now: ResolveNow,
+}
+
+impl Stream for DnsStream {
+    type Item = Result<Vec<Address>, crate::BoxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let result = ready!(self.lookup.as_mut().poll(cx));
+
+        let host = self.host.clone();
+        let resolve_now = self.resolve_now.clone();
+        let DnsResolver {
+            refresh_interval,
+            min_refresh_interval,
+        } = self.resolver;
+        let next = Instant::now() + min_refresh_interval;
+
+        self.lookup = Box::pin(async move {
+            // Either timing out or being notified triggers the next lookup.
+            let _ = tokio::time::timeout(refresh_interval, resolve_now.notified()).await;
+            tokio::time::sleep_until(next).await;
+            lookup(host).await
+        });
+
+        Poll::Ready(Some(result))
+    }
+}
+
+async fn lookup(host: String) -> Result<Vec<Address>, crate::BoxError> {
+    let addresses = tokio::net::lookup_host(&host)
+        .await
+        .map_err(|err| io::Error::new(err.kind(), format!("failed to resolve {host}: {err}")))?;
+
+    Ok(addresses.map(Address::Tcp).collect())
+}
+
+/// Adds the default port to a `host[:port]` endpoint.
+fn host_and_port(endpoint: &str) -> String {
+    if endpoint.parse::<SocketAddr>().is_ok() {
+        return endpoint.into();
+    }
+
+    if let Ok(ip) = endpoint
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        return SocketAddr::new(ip, DEFAULT_PORT).to_string();
+    }
+
+    match endpoint.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => endpoint.into(),
+        _ => format!("{endpoint}:{DEFAULT_PORT}"),
+    }
+}
+
+/// The built-in resolver for `static` and `unix` targets.
+///
+/// A `static` target is a comma separated list of socket addresses, e.g.
+/// `static:10.0.0.1:50051,10.0.0.2:50051`, and a `unix` target is the path of a Unix domain
+/// socket, e.g. `unix:///tmp/grpc.sock`. The addresses never change.
+#[derive(Debug, Clone, Default)]
+pub struct StaticResolver {
+    _priv: (),
+}
+
+impl StaticResolver {
+    /// Create a new static resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn resolve(&self, target: &Target, _resolve_now: ResolveNow) -> AddressStream {
+        let addresses = match target.scheme() {
+            #[cfg(unix)]
+            "unix" => Ok(vec![Address::Unix(target.endpoint().into())]),
+            _ => target
+                .endpoint()
+                .split(',')
+                .map(|address| {
+                    address
+                        .trim()
+                        .parse()
+                        .map(Address::Tcp)
+                        .map_err(|_| format!("invalid socket address {address:?}").into())
+                })
+                .collect(),
+        };
+
+        Box::pin(tokio_stream::once(addresses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        let cases = [
+            ("dns:///example.com:443", ("dns", "", "example.com:443")),
+            (
+                "dns://8.8.8.8/example.com",
+                ("dns", "8.8.8.8", "example.com"),
+            ),
+            ("dns:example.com", ("dns", "", "example.com")),
+            ("example.com:443", ("dns", "", "example.com:443")),
+            ("localhost:50051", ("dns", "", "localhost:50051")),
+            ("[::1]:50051", ("dns", "", "[::1]:50051")),
+            ("unix:///tmp/grpc.sock", ("unix", "", "/tmp/grpc.sock")),
+            ("unix:relative.sock", ("unix", "", "relative.sock")),
+            (
+                "static:10.0.0.1:80,10.0.0.2:80",
+                ("static", "", "10.0.0.1:80,10.0.0.2:80"),
+            ),
+            ("consul://agent/service", ("consul", "agent", "service")),
+        ];
+
+        for (input, (scheme, authority, endpoint)) in cases {
+            let target = input.parse::<Target>().unwrap();
+            assert_eq!(target.scheme(), scheme, "{input}");
+            assert_eq!(target.authority(), authority, "{input}");
+            assert_eq!(target.endpoint(), endpoint, "{input}");
+        }
+
+        assert!("dns:///".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn adds_default_port() {
+        assert_eq!(host_and_port("example.com"), "example.com:443");
+        assert_eq!(host_and_port("example.com:80"), "example.com:80");
+        assert_eq!(host_and_port("10.0.0.1"), "10.0.0.1:443");
+        assert_eq!(host_and_port("[::1]"), "[::1]:443");
+        assert_eq!(host_and_port("::1"), "[::1]:443");
+        assert_eq!(host_and_port("[::1]:80"), "[::1]:80");
+    }
+}
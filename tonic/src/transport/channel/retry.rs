@@ -0,0 +1,836 @@
+//! Client side retries driven by gRPC retry policies.
+//!
+//! See [`RetryPolicy`] for more details.
+
+use super::service::{MethodMap, ReplayBody};
+use super::HedgingPolicies;
+use crate::{
+    body::BoxBody,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::{duration_to_grpc_timeout, try_parse_grpc_timeout},
+    Code, Status,
+};
+use http::{HeaderMap, HeaderValue, Request, Response};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const GRPC_PREVIOUS_RPC_ATTEMPTS: &str = "grpc-previous-rpc-attempts";
+const GRPC_RETRY_PUSHBACK_MS: &str = "grpc-retry-pushback-ms";
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+// The default per-RPC buffer limit of grpc-java.
+const DEFAULT_MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+type PushbackFn = Arc<dyn Fn(&Status) -> Option<Duration> + Send + Sync + 'static>;
+
+/// A gRPC retry policy.
+///
+/// A failed call is retried when its status code is one of the
+/// [retryable status codes](RetryPolicy::retryable_status_codes), the response did not already
+/// start (the server answered with a [Trailers-Only] response), fewer than
+/// [`max_attempts`](RetryPolicy::new) attempts have been made and the request deadline, if any,
+/// has not expired. Between attempts the client waits for a random duration between zero and the
+/// current backoff, which starts at [`initial_backoff`](RetryPolicy::initial_backoff) and is
+/// multiplied by the [`backoff_multiplier`](RetryPolicy::backoff_multiplier) after every attempt,
+/// up to [`max_backoff`](RetryPolicy::max_backoff).
+///
+/// Servers may override the backoff with the `grpc-retry-pushback-ms` trailer, with the
+/// `retry_delay` of `google.rpc.RetryInfo` error details in `grpc-status-details-bin`, or any
+/// other signal via [`RetryPolicy::server_pushback_fn`].
+///
+/// Request bodies are buffered so they can be replayed, this includes client streaming calls.
+/// Once a request exceeds [`max_buffer_size`](RetryPolicy::max_buffer_size) it will not be
+/// retried anymore.
+///
+/// # Example
+///
+/// ```
+/// # use tonic::transport::{Endpoint, channel::RetryPolicy};
+/// # use tonic::Code;
+/// # use std::time::Duration;
+/// let policy = RetryPolicy::new(4)
+///     .initial_backoff(Duration::from_millis(50))
+///     .max_backoff(Duration::from_secs(2))
+///     .retryable_status_codes([Code::Unavailable, Code::ResourceExhausted]);
+///
+/// let endpoint = Endpoint::from_static("http://[::1]:50051").retry_policy(policy);
+/// ```
+///
+/// [Trailers-Only]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md#responses
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff_multiplier: f64,
+    retryable_status_codes: Vec<Code>,
+    max_buffer_size: usize,
+    server_pushback: Option<PushbackFn>,
+}
+
+impl RetryPolicy {
+    /// Create a new retry policy that makes at most `max_attempts` attempts, including the
+    /// original one.
+    ///
+    /// By default only [`Code::Unavailable`] is retried.
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+            retryable_status_codes: vec![Code::Unavailable],
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            server_pushback: None,
+        }
+    }
+
+    /// Set the backoff before the first retry.
+    ///
+    /// Default is 100 milliseconds.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Set the upper bound of the backoff between two attempts.
+    ///
+    /// Default is 1 second.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Set the factor the backoff grows by after every attempt.
+    ///
+    /// Default is 2.
+    pub fn backoff_multiplier(self, backoff_multiplier: f64) -> Self {
+        RetryPolicy {
+            backoff_multiplier,
+            ..self
+        }
+    }
+
+    /// Set the status codes that are retried.
+    ///
+    /// Default is [`Code::Unavailable`].
+    pub fn retryable_status_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        RetryPolicy {
+            retryable_status_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Set how many bytes of a request are buffered to be able to replay it.
+    ///
+    /// Default is 1 MiB.
+    pub fn max_buffer_size(self, max_buffer_size: usize) -> Self {
+        RetryPolicy {
+            max_buffer_size,
+            ..self
+        }
+    }
+
+    /// Let the failed [`Status`] decide how long to wait before the next attempt.
+    ///
+    /// When `f` returns `Some` the client waits for exactly that long instead of the computed
+    /// backoff, or the delay of `google.rpc.RetryInfo` error details:
+    ///
+    /// ```
+    /// # use tonic::transport::channel::RetryPolicy;
+    /// # use std::time::Duration;
+    /// let policy = RetryPolicy::new(3).server_pushback_fn(|status| {
+    ///     status
+    ///         .metadata()
+    ///         .get("retry-after-ms")?
+    ///         .to_str()
+    ///         .ok()?
+    ///         .parse()
+    ///         .ok()
+    ///         .map(Duration::from_millis)
+    /// });
+    /// ```
+    ///
+    /// The `grpc-retry-pushback-ms` trailer is always honoured and takes precedence.
+    pub fn server_pushback_fn<F>(self, f: F) -> Self
+    where
+        F: Fn(&Status) -> Option<Duration> + Send + Sync + 'static,
+    {
+        RetryPolicy {
+            server_pushback: Some(Arc::new(f)),
+            ..self
+        }
+    }
+
+    /// Get the maximum number of attempts.
+    pub fn get_max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Get the backoff before the first retry.
+    pub fn get_initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// Get the upper bound of the backoff between two attempts.
+    pub fn get_max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Get the factor the backoff grows by after every attempt.
+    pub fn get_backoff_multiplier(&self) -> f64 {
+        self.backoff_multiplier
+    }
+
+    /// Get the status codes that are retried.
+    pub fn get_retryable_status_codes(&self) -> &[Code] {
+        &self.retryable_status_codes
+    }
+
+    fn is_retryable(&self, code: Code) -> bool {
+        self.retryable_status_codes.contains(&code)
+    }
+
+    /// The backoff to wait for before attempt number `attempt`, starting with the first retry at
+    /// `1`.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+
+        if backoff.is_finite() && backoff > 0.0 {
+            Duration::from_secs_f64(backoff * crate::util::fast_random())
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("backoff_multiplier", &self.backoff_multiplier)
+            .field("retryable_status_codes", &self.retryable_status_codes)
+            .field("max_buffer_size", &self.max_buffer_size)
+            .finish()
+    }
+}
+
+/// A set of [`RetryPolicy`]s, selected by the called service and method.
+///
+/// A policy for a specific method takes precedence over a policy for its whole service, which
+/// takes precedence over the default policy.
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicies {
+    inner: MethodMap<RetryPolicy>,
+}
+
+impl RetryPolicies {
+    /// Create an empty set of policies, no call is retried.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy for every method without a more specific policy.
+    pub fn default_policy(mut self, policy: RetryPolicy) -> Self {
+        self.inner.set_default(policy);
+        self
+    }
+
+    /// Set the policy for every method of `service`, e.g. `helloworld.Greeter`.
+    pub fn service(mut self, service: impl Into<String>, policy: RetryPolicy) -> Self {
+        self.inner.set_service(service, policy);
+        self
+    }
+
+    /// Set the policy for a single method of `service`, e.g. `SayHello`.
+    pub fn method(mut self, service: &str, method: &str, policy: RetryPolicy) -> Self {
+        self.inner.set_method(service, method, policy);
+        self
+    }
+
+    pub(crate) fn from_method_map(inner: MethodMap<RetryPolicy>) -> Self {
+        Self { inner }
+    }
+
+    /// A request path of a method that has both a retry policy and one of the hedging
+    /// `policies`, which gRPC does not allow.
+    pub(crate) fn conflicting_method(&self, policies: &HedgingPolicies) -> Option<String> {
+        self.inner
+            .paths()
+            .chain(policies.method_map().paths())
+            .find(|path| self.get(path).is_some() && policies.hedges(path))
+    }
+
+    /// The policy retrying calls to the request path.
+    fn get(&self, path: &str) -> Option<&RetryPolicy> {
+        self.inner
+            .get(path)
+            .filter(|policy| policy.max_attempts > 1)
+    }
+}
+
+/// The error of a call to a method with both a retry and a hedging policy.
+pub(crate) fn conflicting_policies(path: &str) -> crate::transport::Error {
+    crate::transport::Error::new_service_config(format!(
+        "`{path}` has both a retry and a hedging policy"
+    ))
+}
+
+impl From<RetryPolicy> for RetryPolicies {
+    fn from(policy: RetryPolicy) -> Self {
+        RetryPolicies::new().default_policy(policy)
+    }
+}
+
+/// Retry failed requests according to [`RetryPolicies`].
+///
+/// This can wrap any client service, for example a load balanced [`Channel`], when the policies
+/// should not be configured through [`Endpoint::retry_policy`].
+///
+/// [`Channel`]: super::Channel
+/// [`Endpoint::retry_policy`]: super::Endpoint::retry_policy
+#[derive(Debug, Clone)]
+pub struct RetryLayer {
+    policies: Arc<RetryPolicies>,
+}
+
+impl RetryLayer {
+    /// Create a new retry layer.
+    pub fn new(policies: impl Into<RetryPolicies>) -> Self {
+        Self {
+            policies: Arc::new(policies.into()),
+        }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = Retry<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Retry {
+            inner,
+            policies: self.policies.clone(),
+            hedging: None,
+        }
+    }
+}
+
+/// A service that retries failed requests, see [`RetryLayer`].
+#[derive(Debug, Clone)]
+pub struct Retry<S> {
+    inner: S,
+    policies: Arc<RetryPolicies>,
+    /// The hedging policies `inner` applies, calls that would be both retried and hedged fail.
+    hedging: Option<Arc<HedgingPolicies>>,
+}
+
+impl<S> Retry<S> {
+    /// Wrap `inner`, retrying its requests according to `policies`.
+    pub fn new(inner: S, policies: impl Into<RetryPolicies>) -> Self {
+        RetryLayer::new(policies).layer(inner)
+    }
+
+    /// Wrap `inner`, which hedges requests according to `hedging`.
+    pub(crate) fn with_hedging(
+        inner: S,
+        policies: RetryPolicies,
+        hedging: HedgingPolicies,
+    ) -> Self {
+        Retry {
+            inner,
+            policies: Arc::new(policies),
+            hedging: Some(Arc::new(hedging)),
+        }
+    }
+}
+
+impl<S, ResBody> Service<Request<BoxBody>> for Retry<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<ResBody>> + Clone,
+    S::Error: Into<crate::BoxError>,
+{
+    type Response = Response<ResBody>;
+    type Error = crate::BoxError;
+    type Future = ResponseFuture<S>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<BoxBody>) -> Self::Future {
+        let path = request.uri().path();
+        let policy = match self.policies.get(path) {
+            Some(_) if self.hedging.as_ref().is_some_and(|h| h.hedges(path)) => {
+                return ResponseFuture {
+                    state: State::Rejected {
+                        error: Some(conflicting_policies(path).into()),
+                    },
+                    attempt: None,
+                }
+            }
+            Some(policy) => policy,
+            None => {
+                return ResponseFuture {
+                    state: State::Called {
+                        future: self.inner.call(request),
+                    },
+                    attempt: None,
+                }
+            }
+        };
+
+        let deadline = parse_deadline(request.headers());
+
+        let (parts, body) = request.into_parts();
+        let body = ReplayBody::new(body, policy.max_buffer_size);
+        let future = self.inner.call(Request::from_parts(
+            parts.clone(),
+            BoxBody::new(body.clone()),
+        ));
+
+        // The ready service is used for this call, keep a clone around for the retries.
+        let service = self.inner.clone();
+
+        ResponseFuture {
+            state: State::Called { future },
+            attempt: Some(Attempt {
+                service,
+                policy: policy.clone(),
+                parts,
+                body,
+                deadline,
+                attempts: 1,
+            }),
+        }
+    }
+}
+
+/// Response future for [`Retry`].
+#[pin_project]
+pub struct ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>>,
+{
+    #[pin]
+    state: State<S::Future>,
+    attempt: Option<Attempt<S>>,
+}
+
+struct Attempt<S> {
+    service: S,
+    policy: RetryPolicy,
+    parts: http::request::Parts,
+    body: ReplayBody,
+    deadline: Option<Instant>,
+    attempts: usize,
+}
+
+#[pin_project(project = StateProj)]
+enum State<F> {
+    Called {
+        #[pin]
+        future: F,
+    },
+    Backoff {
+        #[pin]
+        sleep: Sleep,
+    },
+    Ready,
+    /// The call was not made, see [`conflicting_policies`].
+    Rejected {
+        error: Option<crate::BoxError>,
+    },
+}
+
+impl<S, ResBody> Future for ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<ResBody>>,
+    S::Error: Into<crate::BoxError>,
+{
+    type Output = Result<Response<ResBody>, crate::BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            match this.state.as_mut().project() {
+                StateProj::Called { future } => {
+                    let result = ready!(future.poll(cx)).map_err(Into::into);
+
+                    let Some(attempt) = this.attempt.as_mut() else {
+                        return Poll::Ready(result);
+                    };
+
+                    match attempt.retry_after(&result) {
+                        Some(backoff) => {
+                            tracing::debug!(
+                                attempts = attempt.attempts,
+                                ?backoff,
+                                "retrying failed request"
+                            );
+                            this.state.set(State::Backoff {
+                                sleep: tokio::time::sleep(backoff),
+                            });
+                        }
+                        None => return Poll::Ready(result),
+                    }
+                }
+                StateProj::Backoff { sleep } => {
+                    ready!(sleep.poll(cx));
+                    this.state.set(State::Ready);
+                }
+                StateProj::Ready => {
+                    let attempt = this
+                        .attempt
+                        .as_mut()
+                        .expect("only retried requests back off");
+
+                    ready!(attempt.service.poll_ready(cx)).map_err(Into::into)?;
+
+                    let request = attempt.next_request();
+                    let future = attempt.service.call(request);
+                    this.state.set(State::Called { future });
+                }
+                StateProj::Rejected { error } => {
+                    return Poll::Ready(Err(error.take().expect("polled after completion")));
+                }
+            }
+        }
+    }
+}
+
+impl<S> Attempt<S> {
+    /// Decides whether `result` should be retried, returning how long to wait if so.
+    fn retry_after<ResBody>(
+        &self,
+        result: &Result<Response<ResBody>, crate::BoxError>,
+    ) -> Option<Duration> {
+        if self.attempts >= self.policy.max_attempts || !self.body.is_replayable() {
+            return None;
+        }
+
+        let (status, pushback) = failed_attempt(result)?;
+
+        if !self.policy.is_retryable(status.code()) {
+            return None;
+        }
+
+        let backoff = match pushback {
+            Pushback::Delay(delay) => delay,
+            Pushback::Stop => return None,
+            Pushback::None => self
+                .policy
+                .server_pushback
+                .as_ref()
+                .and_then(|f| f(&status))
+                .or_else(|| retry_info_delay(&status))
+                .unwrap_or_else(|| self.policy.backoff(self.attempts)),
+        };
+
+        if let Some(deadline) = self.deadline {
+            if Instant::now() + backoff >= deadline {
+                return None;
+            }
+        }
+
+        Some(backoff)
+    }
+
+    fn next_request(&mut self) -> Request<BoxBody> {
+        let request = replay_request(&self.parts, &self.body, self.attempts, self.deadline);
+        self.attempts += 1;
+        request
+    }
+}
+
+/// Returns the absolute deadline of a request with a `grpc-timeout` header.
+pub(super) fn parse_deadline(headers: &HeaderMap) -> Option<Instant> {
+    try_parse_grpc_timeout(headers)
+        .ok()
+        .flatten()
+        .map(|timeout| Instant::now() + timeout)
+}
+
+/// Builds another attempt of a request after `previous_attempts` attempts were made.
+pub(super) fn replay_request(
+    parts: &http::request::Parts,
+    body: &ReplayBody,
+    previous_attempts: usize,
+    deadline: Option<Instant>,
+) -> Request<BoxBody> {
+    let mut parts = parts.clone();
+
+    parts.headers.insert(
+        GRPC_PREVIOUS_RPC_ATTEMPTS,
+        HeaderValue::from(previous_attempts),
+    );
+
+    if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if let Ok(value) = duration_to_grpc_timeout(remaining).parse() {
+            parts.headers.insert(GRPC_TIMEOUT_HEADER, value);
+        }
+    }
+
+    Request::from_parts(parts, BoxBody::new(body.clone()))
+}
+
+/// Returns the status of an attempt that failed before the call was committed.
+///
+/// Only Trailers-Only responses and transport errors count as failed attempts, once the server
+/// sent response headers the call is committed.
+pub(super) fn failed_attempt<ResBody>(
+    result: &Result<Response<ResBody>, crate::BoxError>,
+) -> Option<(Status, Pushback)> {
+    match result {
+        Ok(response) => Some((
+            Status::from_header_map(response.headers())?,
+            parse_pushback(response.headers()),
+        )),
+        Err(err) => Some((
+            crate::status::find_status_in_source_chain(&**err)?,
+            Pushback::None,
+        )),
+    }
+}
+
+pub(super) enum Pushback {
+    None,
+    Delay(Duration),
+    Stop,
+}
+
+/// Parses the `grpc-retry-pushback-ms` header, a missing or malformed value means the server
+/// wants the client to stop retrying.
+fn parse_pushback(headers: &HeaderMap) -> Pushback {
+    let Some(value) = headers.get(GRPC_RETRY_PUSHBACK_MS) else {
+        return Pushback::None;
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|ms| Pushback::Delay(Duration::from_millis(ms)))
+        .unwrap_or(Pushback::Stop)
+}
+
+/// The `retry_delay` of the `google.rpc.RetryInfo` error details of `status`, if any.
+///
+/// The details are a `google.rpc.Status` message whose `details` field holds `google.protobuf.Any`
+/// messages, only the fields needed are decoded.
+fn retry_info_delay(status: &Status) -> Option<Duration> {
+    let retry_info = ProtoFields(status.details()).find_map(|(field, value)| {
+        let (3, WireValue::Bytes(any)) = (field, value) else {
+            return None;
+        };
+
+        let mut type_url = None;
+        let mut value = None;
+        for field in ProtoFields(any) {
+            match field {
+                (1, WireValue::Bytes(bytes)) => type_url = Some(bytes),
+                (2, WireValue::Bytes(bytes)) => value = Some(bytes),
+                _ => {}
+            }
+        }
+        (type_url? == RETRY_INFO_TYPE_URL.as_bytes()).then_some(value.unwrap_or_default())
+    })?;
+
+    let retry_delay = ProtoFields(retry_info).find_map(|field| match field {
+        (1, WireValue::Bytes(duration)) => Some(duration),
+        _ => None,
+    })?;
+
+    let (mut seconds, mut nanos) = (0i64, 0i32);
+    for field in ProtoFields(retry_delay) {
+        match field {
+            (1, WireValue::Varint(value)) => seconds = value as i64,
+            (2, WireValue::Varint(value)) => nanos = value as i32,
+            _ => {}
+        }
+    }
+
+    let seconds = u64::try_from(seconds).ok()?;
+    let nanos = u64::try_from(nanos).ok()?;
+    Duration::from_secs(seconds).checked_add(Duration::from_nanos(nanos))
+}
+
+enum WireValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// The fields of an encoded protobuf message, ending at the first malformed one.
+struct ProtoFields<'a>(&'a [u8]);
+
+impl<'a> ProtoFields<'a> {
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for (i, byte) in self.0.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.0 = &self.0[i + 1..];
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn field(&mut self) -> Option<(u64, WireValue<'a>)> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.varint()?),
+            1 => self.bytes(8).map(|_| WireValue::Fixed)?,
+            2 => {
+                let len = usize::try_from(self.varint()?).ok()?;
+                WireValue::Bytes(self.bytes(len)?)
+            }
+            5 => self.bytes(4).map(|_| WireValue::Fixed)?,
+            _ => return None,
+        };
+        Some((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for ProtoFields<'a> {
+    type Item = (u64, WireValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let field = self.field();
+        if field.is_none() {
+            self.0 = &[];
+        }
+        field
+    }
+}
+
+impl<S> fmt::Debug for ResponseFuture<S>
+where
+    S: Service<Request<BoxBody>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::new(5)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(300))
+            .backoff_multiplier(2.0);
+
+        for attempt in 1..10 {
+            let cap = Duration::from_millis(100 * 2u64.pow(attempt as u32 - 1))
+                .min(Duration::from_millis(300));
+            assert!(policy.backoff(attempt) <= cap);
+        }
+    }
+
+    #[test]
+    fn pushback_header() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(parse_pushback(&headers), Pushback::None));
+
+        headers.insert(GRPC_RETRY_PUSHBACK_MS, HeaderValue::from_static("250"));
+        assert!(matches!(
+            parse_pushback(&headers),
+            Pushback::Delay(d) if d == Duration::from_millis(250)
+        ));
+
+        headers.insert(GRPC_RETRY_PUSHBACK_MS, HeaderValue::from_static("-1"));
+        assert!(matches!(parse_pushback(&headers), Pushback::Stop));
+    }
+
+    #[test]
+    fn retry_info_details() {
+        fn field(number: u8, bytes: &[u8]) -> Vec<u8> {
+            let mut field = vec![number << 3 | 2, bytes.len() as u8];
+            field.extend_from_slice(bytes);
+            field
+        }
+
+        // A `google.protobuf.Duration` of 1.5 seconds.
+        let retry_delay = [0x08, 0x01, 0x10, 0x80, 0xca, 0xb5, 0xee, 0x01];
+        let mut any = field(1, RETRY_INFO_TYPE_URL.as_bytes());
+        any.extend(field(2, &field(1, &retry_delay)));
+        let mut other = field(1, b"type.googleapis.com/google.rpc.DebugInfo");
+        other.extend(field(2, &[]));
+
+        let mut details = vec![0x08, 0x0e];
+        details.extend(field(2, b"try again"));
+        details.extend(field(3, &other));
+        details.extend(field(3, &any));
+
+        let status = Status::with_details(Code::Unavailable, "try again", details.into());
+        assert_eq!(retry_info_delay(&status), Some(Duration::from_millis(1500)));
+
+        let status = Status::with_details(Code::Unavailable, "", field(3, &other).into());
+        assert_eq!(retry_info_delay(&status), None);
+        let status = Status::with_details(Code::Unavailable, "", vec![0x1a, 0x10, 0x0a].into());
+        assert_eq!(retry_info_delay(&status), None);
+    }
+
+    #[test]
+    fn finds_methods_with_retry_and_hedging_policies() {
+        use crate::transport::channel::HedgingPolicy;
+
+        let retry = RetryPolicies::new()
+            .service("test.Test", RetryPolicy::new(3))
+            .method("test.Test", "Hedged", RetryPolicy::new(1));
+        let hedging = HedgingPolicies::new().method("test.Test", "Hedged", HedgingPolicy::new(2));
+        assert_eq!(retry.conflicting_method(&hedging), None);
+
+        let hedging = hedging.service("other.Other", HedgingPolicy::new(2));
+        assert_eq!(retry.conflicting_method(&hedging), None);
+
+        let hedging = hedging.method("test.Test", "Retried", HedgingPolicy::new(2));
+        assert_eq!(
+            retry.conflicting_method(&hedging).as_deref(),
+            Some("/test.Test/Retried")
+        );
+
+        // The default hedging policy applies to the other methods of the retried service.
+        let hedging = HedgingPolicies::new().default_policy(HedgingPolicy::new(2));
+        assert_eq!(
+            retry.conflicting_method(&hedging).as_deref(),
+            Some("/test.Test/")
+        );
+    }
+}
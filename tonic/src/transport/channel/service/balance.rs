@@ -0,0 +1,380 @@
+use super::Connection;
+use crate::{
+    body::BoxBody,
+    transport::channel::{connectivity::Subchannel, BoxFuture, LoadBalancingPolicy},
+    util::fast_random,
+    Status,
+};
+use http::{HeaderName, Request, Response};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tower::{
+    discover::{Change, Discover},
+    ready_cache::{error::Failed, ReadyCache},
+};
+use tower_service::Service;
+use tracing::{debug, trace};
+
+// The number of points every endpoint gets on the hash ring.
+const RING_POINTS_PER_ENDPOINT: usize = 100;
+
+/// Balances requests over the discovered connections with a policy other than
+/// power-of-two-choices, which is left to `tower::balance::p2c`.
+pub(crate) struct Balance<D>
+where
+    D: Discover,
+    D::Key: Hash,
+{
+    discover: D,
+    services: ReadyCache<D::Key, Connection, Request<BoxBody>>,
+    /// The discovered keys in the order they were added.
+    keys: Vec<D::Key>,
+    /// The connectivity of every discovered connection, ready or not.
+    subchannels: HashMap<D::Key, Subchannel>,
+    picker: Picker<D::Key>,
+    /// The key picked by `poll_ready`, when the pick does not depend on the request.
+    ready_key: Option<D::Key>,
+}
+
+enum Picker<K> {
+    RoundRobin {
+        next: usize,
+    },
+    PickFirst,
+    WeightedRoundRobin {
+        current: HashMap<K, i64>,
+    },
+    RingHash {
+        header: HeaderName,
+        /// Points on the ring sorted by hash, rebuilt when the keys change.
+        ring: Vec<(u64, K)>,
+    },
+}
+
+impl<D> Balance<D>
+where
+    D: Discover<Service = Connection> + Unpin,
+    D::Key: Hash + Clone,
+    D::Error: Into<crate::BoxError>,
+{
+    pub(crate) fn new(discover: D, policy: LoadBalancingPolicy) -> Self {
+        let picker = match policy {
+            LoadBalancingPolicy::RoundRobin => Picker::RoundRobin { next: 0 },
+            LoadBalancingPolicy::PickFirst => Picker::PickFirst,
+            LoadBalancingPolicy::WeightedRoundRobin => Picker::WeightedRoundRobin {
+                current: HashMap::new(),
+            },
+            LoadBalancingPolicy::RingHash(header) => Picker::RingHash {
+                header,
+                ring: Vec::new(),
+            },
+            LoadBalancingPolicy::PowerOfTwoChoices => {
+                unreachable!("power-of-two-choices is handled by tower")
+            }
+        };
+
+        Self {
+            discover,
+            services: ReadyCache::default(),
+            keys: Vec::new(),
+            subchannels: HashMap::new(),
+            picker,
+            ready_key: None,
+        }
+    }
+
+    fn update_from_discover(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), crate::BoxError>> {
+        loop {
+            match ready!(Pin::new(&mut self.discover).poll_discover(cx)) {
+                None => return Poll::Ready(Ok(())),
+                Some(Err(error)) => return Poll::Ready(Err(error.into())),
+                Some(Ok(Change::Remove(key))) => {
+                    trace!("remove");
+                    self.services.evict(&key);
+                    self.remove_key(&key);
+                }
+                Some(Ok(Change::Insert(key, svc))) => {
+                    trace!("insert");
+                    // A replaced service keeps its position.
+                    if !self.keys.contains(&key) {
+                        self.keys.push(key.clone());
+                        self.picker.keys_changed(&self.keys);
+                    }
+                    self.subchannels
+                        .insert(key.clone(), svc.subchannel().clone());
+                    self.services.push(key, svc);
+                }
+            }
+        }
+    }
+
+    fn promote_pending_to_ready(&mut self, cx: &mut Context<'_>) {
+        loop {
+            match self.services.poll_pending(cx) {
+                Poll::Ready(Ok(())) | Poll::Pending => break,
+                Poll::Ready(Err(Failed(key, error))) => {
+                    debug!(%error, "dropping failed endpoint");
+                    self.remove_key(&key);
+                }
+            }
+        }
+    }
+
+    fn remove_key(&mut self, key: &D::Key) {
+        self.subchannels.remove(key);
+        if let Some(index) = self.keys.iter().position(|k| k == key) {
+            self.keys.remove(index);
+            self.picker.keys_changed(&self.keys);
+        }
+    }
+
+    /// Picks a ready service for a request that is not known yet.
+    fn pick(&mut self) -> Option<D::Key> {
+        let services = &self.services;
+        let is_ready = |key: &D::Key| services.get_ready(key).is_some();
+        let has_failed = |key: &D::Key| {
+            self.subchannels
+                .get(key)
+                .is_some_and(Subchannel::has_failed)
+        };
+
+        match &mut self.picker {
+            Picker::RoundRobin { next } => {
+                let len = self.keys.len();
+                let index = (0..len)
+                    .map(|offset| (*next + offset) % len)
+                    .find(|&index| is_ready(&self.keys[index]))?;
+                *next = index + 1;
+
+                Some(self.keys[index].clone())
+            }
+            Picker::PickFirst => {
+                // Wait for the first endpoint that has not failed to connect instead of skipping
+                // to the next one while it connects. Once all have failed, the first one gets to
+                // report its error.
+                let key = self
+                    .keys
+                    .iter()
+                    .find(|key| !has_failed(key))
+                    .or(self.keys.first())?;
+
+                is_ready(key).then(|| key.clone())
+            }
+            Picker::WeightedRoundRobin { current } => {
+                // Smooth weighted round-robin: every ready endpoint gains its weight, the one with
+                // the highest total is picked and gives back the sum of all weights.
+                let mut total = 0;
+                let mut picked: Option<(&D::Key, i64)> = None;
+                for key in &self.keys {
+                    let Some((_, _, svc)) = services.get_ready(key) else {
+                        continue;
+                    };
+                    let weight = i64::from(svc.weight());
+                    let value = current.entry(key.clone()).or_insert(0);
+                    *value += weight;
+                    total += weight;
+
+                    if !matches!(picked, Some((_, max)) if max >= *value) {
+                        picked = Some((key, *value));
+                    }
+                }
+
+                let (key, _) = picked?;
+                *current.get_mut(key).expect("picked key has a weight") -= total;
+
+                Some(key.clone())
+            }
+            Picker::RingHash { .. } => None,
+        }
+    }
+
+    /// Picks the ready service on the hash ring for the request.
+    fn pick_on_ring(&self, request: &Request<BoxBody>) -> Option<D::Key> {
+        let Picker::RingHash { header, ring } = &self.picker else {
+            return None;
+        };
+
+        let hash = match request.headers().get(header) {
+            Some(value) => hash(value.as_bytes()),
+            None => (fast_random() * u64::MAX as f64) as u64,
+        };
+        let start = ring.partition_point(|(point, _)| *point < hash);
+
+        ring[start..]
+            .iter()
+            .chain(&ring[..start])
+            .map(|(_, key)| key)
+            .find(|key| self.services.get_ready(*key).is_some())
+            .cloned()
+    }
+}
+
+impl<K: Hash + Eq + Clone> Picker<K> {
+    fn keys_changed(&mut self, keys: &[K]) {
+        match self {
+            Picker::RoundRobin { .. } | Picker::PickFirst => {}
+            Picker::WeightedRoundRobin { current } => current.retain(|key, _| keys.contains(key)),
+            Picker::RingHash { ring, .. } => {
+                *ring = keys
+                    .iter()
+                    .flat_map(|key| {
+                        (0..RING_POINTS_PER_ENDPOINT).map(move |point| (hash(&(key, point)), key))
+                    })
+                    .map(|(hash, key)| (hash, key.clone()))
+                    .collect();
+                ring.sort_unstable_by_key(|(hash, _)| *hash);
+            }
+        }
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = RingHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// FNV-1a followed by the 64-bit finalizer of MurmurHash3, so that similar inputs are spread
+/// over the whole ring.
+///
+/// The hash is fixed rather than randomly seeded or left to the standard library, so every client
+/// builds the same ring and sends a header value to the same endpoint, whatever its Rust version.
+struct RingHasher(u64);
+
+impl Default for RingHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for RingHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // Integers are hashed as little-endian bytes to agree across platforms.
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^= h >> 33;
+        h
+    }
+}
+
+impl<D> Service<Request<BoxBody>> for Balance<D>
+where
+    D: Discover<Service = Connection> + Unpin,
+    D::Key: Hash + Clone,
+    D::Error: Into<crate::BoxError>,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Poll::Ready(Err(error)) = self.update_from_discover(cx) {
+            return Poll::Ready(Err(error));
+        }
+        self.promote_pending_to_ready(cx);
+
+        loop {
+            // Make sure the picked service is still ready right before it is called.
+            if let Some(key) = self.ready_key.take() {
+                match self.services.check_ready(cx, &key) {
+                    Ok(true) => {
+                        self.ready_key = Some(key);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(false) => trace!("ready service became unavailable"),
+                    Err(Failed(key, error)) => {
+                        debug!(%error, "endpoint failed");
+                        self.remove_key(&key);
+                    }
+                }
+            }
+
+            if self.services.ready_len() == 0 {
+                return Poll::Pending;
+            }
+
+            // The ring is only consulted once the request is known, so the readiness of the
+            // picked service is only checked by the time it is called.
+            if let Picker::RingHash { .. } = self.picker {
+                return Poll::Ready(Ok(()));
+            }
+
+            self.ready_key = self.pick();
+            if self.ready_key.is_none() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    fn call(&mut self, request: Request<BoxBody>) -> Self::Future {
+        let key = match self
+            .ready_key
+            .take()
+            .or_else(|| self.pick_on_ring(&request))
+        {
+            Some(key) => key,
+            None => {
+                let error = Status::unavailable("no ready endpoint");
+                return Box::pin(async move { Err(error.into()) });
+            }
+        };
+
+        self.services.call_ready(&key, request)
+    }
+}
+
+impl<D> fmt::Debug for Balance<D>
+where
+    D: Discover,
+    D::Key: Hash,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Balance").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_hash_is_stable() {
+        assert_eq!(hash(b"user-1".as_slice()), 15145928409090621350);
+        assert_eq!(hash(&("10.0.0.1:50051", 7usize)), 4005838086943749579);
+    }
+}
@@ -1,7 +1,15 @@
 use super::{AddOrigin, Reconnect, SharedExec, UserAgent};
 use crate::{
     body::{boxed, BoxBody},
-    transport::{channel::BoxFuture, service::GrpcTimeout, Endpoint},
+    transport::{
+        channel::{
+            backoff::Backoff,
+            connectivity::{Connectivity, ConnectivityState, Subchannel},
+            BoxFuture,
+        },
+        service::{ActiveBody, GrpcTimeout, IdleTimeout},
+        Endpoint,
+    },
 };
 use http::{Request, Response, Uri};
 use hyper::rt;
@@ -9,7 +17,11 @@ use hyper::{client::conn::http2::Builder, rt::Executor};
 use hyper_util::rt::TokioTimer;
 use std::{
     fmt,
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 use tower::load::Load;
 use tower::{
@@ -22,10 +34,14 @@ use tower_service::Service;
 
 pub(crate) struct Connection {
     inner: BoxService<Request<BoxBody>, Response<BoxBody>, crate::BoxError>,
+    /// Every pending response future holds a clone, used as the load of this connection.
+    pending: Arc<()>,
+    weight: u32,
+    subchannel: Subchannel,
 }
 
 impl Connection {
-    fn new<C>(connector: C, endpoint: Endpoint, is_lazy: bool) -> Self
+    fn new<C>(connector: C, endpoint: Endpoint, is_lazy: bool, connectivity: &Connectivity) -> Self
     where
         C: Service<Uri> + Send + 'static,
         C::Error: Into<crate::BoxError> + Send,
@@ -62,24 +78,43 @@ impl Connection {
                 AddOrigin::new(s, origin)
             })
             .layer_fn(|s| UserAgent::new(s, endpoint.user_agent.clone()))
+            .map_response(|response: Response<_>| response.map(boxed))
             .layer_fn(|s| GrpcTimeout::new(s, endpoint.timeout))
             .option_layer(endpoint.concurrency_limit.map(ConcurrencyLimitLayer::new))
             .option_layer(endpoint.rate_limit.map(|(l, d)| RateLimitLayer::new(l, d)))
             .into_inner();
 
-        let make_service =
-            MakeSendRequestService::new(connector, endpoint.executor.clone(), settings);
+        let subchannel = connectivity.subchannel();
 
-        let conn = Reconnect::new(make_service, endpoint.uri.clone(), is_lazy);
+        let make_service = MakeSendRequestService::new(
+            connector,
+            endpoint.executor.clone(),
+            settings,
+            endpoint.idle_timeout,
+            subchannel.clone(),
+        );
+
+        let conn = Reconnect::new(
+            make_service,
+            endpoint.uri.clone(),
+            is_lazy,
+            endpoint.resolve_now.clone(),
+            subchannel.clone(),
+            endpoint.reconnect_backoff.clone().map(Backoff::new),
+        );
 
         Self {
             inner: BoxService::new(stack.layer(conn)),
+            pending: Arc::new(()),
+            weight: endpoint.weight,
+            subchannel,
         }
     }
 
     pub(crate) async fn connect<C>(
         connector: C,
         endpoint: Endpoint,
+        connectivity: &Connectivity,
     ) -> Result<Self, crate::BoxError>
     where
         C: Service<Uri> + Send + 'static,
@@ -87,17 +122,29 @@ impl Connection {
         C::Future: Unpin + Send,
         C::Response: rt::Read + rt::Write + Unpin + Send + 'static,
     {
-        Self::new(connector, endpoint, false).ready_oneshot().await
+        Self::new(connector, endpoint, false, connectivity)
+            .ready_oneshot()
+            .await
+    }
+
+    /// The weight of this connection under weighted load balancing.
+    pub(crate) fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// The connectivity of this connection.
+    pub(crate) fn subchannel(&self) -> &Subchannel {
+        &self.subchannel
     }
 
-    pub(crate) fn lazy<C>(connector: C, endpoint: Endpoint) -> Self
+    pub(crate) fn lazy<C>(connector: C, endpoint: Endpoint, connectivity: &Connectivity) -> Self
     where
         C: Service<Uri> + Send + 'static,
         C::Error: Into<crate::BoxError> + Send,
         C::Future: Send,
         C::Response: rt::Read + rt::Write + Unpin + Send + 'static,
     {
-        Self::new(connector, endpoint, true)
+        Self::new(connector, endpoint, true, connectivity)
     }
 }
 
@@ -111,7 +158,13 @@ impl Service<Request<BoxBody>> for Connection {
     }
 
     fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
-        self.inner.call(req)
+        let pending = self.pending.clone();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let _pending = pending;
+            future.await
+        })
     }
 }
 
@@ -119,7 +172,7 @@ impl Load for Connection {
     type Metric = usize;
 
     fn load(&self) -> Self::Metric {
-        0
+        Arc::strong_count(&self.pending) - 1
     }
 }
 
@@ -131,12 +184,7 @@ impl fmt::Debug for Connection {
 
 struct SendRequest {
     inner: hyper::client::conn::http2::SendRequest<BoxBody>,
-}
-
-impl From<hyper::client::conn::http2::SendRequest<BoxBody>> for SendRequest {
-    fn from(inner: hyper::client::conn::http2::SendRequest<BoxBody>) -> Self {
-        Self { inner }
-    }
+    idle_timeout: Option<IdleTimeout>,
 }
 
 impl tower::Service<Request<BoxBody>> for SendRequest {
@@ -145,13 +193,28 @@ impl tower::Service<Request<BoxBody>> for SendRequest {
     type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self
+            .idle_timeout
+            .as_ref()
+            .is_some_and(IdleTimeout::is_expired)
+        {
+            return Poll::Ready(Err("connection closed after being idle".into()));
+        }
+
         self.inner.poll_ready(cx).map_err(Into::into)
     }
 
     fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
+        let active = self.idle_timeout.as_ref().map(IdleTimeout::start);
         let fut = self.inner.send_request(req);
 
-        Box::pin(async move { fut.await.map_err(Into::into).map(|res| res.map(boxed)) })
+        Box::pin(async move {
+            let res = fut.await.map_err(crate::BoxError::from)?;
+            Ok(match active {
+                Some(active) => res.map(|body| boxed(ActiveBody::new(body, active))),
+                None => res.map(boxed),
+            })
+        })
     }
 }
 
@@ -159,14 +222,24 @@ struct MakeSendRequestService<C> {
     connector: C,
     executor: SharedExec,
     settings: Builder<SharedExec>,
+    idle_timeout: Option<Duration>,
+    subchannel: Subchannel,
 }
 
 impl<C> MakeSendRequestService<C> {
-    fn new(connector: C, executor: SharedExec, settings: Builder<SharedExec>) -> Self {
+    fn new(
+        connector: C,
+        executor: SharedExec,
+        settings: Builder<SharedExec>,
+        idle_timeout: Option<Duration>,
+        subchannel: Subchannel,
+    ) -> Self {
         Self {
             connector,
             executor,
             settings,
+            idle_timeout,
+            subchannel,
         }
     }
 }
@@ -190,21 +263,46 @@ where
         let fut = self.connector.call(req);
         let builder = self.settings.clone();
         let executor = self.executor.clone();
+        let idle_timeout = self.idle_timeout.map(IdleTimeout::new);
+        let subchannel = self.subchannel.clone();
 
         Box::pin(async move {
             let io = fut.await.map_err(Into::into)?;
             let (send_request, conn) = builder.handshake(io).await?;
 
-            Executor::<BoxFuture<'static, ()>>::execute(
-                &executor,
-                Box::pin(async move {
-                    if let Err(e) = conn.await {
-                        tracing::debug!("connection task error: {:?}", e);
+            let conn = async move {
+                if let Err(e) = conn.await {
+                    tracing::debug!("connection task error: {:?}", e);
+                }
+            };
+
+            let task: BoxFuture<'static, ()> = match idle_timeout.clone() {
+                Some(idle_timeout) => Box::pin(async move {
+                    let mut conn = pin!(conn);
+                    let mut expired = pin!(idle_timeout.expired());
+
+                    // Dropping the connection future closes the connection once it is idle.
+                    let expired = poll_fn(|cx| {
+                        if conn.as_mut().poll(cx).is_ready() {
+                            return Poll::Ready(false);
+                        }
+                        expired.as_mut().poll(cx).map(|()| true)
+                    })
+                    .await;
+
+                    if expired {
+                        tracing::debug!("closing idle connection");
+                        subchannel.set(ConnectivityState::Idle);
                     }
-                }) as _,
-            );
-
-            Ok(SendRequest::from(send_request))
+                }),
+                None => Box::pin(conn),
+            };
+            Executor::<BoxFuture<'static, ()>>::execute(&executor, task);
+
+            Ok(SendRequest {
+                inner: send_request,
+                idle_timeout,
+            })
         })
     }
 }
@@ -1,12 +1,25 @@
 use super::super::{Connection, Endpoint};
+use crate::transport::channel::connectivity::Connectivity;
 
+#[cfg(unix)]
+use crate::transport::channel::BoxFuture;
+#[cfg(unix)]
+use http::Uri;
 use hyper_util::client::legacy::connect::HttpConnector;
+#[cfg(unix)]
+use hyper_util::rt::TokioIo;
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::{
     hash::Hash,
     pin::Pin,
     task::{Context, Poll},
 };
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::sync::mpsc::Receiver;
+#[cfg(unix)]
+use tower_service::Service;
 
 use tokio_stream::Stream;
 use tower::discover::Change;
@@ -15,11 +28,15 @@ type DiscoverResult<K, S, E> = Result<Change<K, S>, E>;
 
 pub(crate) struct DynamicServiceStream<K: Hash + Eq + Clone> {
     changes: Receiver<Change<K, Endpoint>>,
+    connectivity: Connectivity,
 }
 
 impl<K: Hash + Eq + Clone> DynamicServiceStream<K> {
-    pub(crate) fn new(changes: Receiver<Change<K, Endpoint>>) -> Self {
-        Self { changes }
+    pub(crate) fn new(changes: Receiver<Change<K, Endpoint>>, connectivity: Connectivity) -> Self {
+        Self {
+            changes,
+            connectivity,
+        }
     }
 }
 
@@ -27,18 +44,34 @@ impl<K: Hash + Eq + Clone> Stream for DynamicServiceStream<K> {
     type Item = DiscoverResult<K, Connection, crate::BoxError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let c = &mut self.changes;
+        let this = &mut *self;
+        let c = &mut this.changes;
         match Pin::new(&mut *c).poll_recv(cx) {
             Poll::Pending | Poll::Ready(None) => Poll::Pending,
             Poll::Ready(Some(change)) => match change {
                 Change::Insert(k, endpoint) => {
+                    #[cfg(unix)]
+                    if let Some(path) = endpoint.unix_path.clone() {
+                        let connector = endpoint.connector(UnixConnector { path });
+                        let connection = match endpoint.connect_timeout {
+                            Some(connect_timeout) => {
+                                let mut connector = hyper_timeout::TimeoutConnector::new(connector);
+                                connector.set_connect_timeout(Some(connect_timeout));
+                                Connection::lazy(connector, endpoint, &this.connectivity)
+                            }
+                            None => Connection::lazy(connector, endpoint, &this.connectivity),
+                        };
+                        return Poll::Ready(Some(Ok(Change::Insert(k, connection))));
+                    }
+
                     let mut http = HttpConnector::new();
                     http.set_nodelay(endpoint.tcp_nodelay);
                     http.set_keepalive(endpoint.tcp_keepalive);
                     http.set_connect_timeout(endpoint.connect_timeout);
                     http.enforce_http(false);
 
-                    let connection = Connection::lazy(endpoint.connector(http), endpoint);
+                    let connection =
+                        Connection::lazy(endpoint.connector(http), endpoint, &this.connectivity);
                     let change = Ok(Change::Insert(k, connection));
                     Poll::Ready(Some(change))
                 }
@@ -49,3 +82,26 @@ impl<K: Hash + Eq + Clone> Stream for DynamicServiceStream<K> {
 }
 
 impl<K: Hash + Eq + Clone> Unpin for DynamicServiceStream<K> {}
+
+/// Connects to the Unix domain socket of a resolved `unix` target.
+#[cfg(unix)]
+#[derive(Clone)]
+struct UnixConnector {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Service<Uri> for UnixConnector {
+    type Response = TokioIo<UnixStream>;
+    type Error = std::io::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move { UnixStream::connect(path).await.map(TokioIo::new) })
+    }
+}
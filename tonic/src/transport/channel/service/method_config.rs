@@ -0,0 +1,105 @@
+use super::{MethodMap, WaitForReadyOption};
+use crate::{
+    body::BoxBody,
+    codec::CallMessageLimits,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::{duration_to_grpc_timeout, try_parse_grpc_timeout},
+    transport::channel::MethodConfig,
+};
+use http::{Request, Response};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_service::Service;
+
+/// Applies the timeouts, message size limits and wait-for-ready options of a service config to
+/// each call.
+#[derive(Debug, Clone)]
+pub(crate) struct ApplyMethodConfig<S> {
+    inner: S,
+    configs: Arc<MethodMap<MethodConfig>>,
+}
+
+impl<S> ApplyMethodConfig<S> {
+    pub(crate) fn new(inner: S, configs: MethodMap<MethodConfig>) -> Self {
+        Self {
+            inner,
+            configs: Arc::new(configs),
+        }
+    }
+}
+
+impl<S> Service<Request<BoxBody>> for ApplyMethodConfig<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>>,
+    S::Error: Into<crate::BoxError>,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: Request<BoxBody>) -> Self::Future {
+        let Some(config) = self.configs.get(request.uri().path()) else {
+            return ResponseFuture {
+                inner: self.inner.call(request),
+            };
+        };
+
+        if let Some(timeout) = config.get_timeout() {
+            // The service config timeout is an upper bound, keep a shorter one set by the caller.
+            let timeout = match try_parse_grpc_timeout(request.headers()) {
+                Ok(Some(existing)) => existing.min(timeout),
+                _ => timeout,
+            };
+
+            if let Ok(value) = duration_to_grpc_timeout(timeout).parse() {
+                request.headers_mut().insert(GRPC_TIMEOUT_HEADER, value);
+            }
+        }
+
+        if let Some(wait_for_ready) = config.get_wait_for_ready() {
+            request
+                .extensions_mut()
+                .insert(WaitForReadyOption(wait_for_ready));
+        }
+
+        // The client applies the message size limits while encoding and decoding the messages.
+        if let Some(limits) = request.extensions().get::<CallMessageLimits>() {
+            limits.restrict(
+                config.get_max_request_message_bytes(),
+                config.get_max_response_message_bytes(),
+            );
+        }
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+        }
+    }
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+}
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<crate::BoxError>,
+{
+    type Output = Result<T, crate::BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx).map_err(Into::into)
+    }
+}
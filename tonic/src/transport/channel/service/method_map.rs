@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+/// A table of per-method settings, looked up by the request path.
+///
+/// Entries follow the name matching rules of the gRPC service config: a setting for a specific
+/// method takes precedence over a setting for its whole service, which takes precedence over the
+/// channel wide default.
+#[derive(Debug, Clone)]
+pub(crate) struct MethodMap<T> {
+    default: Option<T>,
+    services: HashMap<String, T>,
+    methods: HashMap<String, T>,
+}
+
+impl<T> MethodMap<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            default: None,
+            services: HashMap::new(),
+            methods: HashMap::new(),
+        }
+    }
+
+    /// Sets the value used for every method that has no more specific entry.
+    pub(crate) fn set_default(&mut self, value: T) {
+        self.default = Some(value);
+    }
+
+    /// Sets the value for every method of `service`, e.g. `helloworld.Greeter`.
+    pub(crate) fn set_service(&mut self, service: impl Into<String>, value: T) {
+        self.services.insert(service.into(), value);
+    }
+
+    /// Sets the value for a single method of `service`.
+    pub(crate) fn set_method(&mut self, service: &str, method: &str, value: T) {
+        self.methods.insert(format!("{service}/{method}"), value);
+    }
+
+    /// Finds the value for a request path of the form `/package.Service/Method`.
+    pub(crate) fn get(&self, path: &str) -> Option<&T> {
+        let name = path.strip_prefix('/').unwrap_or(path);
+
+        if let Some(value) = self.methods.get(name) {
+            return Some(value);
+        }
+
+        name.rsplit_once('/')
+            .and_then(|(service, _)| self.services.get(service))
+            .or(self.default.as_ref())
+    }
+
+    /// A request path for each entry, matching it unless a more specific entry exists.
+    ///
+    /// Looking up these paths, in every map they are combined with, covers all the methods.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = String> + '_ {
+        let default = self.default.as_ref().map(|_| "/".to_string());
+        let services = self.services.keys().map(|service| format!("/{service}/"));
+        let methods = self.methods.keys().map(|method| format!("/{method}"));
+
+        default.into_iter().chain(services).chain(methods)
+    }
+
+    /// Converts every entry, keeping the names they are matched by.
+    pub(crate) fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> MethodMap<U> {
+        MethodMap {
+            default: self.default.as_ref().map(&mut f),
+            services: self
+                .services
+                .iter()
+                .map(|(name, value)| (name.clone(), f(value)))
+                .collect(),
+            methods: self
+                .methods
+                .iter()
+                .map(|(name, value)| (name.clone(), f(value)))
+                .collect(),
+        }
+    }
+}
+
+impl<T> Default for MethodMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_entry_wins() {
+        let mut map = MethodMap::new();
+        map.set_default(0);
+        map.set_service("test.Test", 1);
+        map.set_method("test.Test", "UnaryCall", 2);
+
+        assert_eq!(map.get("/test.Test/UnaryCall"), Some(&2));
+        assert_eq!(map.get("/test.Test/StreamCall"), Some(&1));
+        assert_eq!(map.get("/other.Other/UnaryCall"), Some(&0));
+    }
+
+    #[test]
+    fn paths_match_their_entries() {
+        let mut map = MethodMap::new();
+        map.set_default(0);
+        map.set_service("test.Test", 1);
+        map.set_method("test.Test", "UnaryCall", 2);
+
+        let mut values = map
+            .paths()
+            .map(|path| *map.get(&path).unwrap())
+            .collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, [0, 1, 2]);
+    }
+
+    #[test]
+    fn empty_map_matches_nothing() {
+        let map = MethodMap::<()>::new();
+
+        assert_eq!(map.get("/test.Test/UnaryCall"), None);
+    }
+}
@@ -7,6 +7,9 @@ use self::user_agent::UserAgent;
 mod reconnect;
 use self::reconnect::Reconnect;
 
+mod balance;
+pub(super) use self::balance::Balance;
+
 mod connection;
 pub(super) use self::connection::Connection;
 
@@ -16,6 +19,19 @@ pub(super) use self::discover::DynamicServiceStream;
 mod io;
 use self::io::BoxedIo;
 
+mod method_config;
+pub(super) use self::method_config::ApplyMethodConfig;
+
+mod wait_for_ready;
+pub(super) use self::wait_for_ready::WaitForReady;
+pub(crate) use self::wait_for_ready::WaitForReadyOption;
+
+mod method_map;
+pub(super) use self::method_map::MethodMap;
+
+mod replay;
+pub(super) use self::replay::ReplayBody;
+
 mod connector;
 pub(crate) use self::connector::Connector;
 
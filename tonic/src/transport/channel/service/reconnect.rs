@@ -1,10 +1,19 @@
+use crate::{
+    transport::channel::{
+        backoff::Backoff,
+        connectivity::{ConnectivityState, Subchannel},
+        resolver::ResolveNow,
+    },
+    ConnectError,
+};
 use pin_project::pin_project;
-use std::fmt;
+use std::{error::Error, fmt};
 use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
 };
+use tokio::time::{Instant, Sleep};
 use tower::make::MakeService;
 use tower_service::Service;
 use tracing::trace;
@@ -20,6 +29,13 @@ where
     error: Option<crate::BoxError>,
     has_been_connected: bool,
     is_lazy: bool,
+    resolve_now: Option<ResolveNow>,
+    subchannel: Subchannel,
+    backoff: Option<Backoff>,
+    /// Bounds the current attempt to connect, when backing off.
+    connect_timeout: Option<Pin<Box<Sleep>>>,
+    /// When to attempt to connect again after a failed attempt, when backing off.
+    retry_at: Option<Instant>,
 }
 
 #[derive(Debug)]
@@ -34,7 +50,14 @@ where
     M: Service<Target>,
     M::Error: Into<crate::BoxError>,
 {
-    pub(crate) fn new(mk_service: M, target: Target, is_lazy: bool) -> Self {
+    pub(crate) fn new(
+        mk_service: M,
+        target: Target,
+        is_lazy: bool,
+        resolve_now: Option<ResolveNow>,
+        subchannel: Subchannel,
+        backoff: Option<Backoff>,
+    ) -> Self {
         Reconnect {
             mk_service,
             state: State::Idle,
@@ -42,6 +65,18 @@ where
             error: None,
             has_been_connected: false,
             is_lazy,
+            resolve_now,
+            subchannel,
+            backoff,
+            connect_timeout: None,
+            retry_at: None,
+        }
+    }
+
+    /// Asks the resolver of a balanced channel for fresh addresses.
+    fn connection_failed(&self) {
+        if let Some(resolve_now) = &self.resolve_now {
+            resolve_now.notify();
         }
     }
 }
@@ -70,6 +105,19 @@ where
             match self.state {
                 State::Idle => {
                     trace!("poll_ready; idle");
+
+                    if let Some(retry_at) = self.retry_at {
+                        if Instant::now() < retry_at {
+                            trace!("poll_ready; backing off");
+                            self.error = Some(
+                                ConnectError("waiting to reconnect after a failed attempt".into())
+                                    .into(),
+                            );
+                            return Poll::Ready(Ok(()));
+                        }
+                        self.retry_at = None;
+                    }
+
                     match self.mk_service.poll_ready(cx) {
                         Poll::Ready(r) => r?,
                         Poll::Pending => {
@@ -80,27 +128,53 @@ where
 
                     let fut = self.mk_service.make_service(self.target.clone());
                     self.state = State::Connecting(fut);
+                    self.connect_timeout = self
+                        .backoff
+                        .as_ref()
+                        .map(|backoff| Box::pin(tokio::time::sleep(backoff.connect_timeout())));
+                    self.subchannel.set(ConnectivityState::Connecting);
                     continue;
                 }
                 State::Connecting(ref mut f) => {
                     trace!("poll_ready; connecting");
-                    match Pin::new(f).poll(cx) {
-                        Poll::Ready(Ok(service)) => {
-                            state = State::Connected(service);
-                        }
+                    let result = match Pin::new(f).poll(cx) {
+                        Poll::Ready(result) => result.map_err(Into::into),
                         Poll::Pending => {
-                            trace!("poll_ready; not ready");
-                            return Poll::Pending;
+                            let timed_out = self
+                                .connect_timeout
+                                .as_mut()
+                                .is_some_and(|timeout| timeout.as_mut().poll(cx).is_ready());
+                            if !timed_out {
+                                trace!("poll_ready; not ready");
+                                return Poll::Pending;
+                            }
+                            Err(ConnectError("connection attempt timed out".into()).into())
                         }
-                        Poll::Ready(Err(e)) => {
+                    };
+                    self.connect_timeout = None;
+
+                    match result {
+                        Ok(service) => {
+                            state = State::Connected(service);
+                            self.subchannel.set(ConnectivityState::Ready);
+                            if let Some(backoff) = &mut self.backoff {
+                                backoff.reset();
+                            }
+                        }
+                        Err(e) => {
                             trace!("poll_ready; error");
 
                             state = State::Idle;
+                            self.subchannel.set(ConnectivityState::TransientFailure);
+                            self.connection_failed();
+                            if let Some(backoff) = &mut self.backoff {
+                                self.retry_at = Some(Instant::now() + backoff.next_delay());
+                            }
 
                             if !(self.has_been_connected || self.is_lazy) {
-                                return Poll::Ready(Err(e.into()));
+                                return Poll::Ready(Err(e));
                             } else {
-                                let error = e.into();
+                                let error = e;
                                 tracing::debug!("reconnect::poll_ready: {:?}", error);
                                 self.error = Some(error);
                                 break;
@@ -125,6 +199,8 @@ where
                         Poll::Ready(Err(_)) => {
                             trace!("poll_ready; error");
                             state = State::Idle;
+                            self.subchannel.set(ConnectivityState::Idle);
+                            self.connection_failed();
                         }
                     }
                 }
@@ -141,7 +217,10 @@ where
         tracing::trace!("Reconnect::call");
         if let Some(error) = self.error.take() {
             tracing::debug!("error: {}", error);
-            return ResponseFuture::error(error);
+            return ResponseFuture::error(Box::new(NotConnected {
+                error,
+                retry_at: self.retry_at,
+            }));
         }
 
         let State::Connected(service) = &mut self.state else {
@@ -170,6 +249,44 @@ where
     }
 }
 
+/// The error of a call that was never sent because connecting failed.
+#[derive(Debug)]
+pub(crate) struct NotConnected {
+    error: crate::BoxError,
+    retry_at: Option<Instant>,
+}
+
+impl NotConnected {
+    /// Returns the `NotConnected` error among `error` and its sources, if any.
+    pub(crate) fn find<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a NotConnected> {
+        let mut source = Some(error);
+        while let Some(error) = source {
+            if let Some(not_connected) = error.downcast_ref::<NotConnected>() {
+                return Some(not_connected);
+            }
+            source = error.source();
+        }
+        None
+    }
+
+    /// When the connection attempts to connect again, if it backs off.
+    pub(crate) fn retry_at(&self) -> Option<Instant> {
+        self.retry_at
+    }
+}
+
+impl fmt::Display for NotConnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl Error for NotConnected {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
 /// Future that resolves to the response or failure to connect.
 #[pin_project]
 #[derive(Debug)]
@@ -218,3 +335,141 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::channel::{backoff::ReconnectBackoff, connectivity::Connectivity};
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tower::ServiceExt;
+
+    type Connected = tower::util::ServiceFn<fn(()) -> std::future::Ready<io::Result<()>>>;
+    type Connecting = Pin<Box<dyn Future<Output = io::Result<Connected>> + Send>>;
+
+    fn connected() -> Connected {
+        fn respond(_: ()) -> std::future::Ready<io::Result<()>> {
+            std::future::ready(Ok(()))
+        }
+        tower::service_fn(respond)
+    }
+
+    fn reconnect(
+        connect: impl Fn() -> Connecting + Send + 'static,
+        backoff: ReconnectBackoff,
+    ) -> Reconnect<impl Service<(), Response = Connected, Error = io::Error, Future = Connecting>, ()>
+    {
+        let mk_service = tower::service_fn(move |()| connect());
+        let subchannel = Connectivity::new().subchannel();
+        Reconnect::new(
+            mk_service,
+            (),
+            true,
+            None,
+            subchannel,
+            Some(Backoff::new(backoff)),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_between_attempts() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let mut reconnect = reconnect(
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Err(io::Error::from(io::ErrorKind::ConnectionRefused)) })
+            },
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(1))
+                .multiplier(2.0)
+                .jitter(0.0),
+        );
+
+        reconnect.ready().await.unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // Backing off, the call fails without another attempt.
+        let err = reconnect.call(()).await.unwrap_err();
+        let retry_at = NotConnected::find(&*err).unwrap().retry_at().unwrap();
+        assert_eq!(retry_at - Instant::now(), Duration::from_secs(1));
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap_err();
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap_err();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        // The delay doubled.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap_err();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap_err();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resets_delay_after_connecting() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let mut reconnect = reconnect(
+            move || {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    match attempt {
+                        1 => Ok(connected()),
+                        _ => Err(io::Error::from(io::ErrorKind::ConnectionRefused)),
+                    }
+                })
+            },
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(1))
+                .multiplier(10.0)
+                .jitter(0.0),
+        );
+
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap_err();
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        reconnect.ready().await.unwrap();
+        reconnect.call(()).await.unwrap();
+
+        // Lose the connection, the next attempt fails.
+        reconnect.state = State::Idle;
+        reconnect.ready().await.unwrap();
+        let err = reconnect.call(()).await.unwrap_err();
+        let retry_at = NotConnected::find(&*err).unwrap().retry_at().unwrap();
+        assert_eq!(retry_at - Instant::now(), Duration::from_secs(1));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abandons_attempt_after_connect_timeout() {
+        let mut reconnect = reconnect(
+            || Box::pin(std::future::pending()),
+            ReconnectBackoff::new()
+                .initial_delay(Duration::from_secs(1))
+                .min_connect_timeout(Duration::from_secs(5)),
+        );
+
+        let start = Instant::now();
+        reconnect.ready().await.unwrap();
+        let err = reconnect.call(()).await.unwrap_err();
+
+        assert_eq!(Instant::now() - start, Duration::from_secs(5));
+        assert!(err.source().unwrap().is::<ConnectError>());
+    }
+}
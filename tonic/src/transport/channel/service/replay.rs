@@ -0,0 +1,245 @@
+use crate::{body::BoxBody, Status};
+use bytes::Bytes;
+use http::HeaderMap;
+use http_body::{Body, Frame, SizeHint};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A request body that can be sent more than once.
+///
+/// Every clone of a `ReplayBody` yields the same frames. Frames are pulled lazily from the
+/// original body and recorded, so streaming requests are not delayed until they have been fully
+/// buffered. Once more than `max_buffer_size` bytes have been recorded the body stops recording
+/// and can no longer be replayed, see [`ReplayBody::is_replayable`].
+#[derive(Debug)]
+pub(crate) struct ReplayBody {
+    shared: Arc<Mutex<Shared>>,
+    /// Index of the next frame this body yields.
+    position: usize,
+    sent_trailers: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    source: BoxBody,
+    frames: Vec<Bytes>,
+    buffered: usize,
+    max_buffer_size: usize,
+    /// Number of data frames read from `source`, recorded or not.
+    read: usize,
+    overflowed: bool,
+    end_stream: bool,
+    trailers: Option<HeaderMap>,
+    error: Option<(crate::Code, String)>,
+    waiters: Vec<Waker>,
+}
+
+impl ReplayBody {
+    pub(crate) fn new(source: BoxBody, max_buffer_size: usize) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                source,
+                frames: Vec::new(),
+                buffered: 0,
+                max_buffer_size,
+                read: 0,
+                overflowed: false,
+                end_stream: false,
+                trailers: None,
+                error: None,
+                waiters: Vec::new(),
+            })),
+            position: 0,
+            sent_trailers: false,
+        }
+    }
+
+    /// Returns `true` if a fresh clone of this body would yield the complete request.
+    pub(crate) fn is_replayable(&self) -> bool {
+        !self.shared.lock().unwrap().overflowed
+    }
+}
+
+impl Clone for ReplayBody {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            position: 0,
+            sent_trailers: false,
+        }
+    }
+}
+
+impl Body for ReplayBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        let mut shared = this.shared.lock().unwrap();
+
+        if let Some(frame) = shared.frames.get(this.position) {
+            let frame = frame.clone();
+            this.position += 1;
+            return Poll::Ready(Some(Ok(Frame::data(frame))));
+        }
+
+        if this.position < shared.read {
+            // Another copy of this body read frames that were not recorded.
+            return Poll::Ready(Some(Err(Status::internal(
+                "request body exceeded the retry buffer and cannot be replayed",
+            ))));
+        }
+
+        if shared.end_stream {
+            if let Some((code, message)) = &shared.error {
+                return Poll::Ready(Some(Err(Status::new(*code, message.clone()))));
+            }
+
+            if !this.sent_trailers {
+                this.sent_trailers = true;
+                if let Some(trailers) = shared.trailers.clone() {
+                    return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+                }
+            }
+
+            return Poll::Ready(None);
+        }
+
+        let result = match Pin::new(&mut shared.source).poll_frame(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => {
+                // Only the last poller is registered with the source, so remember every
+                // waiting copy and wake them once the source makes progress.
+                shared.waiters.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+        };
+
+        for waker in shared.waiters.drain(..) {
+            waker.wake();
+        }
+
+        match result {
+            Some(Ok(frame)) => match frame.into_data() {
+                Ok(data) => {
+                    shared.read += 1;
+                    this.position += 1;
+
+                    if !shared.overflowed {
+                        shared.buffered += data.len();
+                        if shared.buffered > shared.max_buffer_size {
+                            shared.overflowed = true;
+                        } else {
+                            shared.frames.push(data.clone());
+                        }
+                    }
+
+                    Poll::Ready(Some(Ok(Frame::data(data))))
+                }
+                Err(frame) => {
+                    if let Ok(trailers) = frame.into_trailers() {
+                        match &mut shared.trailers {
+                            Some(existing) => existing.extend(trailers),
+                            None => shared.trailers = Some(trailers),
+                        }
+                    }
+
+                    // Return an empty frame, the trailers are handed out once the body
+                    // reaches its end.
+                    Poll::Ready(Some(Ok(Frame::data(Bytes::new()))))
+                }
+            },
+            Some(Err(status)) => {
+                shared.end_stream = true;
+                shared.error = Some((status.code(), status.message().to_string()));
+                Poll::Ready(Some(Err(status)))
+            }
+            None => {
+                shared.end_stream = true;
+                this.sent_trailers = true;
+                match shared.trailers.clone() {
+                    Some(trailers) => Poll::Ready(Some(Ok(Frame::trailers(trailers)))),
+                    None => Poll::Ready(None),
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.end_stream
+            && shared.error.is_none()
+            && self.position >= shared.frames.len()
+            && (self.sent_trailers || shared.trailers.is_none())
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let shared = self.shared.lock().unwrap();
+        if shared.end_stream && !shared.overflowed {
+            let remaining = shared.frames[self.position.min(shared.frames.len())..]
+                .iter()
+                .map(|frame| frame.len() as u64)
+                .sum();
+            SizeHint::with_exact(remaining)
+        } else {
+            SizeHint::default()
+        }
+    }
+}
+
+impl Drop for ReplayBody {
+    fn drop(&mut self) {
+        // The source may have registered our waker, hand the baton to any other copy.
+        if let Ok(mut shared) = self.shared.lock() {
+            for waker in shared.waiters.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    fn body(frames: &[&'static [u8]]) -> BoxBody {
+        let frames = frames
+            .iter()
+            .map(|frame| Frame::data(Bytes::from_static(frame)))
+            .map(Ok::<_, Status>)
+            .collect::<Vec<_>>();
+        crate::body::boxed(http_body_util::StreamBody::new(tokio_stream::iter(frames)))
+    }
+
+    async fn collect(body: ReplayBody) -> Bytes {
+        body.collect().await.unwrap().to_bytes()
+    }
+
+    #[tokio::test]
+    async fn replays_all_frames() {
+        let first = ReplayBody::new(body(&[b"hello", b" ", b"world"]), 1024);
+        let second = first.clone();
+
+        assert_eq!(collect(first).await, "hello world");
+        assert_eq!(collect(second.clone()).await, "hello world");
+        assert!(second.is_replayable());
+    }
+
+    #[tokio::test]
+    async fn stops_replaying_past_buffer_limit() {
+        let first = ReplayBody::new(body(&[b"hello", b" ", b"world"]), 4);
+        let second = first.clone();
+
+        assert_eq!(collect(first).await, "hello world");
+        assert!(!second.is_replayable());
+        assert!(second.collect().await.is_err());
+    }
+}
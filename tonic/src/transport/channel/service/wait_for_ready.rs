@@ -0,0 +1,160 @@
+use super::{reconnect::NotConnected, ReplayBody};
+use crate::{
+    body::BoxBody,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::duration_to_grpc_timeout,
+    transport::channel::{connectivity::Connectivity, retry::parse_deadline, BoxFuture},
+    Status,
+};
+use http::{Request, Response};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower::ServiceExt;
+use tower_service::Service;
+
+// How long a waiting call waits for the channel to change state before it tries again, when the
+// channel has no reconnect backoff telling when it connects again.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Request extension overriding the wait-for-ready default of the channel for a call, set from
+/// the method config.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WaitForReadyOption(pub(crate) bool);
+
+/// Keeps calls that opted into wait-for-ready from failing while the channel is not connected.
+///
+/// Such a call is sent again once the channel changes state or is due to connect again after
+/// backing off, until the call deadline expires.
+#[derive(Debug, Clone)]
+pub(crate) struct WaitForReady<S> {
+    inner: S,
+    default: bool,
+    connectivity: Connectivity,
+}
+
+impl<S> WaitForReady<S> {
+    pub(crate) fn new(inner: S, default: bool, connectivity: Connectivity) -> Self {
+        Self {
+            inner,
+            default,
+            connectivity,
+        }
+    }
+}
+
+impl<S> Service<Request<BoxBody>> for WaitForReady<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::BoxError>,
+    S::Future: Send,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<BoxBody>) -> Self::Future {
+        let wait_for_ready = request
+            .extensions()
+            .get::<WaitForReadyOption>()
+            .map_or(self.default, |option| option.0);
+
+        if !wait_for_ready {
+            return ResponseFuture::Passthrough(self.inner.call(request));
+        }
+
+        // The first call was made ready by `poll_ready`, later ones wait for readiness themselves.
+        let (parts, body) = request.into_parts();
+        // Calls are only sent again if connecting failed, before anything of the body was read,
+        // so nothing has to be recorded.
+        let body = ReplayBody::new(body, 0);
+        let first = self.inner.call(Request::from_parts(
+            parts.clone(),
+            BoxBody::new(body.clone()),
+        ));
+        let inner = self.inner.clone();
+        let mut state = self.connectivity.subscribe();
+
+        ResponseFuture::Waiting(Box::pin(async move {
+            let deadline = parse_deadline(&parts.headers);
+            let mut result = first.await.map_err(Into::into);
+
+            loop {
+                let retry_at = match &result {
+                    Err(error) if body.is_replayable() => match NotConnected::find(&**error) {
+                        Some(not_connected) => {
+                            tracing::debug!("waiting for the channel to be ready: {}", error);
+                            not_connected.retry_at()
+                        }
+                        None => return result,
+                    },
+                    _ => return result,
+                };
+                let retry_at = retry_at.unwrap_or_else(|| Instant::now() + RECONNECT_INTERVAL);
+
+                state.borrow_and_update();
+                let changed = tokio::time::timeout_at(retry_at, state.changed());
+                match deadline {
+                    Some(deadline) => {
+                        if tokio::time::timeout_at(deadline, changed).await.is_err() {
+                            return Err(Status::deadline_exceeded(
+                                "deadline expired while waiting for the channel to be ready",
+                            )
+                            .into());
+                        }
+                    }
+                    None => {
+                        let _ = changed.await;
+                    }
+                }
+
+                let mut parts = parts.clone();
+                if let Some(deadline) = deadline {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if let Ok(value) = duration_to_grpc_timeout(remaining).parse() {
+                        parts.headers.insert(GRPC_TIMEOUT_HEADER, value);
+                    }
+                }
+
+                let request = Request::from_parts(parts, BoxBody::new(body.clone()));
+                result = inner.clone().oneshot(request).await.map_err(Into::into);
+            }
+        }))
+    }
+}
+
+#[pin_project(project = ResponseFutureProj)]
+pub(crate) enum ResponseFuture<F> {
+    Passthrough(#[pin] F),
+    Waiting(BoxFuture<'static, Result<Response<BoxBody>, crate::BoxError>>),
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+    E: Into<crate::BoxError>,
+{
+    type Output = Result<Response<BoxBody>, crate::BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            ResponseFutureProj::Passthrough(future) => future.poll(cx).map_err(Into::into),
+            ResponseFutureProj::Waiting(future) => future.as_mut().poll(cx),
+        }
+    }
+}
+
+impl<F> std::fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
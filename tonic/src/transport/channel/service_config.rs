@@ -0,0 +1,614 @@
+//! Per-method call configuration, following the gRPC [service config].
+//!
+//! [service config]: https://github.com/grpc/grpc/blob/master/doc/service_config.md
+
+use super::{service::MethodMap, HedgingPolicies, HedgingPolicy, RetryPolicies, RetryPolicy};
+use std::time::Duration;
+
+/// The configuration of a gRPC channel.
+///
+/// A service config holds a [`MethodConfig`] per service or method and is applied to a
+/// [`Channel`] through [`Endpoint::service_config`]. Name matching follows the gRPC service
+/// config rules: the config for a specific method takes precedence over the config for its
+/// whole service, which takes precedence over the default config. Only the most specific config
+/// is used, its values are not merged with the less specific ones.
+///
+/// With the `service-config` feature a service config can also be parsed from the standard JSON
+/// representation shared with the other gRPC implementations, see `ServiceConfig::from_json`.
+///
+/// ```
+/// # use tonic::transport::channel::{MethodConfig, RetryPolicy, ServiceConfig};
+/// # use std::time::Duration;
+/// let config = ServiceConfig::new()
+///     .default_method_config(MethodConfig::new().timeout(Duration::from_secs(5)))
+///     .method(
+///         "helloworld.Greeter",
+///         "SayHello",
+///         MethodConfig::new()
+///             .timeout(Duration::from_secs(1))
+///             .retry_policy(RetryPolicy::new(3)),
+///     );
+/// ```
+///
+/// [`Channel`]: super::Channel
+/// [`Endpoint::service_config`]: super::Endpoint::service_config
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    methods: MethodMap<MethodConfig>,
+}
+
+/// The configuration of the calls to a method, see [`ServiceConfig`].
+#[derive(Debug, Clone, Default)]
+pub struct MethodConfig {
+    timeout: Option<Duration>,
+    max_request_message_bytes: Option<usize>,
+    max_response_message_bytes: Option<usize>,
+    wait_for_ready: Option<bool>,
+    retry_policy: Option<RetryPolicy>,
+    hedging_policy: Option<HedgingPolicy>,
+}
+
+impl ServiceConfig {
+    /// Create an empty service config.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the config for every method without a more specific config.
+    pub fn default_method_config(mut self, config: MethodConfig) -> Self {
+        self.methods.set_default(config);
+        self
+    }
+
+    /// Set the config for every method of `service`, e.g. `helloworld.Greeter`.
+    pub fn service(mut self, service: impl Into<String>, config: MethodConfig) -> Self {
+        self.methods.set_service(service, config);
+        self
+    }
+
+    /// Set the config for a single method of `service`, e.g. `SayHello`.
+    pub fn method(mut self, service: &str, method: &str, config: MethodConfig) -> Self {
+        self.methods.set_method(service, method, config);
+        self
+    }
+
+    /// Get the config for a request path of the form `/package.Service/Method`.
+    pub fn get(&self, path: &str) -> Option<&MethodConfig> {
+        self.methods.get(path)
+    }
+
+    pub(crate) fn method_configs(&self) -> &MethodMap<MethodConfig> {
+        &self.methods
+    }
+
+    pub(crate) fn retry_policies(&self) -> RetryPolicies {
+        // A method config without a retry policy disables retries for its methods, even when a
+        // less specific config has one.
+        RetryPolicies::from_method_map(self.methods.map(|config| {
+            config
+                .retry_policy
+                .clone()
+                .unwrap_or_else(|| RetryPolicy::new(1))
+        }))
+    }
+
+    pub(crate) fn hedging_policies(&self) -> HedgingPolicies {
+        HedgingPolicies::from_method_map(self.methods.map(|config| {
+            config
+                .hedging_policy
+                .clone()
+                .unwrap_or_else(|| HedgingPolicy::new(1))
+        }))
+    }
+}
+
+impl MethodConfig {
+    /// Create an empty method config.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum duration of a call.
+    ///
+    /// When a request already has a shorter `grpc-timeout` that one is used instead.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        MethodConfig {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Set the maximum size of a request message, which also limits compressed messages once
+    /// decompressed.
+    ///
+    /// It lowers the `max_encoding_message_size` of clients for the call, failing it the same way.
+    pub fn max_request_message_bytes(self, limit: usize) -> Self {
+        MethodConfig {
+            max_request_message_bytes: Some(limit),
+            ..self
+        }
+    }
+
+    /// Set the maximum size of a response message, which also limits compressed messages once
+    /// decompressed.
+    ///
+    /// It lowers the `max_decoding_message_size` of clients for the call, failing it the same way.
+    pub fn max_response_message_bytes(self, limit: usize) -> Self {
+        MethodConfig {
+            max_response_message_bytes: Some(limit),
+            ..self
+        }
+    }
+
+    /// Set whether calls wait for the channel to become ready instead of failing immediately
+    /// when it cannot connect.
+    pub fn wait_for_ready(self, wait_for_ready: bool) -> Self {
+        MethodConfig {
+            wait_for_ready: Some(wait_for_ready),
+            ..self
+        }
+    }
+
+    /// Set the retry policy of the calls.
+    ///
+    /// A method config can have either a retry or a hedging policy, setting one removes the
+    /// other.
+    pub fn retry_policy(self, policy: RetryPolicy) -> Self {
+        MethodConfig {
+            retry_policy: Some(policy),
+            hedging_policy: None,
+            ..self
+        }
+    }
+
+    /// Set the hedging policy of the calls.
+    ///
+    /// A method config can have either a retry or a hedging policy, setting one removes the
+    /// other.
+    pub fn hedging_policy(self, policy: HedgingPolicy) -> Self {
+        MethodConfig {
+            hedging_policy: Some(policy),
+            retry_policy: None,
+            ..self
+        }
+    }
+
+    /// Get the maximum duration of a call.
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get the maximum size of a request message.
+    pub fn get_max_request_message_bytes(&self) -> Option<usize> {
+        self.max_request_message_bytes
+    }
+
+    /// Get the maximum size of a response message.
+    pub fn get_max_response_message_bytes(&self) -> Option<usize> {
+        self.max_response_message_bytes
+    }
+
+    /// Get whether calls wait for the channel to become ready.
+    pub fn get_wait_for_ready(&self) -> Option<bool> {
+        self.wait_for_ready
+    }
+
+    /// Get the retry policy of the calls.
+    pub fn get_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Get the hedging policy of the calls.
+    pub fn get_hedging_policy(&self) -> Option<&HedgingPolicy> {
+        self.hedging_policy.as_ref()
+    }
+}
+
+#[cfg(feature = "service-config")]
+mod json {
+    use super::{MethodConfig, ServiceConfig};
+    use crate::{
+        transport::channel::{HedgingPolicy, RetryPolicy},
+        Code,
+    };
+    use serde_json::{Map, Value};
+    use std::{collections::HashSet, str::FromStr, time::Duration};
+
+    // The gRPC spec caps the attempts of retry and hedging policies at 5.
+    const MAX_ATTEMPTS_LIMIT: u64 = 5;
+
+    type Error = crate::transport::Error;
+
+    impl ServiceConfig {
+        /// Parse a service config from its JSON representation.
+        ///
+        /// The `methodConfig` entries are supported, any other field is ignored. `retryThrottling`
+        /// is not supported.
+        ///
+        /// ```
+        /// # use tonic::transport::channel::ServiceConfig;
+        /// let config = ServiceConfig::from_json(r#"{
+        ///     "methodConfig": [{
+        ///         "name": [{ "service": "helloworld.Greeter" }],
+        ///         "timeout": "1.5s",
+        ///         "retryPolicy": {
+        ///             "maxAttempts": 3,
+        ///             "initialBackoff": "0.1s",
+        ///             "maxBackoff": "1s",
+        ///             "backoffMultiplier": 2,
+        ///             "retryableStatusCodes": ["UNAVAILABLE"]
+        ///         }
+        ///     }]
+        /// }"#).unwrap();
+        ///
+        /// let method = config.get("/helloworld.Greeter/SayHello").unwrap();
+        /// assert_eq!(method.get_retry_policy().unwrap().get_max_attempts(), 3);
+        /// ```
+        pub fn from_json(json: &str) -> Result<Self, Error> {
+            let value: Value = serde_json::from_str(json).map_err(Error::new_service_config)?;
+            parse_service_config(&value).map_err(Error::new_service_config)
+        }
+    }
+
+    impl FromStr for ServiceConfig {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Self::from_json(s)
+        }
+    }
+
+    fn parse_service_config(value: &Value) -> Result<ServiceConfig, String> {
+        let object = as_object(value, "service config")?;
+        let mut config = ServiceConfig::new();
+        let mut names = HashSet::new();
+
+        let Some(method_configs) = object.get("methodConfig") else {
+            return Ok(config);
+        };
+
+        let method_configs = method_configs
+            .as_array()
+            .ok_or("`methodConfig` must be an array")?;
+
+        for method_config in method_configs {
+            let object = as_object(method_config, "`methodConfig` entry")?;
+            let parsed = parse_method_config(object)?;
+
+            let Some(entries) = object.get("name") else {
+                continue;
+            };
+            let entries = entries.as_array().ok_or("`name` must be an array")?;
+
+            for entry in entries {
+                let entry = as_object(entry, "`name` entry")?;
+                let service = optional_str(entry, "service")?.unwrap_or_default();
+                let method = optional_str(entry, "method")?.unwrap_or_default();
+
+                if !names.insert((service, method)) {
+                    return Err(format!("duplicate method config name {service}/{method}"));
+                }
+
+                config = match (service, method) {
+                    ("", "") => config.default_method_config(parsed.clone()),
+                    ("", _) => {
+                        return Err(format!("method {method} is set without a service"));
+                    }
+                    (service, "") => config.service(service, parsed.clone()),
+                    (service, method) => config.method(service, method, parsed.clone()),
+                };
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn parse_method_config(object: &Map<String, Value>) -> Result<MethodConfig, String> {
+        let mut config = MethodConfig::new();
+
+        if let Some(value) = object.get("waitForReady") {
+            let wait_for_ready = value.as_bool().ok_or("`waitForReady` must be a boolean")?;
+            config = config.wait_for_ready(wait_for_ready);
+        }
+
+        if let Some(value) = object.get("timeout") {
+            config = config.timeout(parse_duration(value, "timeout")?);
+        }
+
+        if let Some(value) = object.get("maxRequestMessageBytes") {
+            config = config.max_request_message_bytes(parse_size(value, "maxRequestMessageBytes")?);
+        }
+
+        if let Some(value) = object.get("maxResponseMessageBytes") {
+            config =
+                config.max_response_message_bytes(parse_size(value, "maxResponseMessageBytes")?);
+        }
+
+        match (object.get("retryPolicy"), object.get("hedgingPolicy")) {
+            (Some(_), Some(_)) => {
+                return Err("`retryPolicy` and `hedgingPolicy` cannot both be set".into());
+            }
+            (Some(value), None) => config = config.retry_policy(parse_retry_policy(value)?),
+            (None, Some(value)) => config = config.hedging_policy(parse_hedging_policy(value)?),
+            (None, None) => {}
+        }
+
+        Ok(config)
+    }
+
+    fn parse_retry_policy(value: &Value) -> Result<RetryPolicy, String> {
+        let object = as_object(value, "`retryPolicy`")?;
+
+        let max_attempts = parse_max_attempts(object, "retryPolicy")?;
+        let initial_backoff =
+            parse_duration(required(object, "initialBackoff")?, "initialBackoff")?;
+        let max_backoff = parse_duration(required(object, "maxBackoff")?, "maxBackoff")?;
+        let backoff_multiplier = required(object, "backoffMultiplier")?
+            .as_f64()
+            .ok_or("`backoffMultiplier` must be a number")?;
+        let codes = parse_codes(required(object, "retryableStatusCodes")?)?;
+
+        if initial_backoff.is_zero() || max_backoff.is_zero() || backoff_multiplier <= 0.0 {
+            return Err("retry backoff values must be greater than zero".into());
+        }
+
+        if codes.is_empty() {
+            return Err("`retryableStatusCodes` must not be empty".into());
+        }
+
+        Ok(RetryPolicy::new(max_attempts)
+            .initial_backoff(initial_backoff)
+            .max_backoff(max_backoff)
+            .backoff_multiplier(backoff_multiplier)
+            .retryable_status_codes(codes))
+    }
+
+    fn parse_hedging_policy(value: &Value) -> Result<HedgingPolicy, String> {
+        let object = as_object(value, "`hedgingPolicy`")?;
+
+        let mut policy = HedgingPolicy::new(parse_max_attempts(object, "hedgingPolicy")?);
+
+        if let Some(value) = object.get("hedgingDelay") {
+            policy = policy.hedging_delay(parse_duration(value, "hedgingDelay")?);
+        }
+
+        if let Some(value) = object.get("nonFatalStatusCodes") {
+            policy = policy.non_fatal_status_codes(parse_codes(value)?);
+        }
+
+        Ok(policy)
+    }
+
+    fn parse_max_attempts(object: &Map<String, Value>, policy: &str) -> Result<usize, String> {
+        let max_attempts = required(object, "maxAttempts")?
+            .as_u64()
+            .ok_or("`maxAttempts` must be a positive integer")?;
+
+        if max_attempts < 2 {
+            return Err(format!("`{policy}.maxAttempts` must be at least 2"));
+        }
+
+        Ok(max_attempts.min(MAX_ATTEMPTS_LIMIT) as usize)
+    }
+
+    /// Parses a protobuf JSON duration, e.g. `"1.5s"`.
+    fn parse_duration(value: &Value, field: &str) -> Result<Duration, String> {
+        let invalid = || format!("`{field}` must be a duration like \"1.5s\"");
+
+        let seconds = value
+            .as_str()
+            .and_then(|s| s.strip_suffix('s'))
+            .ok_or_else(invalid)?;
+
+        let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+
+        if whole.is_empty()
+            || fraction.len() > 9
+            || !whole.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let secs = whole.parse::<u64>().map_err(|_| invalid())?;
+        let nanos = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<9}")
+                .parse::<u32>()
+                .map_err(|_| invalid())?
+        };
+
+        Ok(Duration::new(secs, nanos))
+    }
+
+    /// Parses a `uint32` which protobuf JSON allows as a number or a string.
+    fn parse_size(value: &Value, field: &str) -> Result<usize, String> {
+        let size = match value {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) => s.parse::<u64>().ok(),
+            _ => None,
+        };
+
+        size.and_then(|size| usize::try_from(size).ok())
+            .ok_or_else(|| format!("`{field}` must be a positive integer"))
+    }
+
+    /// Parses a list of status codes, given either by name or by number.
+    fn parse_codes(value: &Value) -> Result<Vec<Code>, String> {
+        let codes = value.as_array().ok_or("status codes must be an array")?;
+
+        codes
+            .iter()
+            .map(|code| {
+                let parsed = match code {
+                    Value::String(name) => parse_code_name(name),
+                    Value::Number(n) => n
+                        .as_u64()
+                        .filter(|n| *n <= 16)
+                        .map(|n| Code::from_i32(n as i32)),
+                    _ => None,
+                };
+
+                parsed.ok_or_else(|| format!("invalid status code {code}"))
+            })
+            .collect()
+    }
+
+    fn parse_code_name(name: &str) -> Option<Code> {
+        let code = match name {
+            "OK" => Code::Ok,
+            "CANCELLED" => Code::Cancelled,
+            "UNKNOWN" => Code::Unknown,
+            "INVALID_ARGUMENT" => Code::InvalidArgument,
+            "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+            "NOT_FOUND" => Code::NotFound,
+            "ALREADY_EXISTS" => Code::AlreadyExists,
+            "PERMISSION_DENIED" => Code::PermissionDenied,
+            "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+            "FAILED_PRECONDITION" => Code::FailedPrecondition,
+            "ABORTED" => Code::Aborted,
+            "OUT_OF_RANGE" => Code::OutOfRange,
+            "UNIMPLEMENTED" => Code::Unimplemented,
+            "INTERNAL" => Code::Internal,
+            "UNAVAILABLE" => Code::Unavailable,
+            "DATA_LOSS" => Code::DataLoss,
+            "UNAUTHENTICATED" => Code::Unauthenticated,
+            _ => return None,
+        };
+
+        Some(code)
+    }
+
+    fn as_object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>, String> {
+        value
+            .as_object()
+            .ok_or_else(|| format!("{what} must be an object"))
+    }
+
+    fn required<'a>(object: &'a Map<String, Value>, field: &str) -> Result<&'a Value, String> {
+        object
+            .get(field)
+            .ok_or_else(|| format!("`{field}` is required"))
+    }
+
+    fn optional_str<'a>(
+        object: &'a Map<String, Value>,
+        field: &str,
+    ) -> Result<Option<&'a str>, String> {
+        object
+            .get(field)
+            .map(|value| value.as_str().ok_or(format!("`{field}` must be a string")))
+            .transpose()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_method_configs() {
+            let config = ServiceConfig::from_json(
+                r#"{
+                    "loadBalancingConfig": [{ "round_robin": {} }],
+                    "methodConfig": [
+                        {
+                            "name": [{}],
+                            "timeout": "10s"
+                        },
+                        {
+                            "name": [{ "service": "test.Test" }],
+                            "waitForReady": true,
+                            "maxRequestMessageBytes": 1024,
+                            "maxResponseMessageBytes": "2048",
+                            "hedgingPolicy": {
+                                "maxAttempts": 10,
+                                "hedgingDelay": "0.05s",
+                                "nonFatalStatusCodes": [14]
+                            }
+                        },
+                        {
+                            "name": [{ "service": "test.Test", "method": "UnaryCall" }],
+                            "timeout": "0.250s",
+                            "retryPolicy": {
+                                "maxAttempts": 4,
+                                "initialBackoff": "0.1s",
+                                "maxBackoff": "1s",
+                                "backoffMultiplier": 1.5,
+                                "retryableStatusCodes": ["UNAVAILABLE", "ABORTED"]
+                            }
+                        }
+                    ]
+                }"#,
+            )
+            .unwrap();
+
+            let default = config.get("/other.Other/Call").unwrap();
+            assert_eq!(default.get_timeout(), Some(Duration::from_secs(10)));
+            assert!(default.get_retry_policy().is_none());
+
+            let service = config.get("/test.Test/StreamCall").unwrap();
+            assert_eq!(service.get_timeout(), None);
+            assert_eq!(service.get_wait_for_ready(), Some(true));
+            assert_eq!(service.get_max_request_message_bytes(), Some(1024));
+            assert_eq!(service.get_max_response_message_bytes(), Some(2048));
+            let hedging = service.get_hedging_policy().unwrap();
+            assert_eq!(hedging.get_max_attempts(), 5);
+            assert_eq!(hedging.get_hedging_delay(), Duration::from_millis(50));
+            assert_eq!(hedging.get_non_fatal_status_codes(), [Code::Unavailable]);
+
+            let method = config.get("/test.Test/UnaryCall").unwrap();
+            assert_eq!(method.get_timeout(), Some(Duration::from_millis(250)));
+            let retry = method.get_retry_policy().unwrap();
+            assert_eq!(retry.get_max_attempts(), 4);
+            assert_eq!(retry.get_initial_backoff(), Duration::from_millis(100));
+            assert_eq!(retry.get_max_backoff(), Duration::from_secs(1));
+            assert_eq!(retry.get_backoff_multiplier(), 1.5);
+            assert_eq!(
+                retry.get_retryable_status_codes(),
+                [Code::Unavailable, Code::Aborted]
+            );
+        }
+
+        #[test]
+        fn rejects_invalid_configs() {
+            let invalid = [
+                r#"[]"#,
+                r#"{ "methodConfig": [{ "name": [{ "method": "Call" }] }] }"#,
+                r#"{ "methodConfig": [{ "name": [{}] }, { "name": [{}] }] }"#,
+                r#"{ "methodConfig": [{ "name": [{}], "timeout": "1m" }] }"#,
+                r#"{ "methodConfig": [{ "name": [{}], "timeout": "1.0123456789s" }] }"#,
+                r#"{ "methodConfig": [{ "name": [{}], "maxRequestMessageBytes": -1 }] }"#,
+                r#"{ "methodConfig": [{
+                    "name": [{}],
+                    "retryPolicy": {
+                        "maxAttempts": 1,
+                        "initialBackoff": "1s",
+                        "maxBackoff": "1s",
+                        "backoffMultiplier": 2,
+                        "retryableStatusCodes": ["UNAVAILABLE"]
+                    }
+                }] }"#,
+                r#"{ "methodConfig": [{
+                    "name": [{}],
+                    "retryPolicy": {
+                        "maxAttempts": 2,
+                        "initialBackoff": "1s",
+                        "maxBackoff": "1s",
+                        "backoffMultiplier": 2,
+                        "retryableStatusCodes": ["NOT_A_CODE"]
+                    }
+                }] }"#,
+                r#"{ "methodConfig": [{
+                    "name": [{}],
+                    "retryPolicy": {},
+                    "hedgingPolicy": { "maxAttempts": 2 }
+                }] }"#,
+            ];
+
+            for json in invalid {
+                assert!(ServiceConfig::from_json(json).is_err(), "{json}");
+            }
+        }
+    }
+}
@@ -19,6 +19,8 @@ pub(crate) enum Kind {
     InvalidUri,
     #[cfg(feature = "channel")]
     InvalidUserAgent,
+    #[cfg(feature = "channel")]
+    InvalidServiceConfig,
 }
 
 impl Error {
@@ -47,6 +49,11 @@ impl Error {
         Error::new(Kind::InvalidUserAgent)
     }
 
+    #[cfg(feature = "channel")]
+    pub(crate) fn new_service_config(source: impl Into<Source>) -> Self {
+        Error::new(Kind::InvalidServiceConfig).with(source)
+    }
+
     fn description(&self) -> &str {
         match &self.inner.kind {
             Kind::Transport => "transport error",
@@ -54,6 +61,8 @@ impl Error {
             Kind::InvalidUri => "invalid URI",
             #[cfg(feature = "channel")]
             Kind::InvalidUserAgent => "user agent is not a valid header value",
+            #[cfg(feature = "channel")]
+            Kind::InvalidServiceConfig => "invalid service config",
         }
     }
 }
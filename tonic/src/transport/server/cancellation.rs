@@ -0,0 +1,145 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::Notify;
+
+/// A signal that a call handled by [`Server`](super::Server) has ended.
+///
+/// The server inserts a `CancellationToken` into the extensions of every
+/// request. It is cancelled when the call ends before its response has been
+/// sent in full, which is the case when the client resets the stream, the
+/// connection closes, the deadline of the call expires or the handler fails.
+/// Unlike the handler future, which is simply dropped, tasks spawned by the
+/// handler and producers of response streams can hold on to a clone of the
+/// token and use it to stop their work.
+///
+/// A call whose response is sent in full completes without cancelling its
+/// token, so waiting on the token of a completed call never returns.
+///
+/// ```rust
+/// use tonic::{transport::server::CancellationToken, Request};
+///
+/// fn spawn_work(request: &Request<()>) {
+///     let token = request
+///         .extensions()
+///         .get::<CancellationToken>()
+///         .cloned()
+///         .expect("served by tonic::transport::Server");
+///
+///     tokio::spawn(async move {
+///         tokio::select! {
+///             _ = token.cancelled() => println!("call ended, stopping"),
+///             _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {}
+///         }
+///     });
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` once the call has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Waits until the call has been cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            // `notify_waiters` wakes every `Notified` created before it is
+            // called, so register before checking the flag to not miss it.
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    pub(crate) fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::AcqRel) {
+            self.inner.notify.notify_waiters();
+        }
+    }
+
+    /// Returns a guard that cancels this token when it is dropped, unless it is disarmed first.
+    pub(crate) fn drop_guard(&self) -> DropGuard {
+        DropGuard(Some(self.clone()))
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Cancels the wrapped [`CancellationToken`] when dropped.
+#[derive(Debug)]
+pub(crate) struct DropGuard(Option<CancellationToken>);
+
+impl DropGuard {
+    /// Drops the guard without cancelling the token, as the call completed.
+    pub(crate) fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancels_waiters_when_guard_drops() {
+        let token = CancellationToken::new();
+        let guard = token.drop_guard();
+
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!token.is_cancelled());
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        waiter.await.unwrap();
+        assert!(token.is_cancelled());
+
+        // Waiting on a cancelled token completes right away
+        token.cancelled().await;
+    }
+
+    #[test]
+    fn disarmed_guard_does_not_cancel() {
+        let token = CancellationToken::new();
+        token.drop_guard().disarm();
+        assert!(!token.is_cancelled());
+    }
+}
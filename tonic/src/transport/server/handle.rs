@@ -0,0 +1,85 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// A handle reporting the connections and streams a [`Server`] is serving.
+///
+/// Obtained from [`Server::handle`], it can be used to watch a server drain its connections
+/// after its shutdown signal.
+///
+/// ```
+/// # use tonic::transport::Server;
+/// let mut builder = Server::builder();
+/// let handle = builder.handle();
+///
+/// assert_eq!(handle.active_connections(), 0);
+/// assert_eq!(handle.active_streams(), 0);
+/// ```
+///
+/// [`Server`]: super::Server
+/// [`Server::handle`]: super::Server::handle
+#[derive(Clone, Default)]
+pub struct ServerHandle {
+    counts: Arc<Counts>,
+}
+
+#[derive(Default)]
+struct Counts {
+    connections: AtomicUsize,
+    streams: AtomicUsize,
+}
+
+impl ServerHandle {
+    /// Returns the number of connections currently open.
+    pub fn active_connections(&self) -> usize {
+        self.counts.connections.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of streams, calls whose response has not been sent completely, currently
+    /// in flight.
+    pub fn active_streams(&self) -> usize {
+        self.counts.streams.load(Ordering::Acquire)
+    }
+
+    /// Counts an open connection until the returned guard is dropped.
+    pub(crate) fn connection(&self) -> Active {
+        Active::new(self.counts.clone(), |counts| &counts.connections)
+    }
+
+    /// Counts a stream in flight until the returned guard is dropped.
+    pub(crate) fn stream(&self) -> Active {
+        Active::new(self.counts.clone(), |counts| &counts.streams)
+    }
+}
+
+impl fmt::Debug for ServerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerHandle")
+            .field("active_connections", &self.active_connections())
+            .field("active_streams", &self.active_streams())
+            .finish()
+    }
+}
+
+/// Keeps a connection or stream counted as active.
+pub(crate) struct Active {
+    counts: Arc<Counts>,
+    count: fn(&Counts) -> &AtomicUsize,
+}
+
+impl Active {
+    fn new(counts: Arc<Counts>, count: fn(&Counts) -> &AtomicUsize) -> Self {
+        count(&counts).fetch_add(1, Ordering::AcqRel);
+        Self { counts, count }
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        (self.count)(&self.counts).fetch_sub(1, Ordering::AcqRel);
+    }
+}
@@ -1,6 +1,8 @@
 //! Server implementation and builder.
 
+mod cancellation;
 mod conn;
+mod handle;
 mod incoming;
 mod service;
 #[cfg(feature = "_tls-any")]
@@ -13,7 +15,9 @@ use tracing::{debug, trace};
 
 use crate::service::Routes;
 
+pub use cancellation::CancellationToken;
 pub use conn::{Connected, TcpConnectInfo};
+pub use handle::ServerHandle;
 use hyper_util::{
     rt::{TokioExecutor, TokioIo, TokioTimer},
     server::conn::auto::{Builder as ConnectionBuilder, HttpServerConnExec},
@@ -36,8 +40,8 @@ pub use incoming::TcpIncoming;
 #[cfg(feature = "_tls-any")]
 use crate::transport::Error;
 
-use self::service::{RecoverError, ServerIo};
-use super::service::GrpcTimeout;
+use self::service::{Cancellation, EnforceKeepalive, KeepalivePolicy, RecoverError, ServerIo};
+use super::service::{ActiveBody, GrpcTimeout, IdleTimeout};
 use crate::body::{boxed, BoxBody};
 use crate::server::NamedService;
 use bytes::Bytes;
@@ -96,6 +100,8 @@ pub struct Server<L = Identity> {
     tcp_nodelay: bool,
     http2_keepalive_interval: Option<Duration>,
     http2_keepalive_timeout: Option<Duration>,
+    http2_keepalive_min_ping_interval: Option<Duration>,
+    http2_keepalive_permit_without_stream: bool,
     http2_adaptive_window: Option<bool>,
     http2_max_pending_accept_reset_streams: Option<usize>,
     http2_max_header_list_size: Option<u32>,
@@ -103,6 +109,11 @@ pub struct Server<L = Identity> {
     accept_http1: bool,
     service_builder: ServiceBuilder<L>,
     max_connection_age: Option<Duration>,
+    max_connection_age_jitter: f64,
+    max_connection_age_grace: Option<Duration>,
+    max_connection_idle: Option<Duration>,
+    drain_timeout: Option<Duration>,
+    handle: ServerHandle,
 }
 
 impl Default for Server<Identity> {
@@ -120,6 +131,8 @@ impl Default for Server<Identity> {
             tcp_nodelay: false,
             http2_keepalive_interval: None,
             http2_keepalive_timeout: None,
+            http2_keepalive_min_ping_interval: None,
+            http2_keepalive_permit_without_stream: false,
             http2_adaptive_window: None,
             http2_max_pending_accept_reset_streams: None,
             http2_max_header_list_size: None,
@@ -127,6 +140,11 @@ impl Default for Server<Identity> {
             accept_http1: false,
             service_builder: Default::default(),
             max_connection_age: None,
+            max_connection_age_jitter: 0.0,
+            max_connection_age_grace: None,
+            max_connection_idle: None,
+            drain_timeout: None,
+            handle: ServerHandle::default(),
         }
     }
 }
@@ -241,6 +259,10 @@ impl<L> Server<L> {
 
     /// Sets the maximum time option in milliseconds that a connection may exist
     ///
+    /// Every connection gets exactly this age unless [`Server::max_connection_age_jitter`] is set.
+    /// Once a connection reaches its age a GOAWAY is sent, see
+    /// [`Server::max_connection_age_grace`] to bound how long it may drain afterwards.
+    ///
     /// Default is no limit (`None`).
     ///
     /// # Example
@@ -260,6 +282,105 @@ impl<L> Server<L> {
         }
     }
 
+    /// Set by which fraction the [maximum age](Server::max_connection_age) of every connection
+    /// is randomly lengthened or shortened, between 0 and 1.
+    ///
+    /// Spreading the ages keeps the clients of connections accepted together from all
+    /// reconnecting at once.
+    ///
+    /// Default is 0, every connection gets exactly the maximum age.
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder
+    ///     .max_connection_age(Duration::from_secs(600))
+    ///     .max_connection_age_jitter(0.1);
+    /// ```
+    #[must_use]
+    pub fn max_connection_age_jitter(self, jitter: f64) -> Self {
+        Server {
+            max_connection_age_jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Set how long a connection may drain after reaching its
+    /// [maximum age](Server::max_connection_age) before it is closed, aborting the streams
+    /// still in flight.
+    ///
+    /// Default is no grace period, the connection stays open until its streams complete.
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder
+    ///     .max_connection_age(Duration::from_secs(600))
+    ///     .max_connection_age_grace(Duration::from_secs(30));
+    /// ```
+    #[must_use]
+    pub fn max_connection_age_grace(self, max_connection_age_grace: Duration) -> Self {
+        Server {
+            max_connection_age_grace: Some(max_connection_age_grace),
+            ..self
+        }
+    }
+
+    /// Set how long a connection may go without any stream in flight before it is closed.
+    ///
+    /// A GOAWAY is sent to the client when the connection has been idle for this long, and the
+    /// connection is closed.
+    ///
+    /// Default is no limit.
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.max_connection_idle(Duration::from_secs(300));
+    /// ```
+    #[must_use]
+    pub fn max_connection_idle(self, max_connection_idle: Duration) -> Self {
+        Server {
+            max_connection_idle: Some(max_connection_idle),
+            ..self
+        }
+    }
+
+    /// Set how long connections may drain after the shutdown signal.
+    ///
+    /// On the shutdown signal, the server stops accepting connections and sends a GOAWAY to
+    /// every open connection, letting the streams in flight complete. Connections still open
+    /// once the drain timeout elapses are closed, aborting their streams.
+    ///
+    /// Default is no timeout, the server waits for all streams to complete.
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.drain_timeout(Duration::from_secs(30));
+    /// ```
+    #[must_use]
+    pub fn drain_timeout(self, drain_timeout: Duration) -> Self {
+        Server {
+            drain_timeout: Some(drain_timeout),
+            ..self
+        }
+    }
+
+    /// Returns a [`ServerHandle`] reporting the connections and streams of the servers built
+    /// from this builder.
+    pub fn handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
     /// Set whether HTTP2 Ping frames are enabled on accepted connections.
     ///
     /// If `None` is specified, HTTP2 keepalive is disabled, otherwise the duration
@@ -292,6 +413,49 @@ impl<L> Server<L> {
         }
     }
 
+    /// Sets the minimum interval at which clients may send HTTP2 Ping frames.
+    ///
+    /// A ping arriving sooner than this after the previous one is counted as a strike. Strikes are
+    /// forgiven whenever the server sends data or headers. A client collecting more than two
+    /// strikes is sent a GOAWAY with `ENHANCE_YOUR_CALM` and the connection is closed, aborting
+    /// its streams. While there is no stream on the connection, pings are only allowed every two
+    /// hours unless
+    /// [`Server::http2_keepalive_permit_without_stream`] is set.
+    ///
+    /// Default is no enforcement (`None`).
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.http2_keepalive_min_ping_interval(Some(Duration::from_secs(300)));
+    /// ```
+    #[must_use]
+    pub fn http2_keepalive_min_ping_interval(
+        self,
+        http2_keepalive_min_ping_interval: Option<Duration>,
+    ) -> Self {
+        Server {
+            http2_keepalive_min_ping_interval,
+            ..self
+        }
+    }
+
+    /// Sets whether clients may send HTTP2 Ping frames while there is no stream on the
+    /// connection.
+    ///
+    /// Does nothing if [`Server::http2_keepalive_min_ping_interval`] is not set.
+    ///
+    /// Default is `false`.
+    #[must_use]
+    pub fn http2_keepalive_permit_without_stream(self, enabled: bool) -> Self {
+        Server {
+            http2_keepalive_permit_without_stream: enabled,
+            ..self
+        }
+    }
+
     /// Sets whether to use an adaptive flow control. Defaults to false.
     /// Enabling this will override the limits set in http2_initial_stream_window_size and
     /// http2_initial_connection_window_size.
@@ -519,12 +683,19 @@ impl<L> Server<L> {
             tcp_nodelay: self.tcp_nodelay,
             http2_keepalive_interval: self.http2_keepalive_interval,
             http2_keepalive_timeout: self.http2_keepalive_timeout,
+            http2_keepalive_min_ping_interval: self.http2_keepalive_min_ping_interval,
+            http2_keepalive_permit_without_stream: self.http2_keepalive_permit_without_stream,
             http2_adaptive_window: self.http2_adaptive_window,
             http2_max_pending_accept_reset_streams: self.http2_max_pending_accept_reset_streams,
             http2_max_header_list_size: self.http2_max_header_list_size,
             max_frame_size: self.max_frame_size,
             accept_http1: self.accept_http1,
             max_connection_age: self.max_connection_age,
+            max_connection_age_jitter: self.max_connection_age_jitter,
+            max_connection_age_grace: self.max_connection_age_grace,
+            max_connection_idle: self.max_connection_idle,
+            drain_timeout: self.drain_timeout,
+            handle: self.handle,
         }
     }
 
@@ -562,9 +733,20 @@ impl<L> Server<L> {
         let http2_keepalive_timeout = self
             .http2_keepalive_timeout
             .unwrap_or_else(|| Duration::new(DEFAULT_HTTP2_KEEPALIVE_TIMEOUT_SECS, 0));
+        let keepalive_policy = self
+            .http2_keepalive_min_ping_interval
+            .map(|min_ping_interval| KeepalivePolicy {
+                min_ping_interval,
+                permit_without_stream: self.http2_keepalive_permit_without_stream,
+            });
         let http2_adaptive_window = self.http2_adaptive_window;
         let http2_max_pending_accept_reset_streams = self.http2_max_pending_accept_reset_streams;
         let max_connection_age = self.max_connection_age;
+        let max_connection_age_jitter = self.max_connection_age_jitter;
+        let max_connection_age_grace = self.max_connection_age_grace;
+        let max_connection_idle = self.max_connection_idle;
+        let drain_timeout = self.drain_timeout;
+        let handle = self.handle;
 
         let svc = self.service_builder.service(svc);
 
@@ -578,6 +760,7 @@ impl<L> Server<L> {
             concurrency_limit,
             timeout,
             trace_interceptor,
+            handle: handle.clone(),
             _io: PhantomData,
         };
 
@@ -607,7 +790,7 @@ impl<L> Server<L> {
             builder
         };
 
-        let (signal_tx, signal_rx) = tokio::sync::watch::channel(());
+        let (signal_tx, signal_rx) = tokio::sync::watch::channel(Shutdown::Serving);
         let signal_tx = Arc::new(signal_tx);
 
         let graceful = signal.is_some();
@@ -643,16 +826,28 @@ impl<L> Server<L> {
                         .await
                         .map_err(super::Error::from_source)?;
 
+                    let idle = max_connection_idle.map(IdleTimeout::new);
+                    let req_svc = match idle.clone() {
+                        Some(idle) => track_idle(req_svc, idle),
+                        None => req_svc,
+                    };
+
+                    let io = EnforceKeepalive::new(io, keepalive_policy);
                     let hyper_io = TokioIo::new(io);
                     let hyper_svc = TowerToHyperService::new(req_svc.map_request(|req: Request<Incoming>| req.map(boxed)));
 
-                    serve_connection(hyper_io, hyper_svc, server.clone(), graceful.then(|| signal_rx.clone()), max_connection_age);
+                    let limits = ConnectionLimits {
+                        max_age: max_connection_age.map(|max_age| jitter(max_age, max_connection_age_jitter)),
+                        max_age_grace: max_connection_age_grace,
+                        idle,
+                    };
+                    serve_connection(hyper_io, hyper_svc, server.clone(), graceful.then(|| signal_rx.clone()), limits, handle.connection());
                 }
             }
         }
 
         if graceful {
-            let _ = signal_tx.send(());
+            let _ = signal_tx.send(Shutdown::Draining);
             drop(signal_rx);
             trace!(
                 "waiting for {} connections to close",
@@ -660,21 +855,77 @@ impl<L> Server<L> {
             );
 
             // Wait for all connections to close
-            signal_tx.closed().await;
+            let drained = match drain_timeout {
+                Some(drain_timeout) => tokio::time::timeout(drain_timeout, signal_tx.closed())
+                    .await
+                    .is_ok(),
+                None => {
+                    signal_tx.closed().await;
+                    true
+                }
+            };
+
+            if !drained {
+                debug!(
+                    "drain timeout elapsed, closing {} connections",
+                    signal_tx.receiver_count()
+                );
+                let _ = signal_tx.send(Shutdown::Closing);
+                signal_tx.closed().await;
+            }
         }
 
         Ok(())
     }
 }
 
+/// Bounds the lifetime of a connection.
+struct ConnectionLimits {
+    /// The max connection age, with jitter applied.
+    max_age: Option<Duration>,
+    max_age_grace: Option<Duration>,
+    idle: Option<IdleTimeout>,
+}
+
+/// Spreads a max connection age by up to `jitter` of it either way, so that connections accepted
+/// together are not all closed at once.
+fn jitter(max_age: Duration, jitter: f64) -> Duration {
+    if jitter == 0.0 {
+        return max_age;
+    }
+    max_age.mul_f64(1.0 - jitter + 2.0 * jitter * crate::util::fast_random())
+}
+
+/// Counts the streams of a connection as in flight until their response body ends or is dropped.
+fn track_idle(svc: BoxService, idle: IdleTimeout) -> BoxService {
+    BoxCloneService::new(svc.map_future(move |future| {
+        let active = idle.start();
+        async move {
+            let response = future.await?;
+            Ok::<_, crate::BoxError>(response.map(|body| boxed(ActiveBody::new(body, active))))
+        }
+    }))
+}
+
+/// The shutdown phase of a server, watched by its connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shutdown {
+    Serving,
+    /// Connections finish their streams and close.
+    Draining,
+    /// The drain timeout elapsed, connections close right away.
+    Closing,
+}
+
 // This is moved to its own function as a way to get around
 // https://github.com/rust-lang/rust/issues/102211
 fn serve_connection<B, IO, S, E>(
     hyper_io: IO,
     hyper_svc: S,
     builder: ConnectionBuilder<E>,
-    mut watcher: Option<tokio::sync::watch::Receiver<()>>,
-    max_connection_age: Option<Duration>,
+    mut watcher: Option<tokio::sync::watch::Receiver<Shutdown>>,
+    limits: ConnectionLimits,
+    active: handle::Active,
 ) where
     B: http_body::Body + Send + 'static,
     B::Data: Send,
@@ -687,14 +938,15 @@ fn serve_connection<B, IO, S, E>(
 {
     tokio::spawn(async move {
         {
-            let mut sig = pin!(Fuse {
-                inner: watcher.as_mut().map(|w| w.changed()),
-            });
-
             let mut conn = pin!(builder.serve_connection(hyper_io, hyper_svc));
 
-            let sleep = sleep_or_pending(max_connection_age);
+            let sleep = sleep_or_pending(limits.max_age);
             tokio::pin!(sleep);
+            let mut aged = false;
+
+            let mut idle = pin!(Fuse {
+                inner: limits.idle.as_ref().map(IdleTimeout::expired),
+            });
 
             loop {
                 tokio::select! {
@@ -705,21 +957,47 @@ fn serve_connection<B, IO, S, E>(
                         break;
                     },
                     _ = &mut sleep  => {
+                        if aged {
+                            debug!("closing connection after max connection age grace");
+                            break;
+                        }
                         conn.as_mut().graceful_shutdown();
-                        sleep.set(sleep_or_pending(None));
+                        sleep.set(sleep_or_pending(limits.max_age_grace));
+                        aged = true;
                     },
-                    _ = &mut sig => {
+                    _ = &mut idle => {
+                        trace!("connection idle, shutting down");
                         conn.as_mut().graceful_shutdown();
+                    },
+                    shutdown = shutdown_changed(&mut watcher) => match shutdown {
+                        Shutdown::Serving => {},
+                        Shutdown::Draining => conn.as_mut().graceful_shutdown(),
+                        Shutdown::Closing => {
+                            debug!("closing connection after drain timeout");
+                            break;
+                        }
                     }
                 }
             }
         }
 
+        drop(active);
         drop(watcher);
         trace!("connection closed");
     });
 }
 
+async fn shutdown_changed(
+    watcher: &mut Option<tokio::sync::watch::Receiver<Shutdown>>,
+) -> Shutdown {
+    if let Some(watcher) = watcher {
+        if watcher.changed().await.is_ok() {
+            return *watcher.borrow_and_update();
+        }
+    }
+    pending().await
+}
+
 async fn sleep_or_pending(wait_for: Option<Duration>) {
     match wait_for {
         Some(wait) => sleep(wait).await,
@@ -913,6 +1191,7 @@ impl<L> fmt::Debug for Server<L> {
 struct Svc<S> {
     inner: S,
     trace_interceptor: Option<TraceInterceptor>,
+    handle: ServerHandle,
 }
 
 impl<S, ResBody> Service<Request<BoxBody>> for Svc<S>
@@ -948,6 +1227,7 @@ where
         SvcFuture {
             inner: self.inner.call(req),
             span,
+            active: Some(self.handle.stream()),
         }
     }
 }
@@ -957,6 +1237,7 @@ struct SvcFuture<F> {
     #[pin]
     inner: F,
     span: tracing::Span,
+    active: Option<handle::Active>,
 }
 
 impl<F, E, ResBody> Future for SvcFuture<F>
@@ -973,7 +1254,9 @@ where
         let _guard = this.span.enter();
 
         let response: Response<ResBody> = ready!(this.inner.poll(cx)).map_err(Into::into)?;
-        let response = response.map(|body| boxed(body.map_err(Into::into)));
+        let active = this.active.take().expect("polled after ready");
+        let response =
+            response.map(|body| boxed(ActiveBody::new(body.map_err(Into::into), active)));
         Poll::Ready(Ok(response))
     }
 }
@@ -990,6 +1273,7 @@ struct MakeSvc<S, IO> {
     timeout: Option<Duration>,
     inner: S,
     trace_interceptor: Option<TraceInterceptor>,
+    handle: ServerHandle,
     _io: PhantomData<fn() -> IO>,
 }
 
@@ -1017,11 +1301,13 @@ where
         let concurrency_limit = self.concurrency_limit;
         let timeout = self.timeout;
         let trace_interceptor = self.trace_interceptor.clone();
+        let handle = self.handle.clone();
 
         let svc = ServiceBuilder::new()
             .layer_fn(RecoverError::new)
             .option_layer(concurrency_limit.map(ConcurrencyLimitLayer::new))
             .layer_fn(|s| GrpcTimeout::new(s, timeout))
+            .layer_fn(Cancellation::new)
             .service(svc);
 
         let svc = ServiceBuilder::new()
@@ -1052,6 +1338,7 @@ where
             .service(Svc {
                 inner: svc,
                 trace_interceptor,
+                handle,
             });
 
         future::ready(Ok(svc))
@@ -0,0 +1,133 @@
+use crate::transport::server::cancellation::{CancellationToken, DropGuard};
+use http::{Request, Response};
+use http_body::Frame;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tower_service::Service;
+
+/// Middleware that inserts a [`CancellationToken`] into every request and cancels it if the
+/// response future, or the response body before reaching its end, is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct Cancellation<S> {
+    inner: S,
+}
+
+impl<S> Cancellation<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Cancellation<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: http_body::Body,
+{
+    type Response = Response<CancellableBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let token = CancellationToken::new();
+        let guard = token.drop_guard();
+        req.extensions_mut().insert(token);
+
+        ResponseFuture {
+            inner: self.inner.call(req),
+            guard: Some(guard),
+        }
+    }
+}
+
+#[pin_project]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    guard: Option<DropGuard>,
+}
+
+impl<F, E, ResBody> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+    ResBody: http_body::Body,
+{
+    type Output = Result<Response<CancellableBody<ResBody>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = ready!(this.inner.poll(cx));
+
+        // Hand the guard over to the body so streaming responses stay uncancelled while they are
+        // being sent. On errors it is dropped along with the future.
+        Poll::Ready(result.map(|response| {
+            let guard = this.guard.take();
+            response.map(|inner| CancellableBody::new(inner, guard))
+        }))
+    }
+}
+
+#[pin_project]
+pub(crate) struct CancellableBody<B> {
+    #[pin]
+    inner: B,
+    guard: Option<DropGuard>,
+}
+
+impl<B: http_body::Body> CancellableBody<B> {
+    fn new(inner: B, mut guard: Option<DropGuard>) -> Self {
+        // An empty body is not polled, the response is complete once it is sent.
+        if inner.is_end_stream() {
+            if let Some(guard) = guard.take() {
+                guard.disarm();
+            }
+        }
+        Self { inner, guard }
+    }
+}
+
+impl<B> http_body::Body for CancellableBody<B>
+where
+    B: http_body::Body,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let frame = ready!(this.inner.as_mut().poll_frame(cx));
+
+        // The response was sent in full, the call completed rather than being cancelled. The body
+        // may not be polled again after trailers or once it reports its end.
+        let completed = match &frame {
+            None => true,
+            Some(Ok(frame)) => frame.is_trailers() || this.inner.is_end_stream(),
+            Some(Err(_)) => false,
+        };
+        if completed {
+            if let Some(guard) = this.guard.take() {
+                guard.disarm();
+            }
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
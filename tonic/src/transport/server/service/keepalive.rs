@@ -0,0 +1,653 @@
+use std::{
+    collections::HashSet,
+    io,
+    io::IoSlice,
+    pin::Pin,
+    task::{ready, Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Instant,
+};
+use tracing::debug;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_HEADER_LEN: usize = 9;
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+
+const ENHANCE_YOUR_CALM: u32 = 0xb;
+const GOAWAY_LEN: usize = FRAME_HEADER_LEN + 8;
+
+// The number of pings a client may send too early before the connection is closed, as in
+// grpc-go.
+const MAX_PING_STRIKES: u32 = 2;
+// The minimum ping interval while there are no streams and pings without streams aren't
+// permitted.
+const MIN_PING_INTERVAL_WITHOUT_STREAM: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// How often clients are allowed to ping a server.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KeepalivePolicy {
+    pub(crate) min_ping_interval: Duration,
+    pub(crate) permit_without_stream: bool,
+}
+
+/// Enforces a [`KeepalivePolicy`] on the HTTP/2 connection over `IO`.
+///
+/// The frames exchanged are followed to count the client pings arriving sooner than the policy
+/// allows, and the streams that are open. Once a client pings too often, a GOAWAY frame with
+/// `ENHANCE_YOUR_CALM` is written between the frames of the server and the connection is
+/// closed, ending reads and failing writes. Connections that do not start with the HTTP/2
+/// preface are left alone.
+pub(crate) struct EnforceKeepalive<IO> {
+    inner: IO,
+    enforcement: Option<Enforcement>,
+}
+
+impl<IO> EnforceKeepalive<IO> {
+    /// Wraps `inner`, enforcing `policy` if there is one.
+    pub(crate) fn new(inner: IO, policy: Option<KeepalivePolicy>) -> Self {
+        Self {
+            inner,
+            enforcement: policy.map(Enforcement::new),
+        }
+    }
+}
+
+struct Enforcement {
+    policy: KeepalivePolicy,
+    /// The bytes of the preface the client has yet to send.
+    preface: &'static [u8],
+    read: FrameParser,
+    write: FrameParser,
+    open_streams: HashSet<u32>,
+    last_stream_id: u32,
+    last_ping: Option<Instant>,
+    strikes: u32,
+    reset_strikes: bool,
+    closing: Closing,
+}
+
+enum Closing {
+    No,
+    /// The client pinged too often, the GOAWAY frame is written from `written` on once the
+    /// server is between frames.
+    GoAway {
+        frame: [u8; GOAWAY_LEN],
+        written: usize,
+        /// The task reading the connection, waiting for the GOAWAY frame to be written.
+        reader: Option<Waker>,
+    },
+    Closed,
+}
+
+impl Enforcement {
+    fn new(policy: KeepalivePolicy) -> Self {
+        Self {
+            policy,
+            preface: PREFACE,
+            read: FrameParser::default(),
+            write: FrameParser::default(),
+            open_streams: HashSet::new(),
+            last_stream_id: 0,
+            last_ping: None,
+            strikes: 0,
+            reset_strikes: false,
+            closing: Closing::No,
+        }
+    }
+
+    fn on_read(&mut self, mut data: &[u8]) {
+        if !self.preface.is_empty() {
+            let n = self.preface.len().min(data.len());
+            if data[..n] != self.preface[..n] {
+                // Not an HTTP/2 connection, there is nothing to enforce.
+                self.preface = &[];
+                self.read.disabled = true;
+                self.write.disabled = true;
+                return;
+            }
+            self.preface = &self.preface[n..];
+            data = &data[n..];
+        }
+
+        let Self {
+            read,
+            policy,
+            open_streams,
+            last_stream_id,
+            last_ping,
+            strikes,
+            reset_strikes,
+            ..
+        } = self;
+
+        read.feed(data, |frame| match frame.kind {
+            HEADERS if frame.stream_id > *last_stream_id => {
+                *last_stream_id = frame.stream_id;
+                open_streams.insert(frame.stream_id);
+            }
+            RST_STREAM => {
+                open_streams.remove(&frame.stream_id);
+            }
+            PING if frame.flags & ACK == 0 => {
+                if std::mem::take(reset_strikes) {
+                    *strikes = 0;
+                    *last_ping = None;
+                }
+
+                let min_interval = if open_streams.is_empty() && !policy.permit_without_stream {
+                    MIN_PING_INTERVAL_WITHOUT_STREAM
+                } else {
+                    policy.min_ping_interval
+                };
+
+                let now = Instant::now();
+                if last_ping.is_some_and(|last| now < last + min_interval) {
+                    *strikes += 1;
+                }
+                *last_ping = Some(now);
+            }
+            _ => {}
+        });
+
+        if self.strikes > MAX_PING_STRIKES && matches!(self.closing, Closing::No) {
+            debug!("client pinged too often, closing connection");
+            self.closing = Closing::GoAway {
+                frame: goaway(self.last_stream_id, ENHANCE_YOUR_CALM),
+                written: 0,
+                reader: None,
+            };
+        }
+    }
+
+    fn on_write(&mut self, data: &[u8]) {
+        let Self {
+            write,
+            open_streams,
+            reset_strikes,
+            ..
+        } = self;
+
+        write.feed(data, |frame| match frame.kind {
+            DATA | HEADERS => {
+                // Like grpc-go, pings are welcome again once the server has sent something.
+                *reset_strikes = true;
+                if frame.flags & END_STREAM != 0 {
+                    open_streams.remove(&frame.stream_id);
+                }
+            }
+            RST_STREAM => {
+                open_streams.remove(&frame.stream_id);
+            }
+            _ => {}
+        });
+    }
+}
+
+impl<IO> EnforceKeepalive<IO>
+where
+    IO: AsyncWrite + Unpin,
+{
+    /// Writes the pending GOAWAY frame if the server is between frames, then shuts the
+    /// connection down.
+    ///
+    /// Ready with whether the connection is closed, or with `false` if there is nothing to write
+    /// yet.
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        let Some(enforcement) = &mut self.enforcement else {
+            return Poll::Ready(Ok(false));
+        };
+
+        let Closing::GoAway {
+            frame,
+            written,
+            reader,
+        } = &mut enforcement.closing
+        else {
+            return Poll::Ready(Ok(matches!(enforcement.closing, Closing::Closed)));
+        };
+
+        // The server must have sent its SETTINGS frame first, and not be halfway through a frame.
+        if !enforcement.write.between_frames() {
+            return Poll::Ready(Ok(false));
+        }
+
+        while *written < frame.len() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &frame[*written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            *written += n;
+        }
+        ready!(Pin::new(&mut self.inner).poll_flush(cx))?;
+        ready!(Pin::new(&mut self.inner).poll_shutdown(cx))?;
+
+        if let Some(reader) = reader.take() {
+            reader.wake();
+        }
+        enforcement.closing = Closing::Closed;
+        Poll::Ready(Ok(true))
+    }
+
+    /// The most the server may write now, so that the GOAWAY frame can follow a whole frame.
+    fn max_write_len(&self) -> usize {
+        match &self.enforcement {
+            Some(Enforcement {
+                closing: Closing::GoAway { .. },
+                write,
+                ..
+            }) => write.frame_remaining(),
+            _ => usize::MAX,
+        }
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "connection closed after too many pings",
+    )
+}
+
+impl<IO> AsyncRead for EnforceKeepalive<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if ready!(this.poll_close(cx))? {
+            // Nothing more is read from a closed connection.
+            return Poll::Ready(Ok(()));
+        }
+        if let Some(Enforcement {
+            closing: Closing::GoAway { reader, .. },
+            ..
+        }) = &mut this.enforcement
+        {
+            // Wait for the server to finish writing its frame, followed by the GOAWAY frame.
+            *reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        if let Some(enforcement) = &mut this.enforcement {
+            enforcement.on_read(&buf.filled()[filled..]);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<IO> AsyncWrite for EnforceKeepalive<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if ready!(this.poll_close(cx))? {
+            return Poll::Ready(Err(closed()));
+        }
+
+        let buf = &buf[..buf.len().min(this.max_write_len())];
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+
+        if let Some(enforcement) = &mut this.enforcement {
+            enforcement.on_write(&buf[..n]);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.max_write_len() != usize::MAX {
+            // Writes are cut at the end of the current frame while closing.
+            let buf = bufs
+                .iter()
+                .find(|buf| !buf.is_empty())
+                .map_or(&[][..], |buf| buf);
+            return Pin::new(this).poll_write(cx, buf);
+        }
+        if ready!(this.poll_close(cx))? {
+            return Poll::Ready(Err(closed()));
+        }
+
+        let n = ready!(Pin::new(&mut this.inner).poll_write_vectored(cx, bufs))?;
+
+        if let Some(enforcement) = &mut this.enforcement {
+            let mut remaining = n;
+            for buf in bufs {
+                if remaining == 0 {
+                    break;
+                }
+                let len = buf.len().min(remaining);
+                enforcement.on_write(&buf[..len]);
+                remaining -= len;
+            }
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if ready!(this.poll_close(cx))? {
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if ready!(this.poll_close(cx))? {
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// A GOAWAY frame with the given last stream id and error code.
+fn goaway(last_stream_id: u32, error_code: u32) -> [u8; GOAWAY_LEN] {
+    let mut frame = [0; GOAWAY_LEN];
+    frame[..3].copy_from_slice(&8u32.to_be_bytes()[1..]);
+    frame[3] = GOAWAY;
+    frame[FRAME_HEADER_LEN..FRAME_HEADER_LEN + 4].copy_from_slice(&last_stream_id.to_be_bytes());
+    frame[FRAME_HEADER_LEN + 4..].copy_from_slice(&error_code.to_be_bytes());
+    frame
+}
+
+struct FrameHeader {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+}
+
+/// Splits one direction of an HTTP/2 connection into frames, only looking at their headers.
+#[derive(Default)]
+struct FrameParser {
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    payload_remaining: usize,
+    /// Whether a frame has been seen.
+    started: bool,
+    disabled: bool,
+}
+
+impl FrameParser {
+    /// Whether a frame has been seen and ended, without any of the next one.
+    fn between_frames(&self) -> bool {
+        self.started && self.header_len == 0 && self.payload_remaining == 0
+    }
+
+    /// The number of bytes left in the current frame, or in the header of the next one.
+    fn frame_remaining(&self) -> usize {
+        if self.payload_remaining > 0 {
+            self.payload_remaining
+        } else {
+            FRAME_HEADER_LEN - self.header_len
+        }
+    }
+
+    fn feed(&mut self, mut data: &[u8], mut on_frame: impl FnMut(FrameHeader)) {
+        if self.disabled {
+            return;
+        }
+
+        while !data.is_empty() {
+            if self.payload_remaining > 0 {
+                let n = self.payload_remaining.min(data.len());
+                self.payload_remaining -= n;
+                data = &data[n..];
+                continue;
+            }
+
+            let n = (FRAME_HEADER_LEN - self.header_len).min(data.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&data[..n]);
+            self.header_len += n;
+            data = &data[n..];
+
+            if self.header_len == FRAME_HEADER_LEN {
+                let header = &self.header;
+                self.header_len = 0;
+                self.started = true;
+                self.payload_remaining =
+                    u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+                on_frame(FrameHeader {
+                    kind: header[3],
+                    flags: header[4],
+                    stream_id: u32::from_be_bytes([header[5], header[6], header[7], header[8]])
+                        & 0x7fff_ffff,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    type Server = EnforceKeepalive<tokio::io::DuplexStream>;
+
+    const SETTINGS: u8 = 0x4;
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+        frame.extend_from_slice(&[kind, flags]);
+        frame.extend_from_slice(&stream_id.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn ping() -> Vec<u8> {
+        frame(PING, 0, 0, &[0; 8])
+    }
+
+    fn server(permit_without_stream: bool) -> (tokio::io::DuplexStream, Server) {
+        let (client, server) = tokio::io::duplex(1024);
+        let policy = KeepalivePolicy {
+            min_ping_interval: Duration::from_secs(10),
+            permit_without_stream,
+        };
+        (client, EnforceKeepalive::new(server, Some(policy)))
+    }
+
+    fn pinged_too_often(server: &Server) -> bool {
+        !matches!(server.enforcement.as_ref().unwrap().closing, Closing::No)
+    }
+
+    /// Sends `frames` from the client and checks that the server side reads them unchanged.
+    async fn send(server: &mut Server, client: &mut tokio::io::DuplexStream, frames: &[u8]) {
+        client.write_all(frames).await.unwrap();
+        let mut buf = vec![0; frames.len()];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, frames);
+    }
+
+    /// Pings the server until it has collected too many strikes, on a stream so that pings are
+    /// allowed at all.
+    async fn ping_flood(server: &mut Server, client: &mut tokio::io::DuplexStream) {
+        let mut frames = PREFACE.to_vec();
+        frames.extend(frame(HEADERS, 0, 1, &[]));
+        frames.extend(ping());
+        send(server, client, &frames).await;
+
+        for _ in 0..MAX_PING_STRIKES {
+            send(server, client, &ping()).await;
+        }
+        assert!(!pinged_too_often(server));
+
+        send(server, client, &ping()).await;
+        assert!(pinged_too_often(server));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closes_with_goaway_after_too_many_pings() {
+        let (mut client, mut server) = server(false);
+
+        let settings = frame(SETTINGS, 0, 0, &[]);
+        server.write_all(&settings).await.unwrap();
+        ping_flood(&mut server, &mut client).await;
+
+        // The server reads the end of the connection once the GOAWAY frame is written.
+        let mut buf = [0; 1];
+        assert_eq!(server.read(&mut buf).await.unwrap(), 0);
+        let err = server.write_all(&settings).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+        let mut written = Vec::new();
+        client.read_to_end(&mut written).await.unwrap();
+        let mut expected = settings;
+        expected.extend(goaway(1, ENHANCE_YOUR_CALM));
+        assert_eq!(written, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn goaway_follows_whole_frame() {
+        let (mut client, mut server) = server(false);
+
+        let settings = frame(SETTINGS, 0, 0, &[]);
+        let data = frame(DATA, 0, 1, b"data");
+        server.write_all(&settings).await.unwrap();
+        // The server is halfway through the header of a frame when the client pings too often.
+        server.write_all(&data[..4]).await.unwrap();
+        ping_flood(&mut server, &mut client).await;
+
+        // Only the rest of the frame is written, followed by the GOAWAY frame.
+        let mut rest = data[4..].to_vec();
+        rest.extend_from_slice(&data);
+        let mut written = 0;
+        while written < data.len() - 4 {
+            written += server.write(&rest[written..]).await.unwrap();
+        }
+        assert_eq!(written, data.len() - 4);
+        assert!(server.write(&rest[written..]).await.is_err());
+
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await.unwrap();
+        let mut expected = settings;
+        expected.extend(data);
+        expected.extend(goaway(1, ENHANCE_YOUR_CALM));
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn follows_split_reads() {
+        let (mut client, mut server) = server(false);
+
+        let mut frames = PREFACE.to_vec();
+        frames.extend(frame(HEADERS, 0, 1, &[]));
+        for _ in 0..=MAX_PING_STRIKES {
+            frames.extend(ping());
+        }
+        let last_ping = ping();
+
+        // Every byte is read on its own, splitting the preface and the frame headers.
+        for byte in frames.iter().chain(&last_ping[..FRAME_HEADER_LEN - 1]) {
+            send(&mut server, &mut client, &[*byte]).await;
+        }
+        assert!(!pinged_too_often(&server));
+
+        send(&mut server, &mut client, &last_ping[FRAME_HEADER_LEN - 1..]).await;
+        assert!(pinged_too_often(&server));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn allows_pings_at_min_interval() {
+        let (mut client, mut server) = server(true);
+
+        send(&mut server, &mut client, PREFACE).await;
+        for _ in 0..5 {
+            send(&mut server, &mut client, &ping()).await;
+            tokio::time::advance(Duration::from_secs(10)).await;
+        }
+        assert!(!pinged_too_often(&server));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pings_without_stream_need_permission() {
+        let (mut client, mut server) = server(false);
+
+        send(&mut server, &mut client, PREFACE).await;
+        for _ in 0..=MAX_PING_STRIKES {
+            send(&mut server, &mut client, &ping()).await;
+            tokio::time::advance(Duration::from_secs(10)).await;
+        }
+        assert!(!pinged_too_often(&server));
+
+        send(&mut server, &mut client, &ping()).await;
+        assert!(pinged_too_often(&server));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_frames_reset_strikes() {
+        let (mut client, mut server) = server(true);
+
+        send(&mut server, &mut client, PREFACE).await;
+        for _ in 0..10 {
+            send(&mut server, &mut client, &ping()).await;
+            server.write_all(&frame(DATA, 0, 1, b"data")).await.unwrap();
+        }
+        assert!(!pinged_too_often(&server));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn follows_vectored_writes() {
+        let (mut client, mut server) = server(true);
+
+        send(&mut server, &mut client, PREFACE).await;
+        let data = frame(DATA, 0, 1, b"data");
+        let (header, payload) = data.split_at(4);
+        for _ in 0..10 {
+            send(&mut server, &mut client, &ping()).await;
+
+            // A frame split over slices is written, and followed, as a whole.
+            let bufs = [IoSlice::new(header), IoSlice::new(payload)];
+            let n = server.write_vectored(&bufs).await.unwrap();
+            assert_eq!(n, data.len());
+        }
+        assert!(!pinged_too_often(&server));
+
+        let mut written = vec![0; 10 * data.len()];
+        client.read_exact(&mut written).await.unwrap();
+        assert_eq!(written, data.repeat(10));
+    }
+
+    #[tokio::test]
+    async fn ignores_http1() {
+        let (mut client, mut server) = server(false);
+
+        send(&mut server, &mut client, b"GET / HTTP/1.1\r\n\r\n").await;
+        for _ in 0..5 {
+            send(&mut server, &mut client, &ping()).await;
+        }
+        assert!(!pinged_too_often(&server));
+    }
+}
@@ -1,6 +1,12 @@
+mod cancellation;
+pub(crate) use self::cancellation::Cancellation;
+
 mod io;
 pub(crate) use self::io::ServerIo;
 
+mod keepalive;
+pub(crate) use self::keepalive::{EnforceKeepalive, KeepalivePolicy};
+
 mod recover_error;
 pub(crate) use self::recover_error::RecoverError;
 
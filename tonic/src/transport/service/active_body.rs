@@ -0,0 +1,70 @@
+use http_body::{Body, Frame, SizeHint};
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+/// A response body holding a guard until the body ends or is dropped, keeping its call counted
+/// as in flight for as long as the response is streamed.
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ActiveBody<B, G> {
+    #[pin]
+    inner: B,
+    guard: Option<G>,
+}
+
+impl<B, G> ActiveBody<B, G> {
+    pub(crate) fn new(inner: B, guard: G) -> Self {
+        Self {
+            inner,
+            guard: Some(guard),
+        }
+    }
+}
+
+impl<B: Body, G> Body for ActiveBody<B, G> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(this.inner.poll_frame(cx));
+        if !matches!(frame, Some(Ok(_))) {
+            this.guard.take();
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn releases_guard_at_end_of_stream() {
+        let guard = Arc::new(());
+        let mut body = ActiveBody::new(Full::new(Bytes::from_static(b"data")), guard.clone());
+
+        body.frame().await.unwrap().unwrap();
+        assert_eq!(Arc::strong_count(&guard), 2);
+
+        assert!(body.frame().await.is_none());
+        assert_eq!(Arc::strong_count(&guard), 1);
+    }
+}
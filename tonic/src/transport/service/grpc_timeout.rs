@@ -1,13 +1,14 @@
-use crate::{metadata::GRPC_TIMEOUT_HEADER, TimeoutExpired};
-use http::{HeaderMap, HeaderValue, Request};
+use crate::{request::try_parse_grpc_timeout, Deadline, Status, TimeoutExpired};
+use http::{Request, Response};
+use http_body::Frame;
 use pin_project::pin_project;
 use std::{
     future::Future,
     pin::Pin,
     task::{ready, Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::time::Sleep;
+use tokio::{task::futures::TaskLocalFuture, time::Sleep};
 use tower_service::Service;
 
 #[derive(Debug, Clone)]
@@ -25,12 +26,12 @@ impl<S> GrpcTimeout<S> {
     }
 }
 
-impl<S, ReqBody> Service<Request<ReqBody>> for GrpcTimeout<S>
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for GrpcTimeout<S>
 where
-    S: Service<Request<ReqBody>>,
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
     S::Error: Into<crate::BoxError>,
 {
-    type Response = S::Response;
+    type Response = Response<DeadlineBody<ResBody>>;
     type Error = crate::BoxError;
     type Future = ResponseFuture<S::Future>;
 
@@ -38,7 +39,7 @@ where
         self.inner.poll_ready(cx).map_err(Into::into)
     }
 
-    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
         let client_timeout = try_parse_grpc_timeout(req.headers()).unwrap_or_else(|e| {
             tracing::trace!("Error parsing `grpc-timeout` header {:?}", e);
             None
@@ -55,9 +56,15 @@ where
             }
         };
 
+        let deadline = timeout_duration.map(|timeout| Deadline::new(Instant::now() + timeout));
+        if let Some(deadline) = deadline {
+            req.extensions_mut().insert(deadline);
+        }
+
         ResponseFuture {
-            inner: self.inner.call(req),
-            sleep: timeout_duration.map(tokio::time::sleep),
+            inner: Deadline::scope(deadline, self.inner.call(req)),
+            sleep: deadline.map(|deadline| tokio::time::sleep_until(deadline.instant().into())),
+            deadline,
         }
     }
 }
@@ -65,23 +72,30 @@ where
 #[pin_project]
 pub(crate) struct ResponseFuture<F> {
     #[pin]
-    inner: F,
+    inner: TaskLocalFuture<Option<Deadline>, F>,
     #[pin]
     sleep: Option<Sleep>,
+    deadline: Option<Deadline>,
 }
 
-impl<F, Res, E> Future for ResponseFuture<F>
+impl<F, ResBody, E> Future for ResponseFuture<F>
 where
-    F: Future<Output = Result<Res, E>>,
+    F: Future<Output = Result<Response<ResBody>, E>>,
     E: Into<crate::BoxError>,
 {
-    type Output = Result<Res, crate::BoxError>;
+    type Output = Result<Response<DeadlineBody<ResBody>>, crate::BoxError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
         if let Poll::Ready(result) = this.inner.poll(cx) {
-            return Poll::Ready(result.map_err(Into::into));
+            // The deadline covers the whole response, including streamed messages
+            let deadline = *this.deadline;
+            return Poll::Ready(
+                result
+                    .map(|response| response.map(|body| DeadlineBody::new(body, deadline)))
+                    .map_err(Into::into),
+            );
         }
 
         if let Some(sleep) = this.sleep.as_pin_mut() {
@@ -93,64 +107,157 @@ where
     }
 }
 
-const SECONDS_IN_HOUR: u64 = 60 * 60;
-const SECONDS_IN_MINUTE: u64 = 60;
-
-/// Tries to parse the `grpc-timeout` header if it is present. If we fail to parse, returns
-/// the value we attempted to parse.
-///
-/// Follows the [gRPC over HTTP2 spec](https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md).
-fn try_parse_grpc_timeout(
-    headers: &HeaderMap<HeaderValue>,
-) -> Result<Option<Duration>, &HeaderValue> {
-    let Some(val) = headers.get(GRPC_TIMEOUT_HEADER) else {
-        return Ok(None);
-    };
-
-    let (timeout_value, timeout_unit) = val
-        .to_str()
-        .map_err(|_| val)
-        .and_then(|s| if s.is_empty() { Err(val) } else { Ok(s) })?
-        // `HeaderValue::to_str` only returns `Ok` if the header contains ASCII so this
-        // `split_at` will never panic from trying to split in the middle of a character.
-        // See https://docs.rs/http/0.2.4/http/header/struct.HeaderValue.html#method.to_str
-        //
-        // `len - 1` also wont panic since we just checked `s.is_empty`.
-        .split_at(val.len() - 1);
-
-    // gRPC spec specifies `TimeoutValue` will be at most 8 digits
-    // Caping this at 8 digits also prevents integer overflow from ever occurring
-    if timeout_value.len() > 8 {
-        return Err(val);
+/// Ends the response body with a `DeadlineExceeded` status once the deadline expires.
+#[pin_project]
+pub(crate) struct DeadlineBody<B> {
+    #[pin]
+    inner: Option<B>,
+    #[pin]
+    sleep: Option<Sleep>,
+}
+
+impl<B> DeadlineBody<B> {
+    fn new(inner: B, deadline: Option<Deadline>) -> Self {
+        Self {
+            inner: Some(inner),
+            sleep: deadline.map(|deadline| tokio::time::sleep_until(deadline.instant().into())),
+        }
     }
+}
 
-    let timeout_value: u64 = timeout_value.parse().map_err(|_| val)?;
-
-    let duration = match timeout_unit {
-        // Hours
-        "H" => Duration::from_secs(timeout_value * SECONDS_IN_HOUR),
-        // Minutes
-        "M" => Duration::from_secs(timeout_value * SECONDS_IN_MINUTE),
-        // Seconds
-        "S" => Duration::from_secs(timeout_value),
-        // Milliseconds
-        "m" => Duration::from_millis(timeout_value),
-        // Microseconds
-        "u" => Duration::from_micros(timeout_value),
-        // Nanoseconds
-        "n" => Duration::from_nanos(timeout_value),
-        _ => return Err(val),
-    };
-
-    Ok(Some(duration))
+impl<B> http_body::Body for DeadlineBody<B>
+where
+    B: http_body::Body,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        let Some(inner) = this.inner.as_mut().as_pin_mut() else {
+            return Poll::Ready(None);
+        };
+
+        // Check the deadline first, a body that always has data ready must still be cut off
+        if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+            if sleep.poll(cx).is_ready() {
+                this.sleep.set(None);
+                // Dropping the body cancels the rest of the stream
+                this.inner.set(None);
+
+                let status = Status::deadline_exceeded(TimeoutExpired(()).to_string());
+                let trailers = status
+                    .to_header_map()
+                    .expect("status message is a valid header value");
+                return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+            }
+        }
+
+        let frame = ready!(inner.poll_frame(cx));
+        if !matches!(&frame, Some(Ok(frame)) if frame.is_data()) {
+            // The body ended, the deadline no longer applies
+            this.sleep.set(None);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            Some(inner) => inner.is_end_stream(),
+            None => true,
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match &self.inner {
+            Some(inner) => inner.size_hint(),
+            None => http_body::SizeHint::with_exact(0),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::GRPC_TIMEOUT_HEADER;
+    use http::{HeaderMap, HeaderValue};
+    use http_body::Body as _;
+    use http_body_util::BodyExt as _;
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
 
+    #[tokio::test]
+    async fn sets_deadline_of_call() {
+        let svc = tower::service_fn(|request: Request<()>| async move {
+            let deadline = request.extensions().get::<Deadline>().copied();
+            assert_eq!(deadline, Deadline::current());
+            let mut response = Response::new(());
+            response.extensions_mut().insert(deadline);
+            Ok::<_, crate::BoxError>(response)
+        });
+
+        let mut request = Request::new(());
+        request
+            .headers_mut()
+            .insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_static("5S"));
+
+        let mut svc = GrpcTimeout::new(svc, Some(Duration::from_secs(1)));
+        let response = svc.call(request).await.unwrap();
+        let deadline = response.extensions().get::<Option<Deadline>>().unwrap();
+        assert!(deadline.unwrap().remaining() <= Duration::from_secs(1));
+        assert!(Deadline::current().is_none());
+
+        let mut svc = GrpcTimeout::new(svc.inner, None);
+        let response = svc.call(Request::new(())).await.unwrap();
+        let deadline = response.extensions().get::<Option<Deadline>>().unwrap();
+        assert!(deadline.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ends_body_when_deadline_expires() {
+        let svc = tower::service_fn(|_: Request<()>| async {
+            let body = http_body_util::StreamBody::new(tokio_stream::pending::<
+                Result<Frame<bytes::Bytes>, Status>,
+            >());
+            Ok::<_, crate::BoxError>(Response::new(body))
+        });
+
+        let mut svc = GrpcTimeout::new(svc, Some(Duration::from_secs(1)));
+        let mut body = std::pin::pin!(svc.call(Request::new(())).await.unwrap().into_body());
+
+        let frame = body.frame().await.unwrap().unwrap();
+        let status = Status::from_header_map(frame.trailers_ref().unwrap()).unwrap();
+        assert_eq!(status.code(), crate::Code::DeadlineExceeded);
+        assert!(body.is_end_stream());
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ends_body_always_ready_with_data_when_deadline_expires() {
+        let svc = tower::service_fn(|_: Request<()>| async {
+            let body =
+                http_body_util::StreamBody::new(tokio_stream::iter(std::iter::repeat_with(|| {
+                    Ok::<_, Status>(Frame::data(bytes::Bytes::from_static(b"data")))
+                })));
+            Ok::<_, crate::BoxError>(Response::new(body))
+        });
+
+        let mut svc = GrpcTimeout::new(svc, Some(Duration::from_secs(1)));
+        let mut body = std::pin::pin!(svc.call(Request::new(())).await.unwrap().into_body());
+
+        assert!(body.frame().await.unwrap().unwrap().is_data());
+        tokio::time::advance(Duration::from_secs(2)).await;
+
+        let frame = body.frame().await.unwrap().unwrap();
+        let status = Status::from_header_map(frame.trailers_ref().unwrap()).unwrap();
+        assert_eq!(status.code(), crate::Code::DeadlineExceeded);
+        assert!(body.frame().await.is_none());
+    }
+
     // Helper function to reduce the boiler plate of our test cases
     fn setup_map_try_parse(val: Option<&str>) -> Result<Option<Duration>, HeaderValue> {
         let mut hm = HeaderMap::new();
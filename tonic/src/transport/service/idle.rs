@@ -0,0 +1,130 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::Notify, time::Instant};
+
+/// Tracks the calls in flight on a connection, to close it once it had no calls for a while.
+#[derive(Debug, Clone)]
+pub(crate) struct IdleTimeout {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    timeout: Duration,
+    state: Mutex<State>,
+    changed: Notify,
+}
+
+#[derive(Debug)]
+struct State {
+    active: usize,
+    idle_since: Instant,
+    expired: bool,
+}
+
+impl IdleTimeout {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                timeout,
+                state: Mutex::new(State {
+                    active: 0,
+                    idle_since: Instant::now(),
+                    expired: false,
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Returns `true` once the connection has been idle for the timeout, no call may start on it
+    /// anymore.
+    #[cfg(feature = "channel")]
+    pub(crate) fn is_expired(&self) -> bool {
+        self.shared.state.lock().unwrap().expired
+    }
+
+    /// Marks a call as in flight until the returned guard is dropped.
+    pub(crate) fn start(&self) -> Active {
+        self.shared.state.lock().unwrap().active += 1;
+        self.shared.changed.notify_waiters();
+
+        Active {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Resolves once the connection has had no call in flight for the timeout.
+    pub(crate) async fn expired(&self) {
+        loop {
+            // Created before the state is checked so that no change is missed.
+            let changed = self.shared.changed.notified();
+
+            let deadline = {
+                let mut state = self.shared.state.lock().unwrap();
+                if state.active > 0 {
+                    None
+                } else {
+                    let deadline = state.idle_since + self.shared.timeout;
+                    if Instant::now() >= deadline {
+                        state.expired = true;
+                        return;
+                    }
+                    Some(deadline)
+                }
+            };
+
+            match deadline {
+                Some(deadline) => {
+                    let _ = tokio::time::timeout_at(deadline, changed).await;
+                }
+                None => changed.await,
+            }
+        }
+    }
+}
+
+/// A call in flight on a connection.
+#[derive(Debug)]
+pub(crate) struct Active {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.active -= 1;
+        if state.active == 0 {
+            state.idle_since = Instant::now();
+            drop(state);
+            self.shared.changed.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn expires_after_last_call() {
+        let idle = IdleTimeout::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        let active = idle.start();
+        let expired = tokio::spawn({
+            let idle = idle.clone();
+            async move { idle.expired().await }
+        });
+
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert!(!expired.is_finished());
+
+        drop(active);
+        expired.await.unwrap();
+
+        assert_eq!(Instant::now() - start, Duration::from_secs(40));
+    }
+}
@@ -1,5 +1,9 @@
+pub(crate) mod active_body;
 pub(crate) mod grpc_timeout;
+pub(crate) mod idle;
 #[cfg(feature = "_tls-any")]
 pub(crate) mod tls;
 
+pub(crate) use self::active_body::ActiveBody;
 pub(crate) use self::grpc_timeout::GrpcTimeout;
+pub(crate) use self::idle::IdleTimeout;
@@ -26,3 +26,30 @@ pub(crate) mod base64 {
             .with_decode_padding_mode(DecodePaddingMode::Indifferent),
     );
 }
+
+/// Returns a pseudo-random number in the range `[0, 1)`.
+///
+/// This is only meant for jittering timers and is not suitable for anything security related.
+pub(crate) fn fast_random() -> f64 {
+    use std::{
+        cell::Cell,
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+
+    thread_local! {
+        static STATE: (RandomState, Cell<u64>) = (RandomState::new(), Cell::new(0));
+    }
+
+    let bits = STATE.with(|(seed, counter)| {
+        let n = counter.get().wrapping_add(1);
+        counter.set(n);
+
+        let mut hasher = seed.build_hasher();
+        hasher.write_u64(n);
+        hasher.finish()
+    });
+
+    // Use the upper 53 bits so every value is exactly representable as an `f64`.
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}